@@ -74,7 +74,7 @@ pub fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         // Add your Plugin
-        .add_plugins(BevyERMPlugin)
+        .add_plugins(BevyERMPlugin::default())
         // Register your types. This ensures, that we can reflect over these types
         // regardless whether a type has been used as a component or not.
         .register_type::<Player>()
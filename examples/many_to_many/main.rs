@@ -109,7 +109,7 @@ pub fn update(mut exit: EventWriter<AppExit>, frame_count: Res<FrameCount>) {
 pub fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(BevyERMPlugin)
+        .add_plugins(BevyERMPlugin::default())
         
         // Register your types. This ensures, that we can reflect over these types.
         .register_type::<SpawnPoint>()
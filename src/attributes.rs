@@ -1,5 +1,7 @@
 use bevy::reflect::Reflect;
 
+use crate::prelude::ReferentialAction;
+
 /// Marker for key coluimns
 #[derive(Reflect, Debug, Default)]
 pub struct Key;
@@ -12,6 +14,21 @@ pub struct NotNull;
 #[derive(Reflect, Debug, Default)]
 pub struct Unique;
 
+/// Marker for the version column of a table. Updates only succeed, if the row still
+/// holds the version of the value, and increment the version.
+#[derive(Reflect, Debug, Default)]
+pub struct Version;
+
+/// Marker for the column holding the time a row was inserted.
+/// The ERM writes the time of the `Clock` on insert, updates keep it.
+#[derive(Reflect, Debug, Default)]
+pub struct CreatedAt;
+
+/// Marker for the column holding the time a row was last written.
+/// The ERM writes the time of the `Clock` on insert and update.
+#[derive(Reflect, Debug, Default)]
+pub struct UpdatedAt;
+
 /// Rename a column.
 #[derive(Reflect, Debug, Default)]
 pub struct ColumnName {
@@ -42,6 +59,60 @@ impl Reference {
     }
 }
 
+/// Set the action taken on the referencing rows, once the referenced row is deleted.
+#[derive(Reflect, Debug, Default)]
+pub struct OnDelete {
+    pub action: ReferentialAction,
+}
+
+impl OnDelete {
+    pub fn new(action: ReferentialAction) -> Self {
+        OnDelete { action }
+    }
+}
+
+/// Set the action taken on the referencing rows, once the referenced key changes.
+#[derive(Reflect, Debug, Default)]
+pub struct OnUpdate {
+    pub action: ReferentialAction,
+}
+
+impl OnUpdate {
+    pub fn new(action: ReferentialAction) -> Self {
+        OnUpdate { action }
+    }
+}
+
+/// Cascade saving or deleting a value to the values its relation references.
+/// Saving writes the referenced values before the value referencing them,
+/// deleting removes them after the value referencing them.
+#[derive(Reflect, Debug, Default)]
+pub struct Cascade {
+    pub save: bool,
+    pub delete: bool,
+}
+
+impl Cascade {
+    pub fn new(save: bool, delete: bool) -> Self {
+        Cascade { save, delete }
+    }
+
+    /// Cascade saving and deleting.
+    pub fn all() -> Self {
+        Cascade::new(true, true)
+    }
+
+    /// Cascade saving only.
+    pub fn save() -> Self {
+        Cascade::new(true, false)
+    }
+
+    /// Cascade deleting only.
+    pub fn delete() -> Self {
+        Cascade::new(false, true)
+    }
+}
+
 /// Add max length to a string field.
 #[derive(Reflect, Debug, Default)]
 pub struct MaxLength {
@@ -52,4 +123,27 @@ impl MaxLength {
     pub fn new(s: usize) -> Self {
         MaxLength { length: s }
     }
-}
\ No newline at end of file
+}
+/// Store a nested struct as framed blob. Framed blobs tag each field by name,
+/// so fields can be added or removed without breaking stored values.
+#[derive(Reflect, Debug, Default)]
+pub struct Framed;
+
+/// Store a vector or quaternion as one column per component instead of a blob.
+/// A field 'location: Vec3' is mapped to the columns location_x, location_y and location_z.
+#[derive(Reflect, Debug, Default)]
+pub struct Decompose;
+
+/// Store the field as json. Useful for nested structs, collections and enums
+/// that should stay readable in the database.
+#[derive(Reflect, Debug, Default)]
+pub struct Json;
+
+/// Store the elements of a collection in a child table, one row per element.
+#[derive(Reflect, Debug, Default)]
+pub struct ElementTable;
+
+/// Store a list or array of numbers or math types as a single blob,
+/// with the elements packed back to back.
+#[derive(Reflect, Debug, Default)]
+pub struct PackedBlob;
@@ -0,0 +1,750 @@
+use std::sync::Arc;
+
+use bevy::prelude::Resource;
+
+use crate::prelude::{
+    ChildTable, ErmError, ErmResult, ErmTypesRegistry, Filter, Row, Select, SqlDialect, SqlValue,
+    TableDefinition,
+};
+
+/// A storage engine for the tables of the ERM-Registry. Rows are passed as column/value
+/// pairs, using the sql names of the columns. The ORM is written against this trait only,
+/// so backends can be swapped using the configuration of the plugin.
+pub trait ErmBackend: Send + Sync {
+    /// The dialect of the statements this backend understands.
+    fn dialect(&self) -> SqlDialect;
+
+    /// Create the tables, including child tables, for all types registered with the ERM-Registry.
+    /// Existing tables are kept.
+    fn create_schema(&self, registry: &ErmTypesRegistry) -> ErmResult<()>;
+
+    /// Insert a row into the table.
+    fn insert(&self, table: &TableDefinition, row: &Row) -> ErmResult<()>;
+
+    /// Insert the rows into the table in a single transaction. Either all rows are inserted
+    /// or none. Backends understanding sql insert as many rows per statement as the
+    /// parameter limit of their dialect allows.
+    fn insert_many(&self, table: &TableDefinition, rows: &[Row]) -> ErmResult<()> {
+        self.begin()?;
+        let result = rows.iter().try_for_each(|x| self.insert(table, x));
+        finish(self, result)
+    }
+
+    /// Insert the rows into the table in a single transaction. Rows holding the values of an
+    /// existing row in the conflict columns, which are key or unique columns, update the
+    /// existing row instead. Updates keep the key and the time of creation of the row,
+    /// see `upsert_columns`. Tables with a version column are rejected, see `check_upsert`.
+    fn upsert_many(
+        &self,
+        table: &TableDefinition,
+        rows: &[Row],
+        conflict_columns: &[&str],
+    ) -> ErmResult<()> {
+        check_upsert(table)?;
+        self.begin()?;
+        let result = rows.iter().try_for_each(|row| {
+            let filter = Filter::And(
+                conflict_columns
+                    .iter()
+                    .map(|x| Filter::Eq(x.to_string(), row.get(x).cloned().unwrap_or_default()))
+                    .collect(),
+            );
+
+            let Some(existing) = self.select(table, &filter)?.into_iter().next() else {
+                return self.insert(table, row);
+            };
+
+            let (mut changed, _) = split_keys(table, &existing)?;
+            for column in upsert_columns(table, row, conflict_columns) {
+                changed.push(column, row.get(column).cloned().unwrap_or_default());
+            }
+            self.update(table, &changed).map(|_| ())
+        });
+        finish(self, result)
+    }
+
+    /// Update the row with the key held by the given row. Columns missing from the
+    /// row keep their value. Returns false, if there is no such row.
+    fn update(&self, table: &TableDefinition, row: &Row) -> ErmResult<bool> {
+        self.update_if(table, row, &Filter::All)
+    }
+
+    /// Update the row with the key held by the given row, if it matches the filter as well.
+    /// Returns false, if there is no such row.
+    fn update_if(&self, table: &TableDefinition, row: &Row, filter: &Filter) -> ErmResult<bool>;
+
+    /// Delete the row with the key held by the given row. Returns false, if there is no such row.
+    fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool>;
+
+    /// Select the rows matching the filter of the select, ordered and limited as requested.
+    /// The values are converted to the sql types of the columns.
+    fn query(&self, table: &TableDefinition, select: &Select) -> ErmResult<Vec<Row>>;
+
+    /// Select all rows matching the filter.
+    fn select(&self, table: &TableDefinition, filter: &Filter) -> ErmResult<Vec<Row>> {
+        self.query(table, &Select::new(filter.clone()))
+    }
+
+    /// Select the row with the key held by the given row.
+    fn select_by_key(&self, table: &TableDefinition, key: &Row) -> ErmResult<Option<Row>> {
+        Ok(self
+            .select(table, &Filter::by_key(table, key)?)?
+            .into_iter()
+            .next())
+    }
+
+    /// Run a statement written in the dialect of this backend and return the rows of its result.
+    /// The values are returned as the database stores them. Backends not understanding sql
+    /// return `ErmError::Unsupported`.
+    fn query_sql(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<Row>>;
+
+    /// Replace the elements of a child table belonging to the owner with the given rows.
+    /// The rows hold all columns of the child table but the owner column.
+    fn replace_children(&self, child: &ChildTable, owner: &SqlValue, rows: &[Row])
+        -> ErmResult<()>;
+
+    /// Select the elements of a child table belonging to any of the owners.
+    /// Rows include the owner column, elements of lists are ordered by their position.
+    fn select_children(&self, child: &ChildTable, owners: &[SqlValue]) -> ErmResult<Vec<Row>>;
+
+    /// Start a transaction. Transactions can be nested, committing or rolling back
+    /// only affects the innermost transaction.
+    fn begin(&self) -> ErmResult<()>;
+
+    /// Commit the innermost transaction.
+    fn commit(&self) -> ErmResult<()>;
+
+    /// Roll back the innermost transaction.
+    fn rollback(&self) -> ErmResult<()>;
+}
+
+impl dyn ErmBackend + '_ {
+    /// Run the closure in a transaction. The transaction is committed, if the closure
+    /// succeeds, and rolled back otherwise.
+    pub fn transaction<R>(&self, f: impl FnOnce(&dyn ErmBackend) -> ErmResult<R>) -> ErmResult<R> {
+        self.begin()?;
+        let result = f(self);
+        finish(self, result)
+    }
+}
+
+/// End the transaction begun for the result. It is committed, if the result is ok,
+/// and rolled back otherwise.
+fn finish<B: ErmBackend + ?Sized, R>(db: &B, result: ErmResult<R>) -> ErmResult<R> {
+    match result {
+        Ok(result) => {
+            db.commit()?;
+            Ok(result)
+        }
+        Err(e) => {
+            db.rollback()?;
+            Err(e)
+        }
+    }
+}
+
+/// Fail for tables with a version column. An upsert would overwrite rows regardless
+/// of the version they hold, so versioned rows are updated one by one instead.
+pub(crate) fn check_upsert(table: &TableDefinition) -> ErmResult<()> {
+    match table.version_column() {
+        Some(column) => Err(ErmError::Unsupported(format!(
+            "Table {} has the version column {}, its rows cannot be upserted in bulk",
+            table.sql_name, column.sql_name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Return the columns of the row an upsert writes to a conflicting row: all columns,
+/// but the conflict and key columns, the time of creation and the deletion mark.
+pub(crate) fn upsert_columns<'r>(
+    table: &TableDefinition,
+    row: &'r Row,
+    conflict_columns: &[&str],
+) -> Vec<&'r str> {
+    row.names()
+        .into_iter()
+        .filter(|x| !conflict_columns.contains(x))
+        .filter(|x| {
+            table.get(x).is_none_or(|column| {
+                !column.is_key() && !column.is_created_at() && !table.is_soft_delete(column)
+            })
+        })
+        .collect()
+}
+
+/// Creates the backend, when the plugin is built.
+pub type BackendFactory = Arc<dyn Fn() -> ErmResult<Box<dyn ErmBackend>> + Send + Sync>;
+
+/// The database used by the ERM. The backend is configured using the plugin.
+#[derive(Resource)]
+pub struct ErmDatabase {
+    backend: Box<dyn ErmBackend>,
+}
+
+impl ErmDatabase {
+    pub fn new(backend: Box<dyn ErmBackend>) -> Self {
+        ErmDatabase { backend }
+    }
+
+    pub fn backend(&self) -> &dyn ErmBackend {
+        self.backend.as_ref()
+    }
+}
+
+impl std::ops::Deref for ErmDatabase {
+    type Target = dyn ErmBackend;
+
+    fn deref(&self) -> &Self::Target {
+        self.backend.as_ref()
+    }
+}
+
+/// Split a row into its key columns, in the order of the keys, and all other columns.
+pub(crate) fn split_keys(table: &TableDefinition, row: &Row) -> ErmResult<(Row, Row)> {
+    let keys = table.key_columns();
+    if keys.is_empty() {
+        return Err(ErmError::InvalidMapping(format!(
+            "Table {} has no key",
+            table.sql_name
+        )));
+    }
+
+    let mut key_row = Row::new();
+    for key in keys.iter() {
+        let Some(value) = row.get(&key.sql_name) else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Row for table {} has no value for key {}",
+                table.sql_name, key.sql_name
+            )));
+        };
+
+        key_row.push(&key.sql_name, value.clone());
+    }
+
+    let mut values = Row::new();
+    for (name, value) in row.columns.iter() {
+        if key_row.get(name).is_none() {
+            values.push(name, value.clone());
+        }
+    }
+
+    Ok((key_row, values))
+}
+
+/// Tests every backend has to pass. Each backend runs them from its own test module.
+#[cfg(test)]
+pub(crate) mod conformance {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Players"))]
+    pub struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@MaxLength::new(8), @Unique)]
+        pub name: String,
+        pub score: Option<f32>,
+        pub active: bool,
+        pub tags: Vec<String>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Zombies"))]
+    pub struct Zombie {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Reference::new("Player", "id"))]
+        pub target: Option<Player>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Minions"))]
+    pub struct Minion {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@OnDelete::new(ReferentialAction::Cascade))]
+        pub master: Option<Player>,
+        #[reflect(
+            @Reference::new("Player", "name"),
+            @OnDelete::new(ReferentialAction::SetNull),
+            @OnUpdate::new(ReferentialAction::Cascade)
+        )]
+        pub rival: Option<Player>,
+    }
+
+    /// Register the test types and create their tables.
+    pub fn prepare(backend: &dyn ErmBackend) -> ErmTypesRegistry {
+        let app_registry = AppTypeRegistry::default();
+        app_registry.write().register::<Player>();
+        app_registry.write().register::<Zombie>();
+        app_registry.write().register::<Minion>();
+
+        let mut registry = ErmTypesRegistry::default();
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        assert!(registry.register_type::<Zombie>(&app_registry).is_some());
+        assert!(registry.register_type::<Minion>(&app_registry).is_some());
+
+        backend.create_schema(&registry).unwrap();
+
+        // Creating the tables twice keeps the existing tables.
+        backend.create_schema(&registry).unwrap();
+
+        registry
+    }
+
+    pub fn player(id: i64, name: &str) -> Row {
+        Row::new()
+            .with("id", id)
+            .with("name", name)
+            .with("score", Some(1.5))
+            .with("active", true)
+    }
+
+    pub fn crud(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+        let key = Row::new().with("id", 1i64);
+
+        backend.insert(table, &player(1, "Ann")).unwrap();
+        backend.insert(table, &player(2, "Bob")).unwrap();
+
+        let row = backend.select_by_key(table, &key).unwrap().unwrap();
+        assert_eq!(row.names(), vec!["id", "name", "score", "active"]);
+        assert_eq!(row.get("name"), Some(&SqlValue::from("Ann")));
+        assert_eq!(row.get("score"), Some(&SqlValue::Float(1.5)));
+        assert_eq!(row.get("active"), Some(&SqlValue::Bool(true)));
+
+        let rows = backend
+            .select(table, &Filter::Eq("name".to_owned(), "Bob".into()))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("id"), Some(&SqlValue::Integer(2)));
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 2);
+
+        // Columns missing from the row keep their value.
+        let changed = Row::new()
+            .with("id", 1i64)
+            .with("score", None::<f32>)
+            .with("active", false);
+        assert!(backend.update(table, &changed).unwrap());
+
+        let row = backend.select_by_key(table, &key).unwrap().unwrap();
+        assert_eq!(row.get("name"), Some(&SqlValue::from("Ann")));
+        assert_eq!(row.get("score"), Some(&SqlValue::Null));
+        assert_eq!(row.get("active"), Some(&SqlValue::Bool(false)));
+
+        assert!(!backend
+            .update(table, &Row::new().with("id", 3i64).with("active", true))
+            .unwrap());
+
+        assert!(backend.delete(table, &key).unwrap());
+        assert!(!backend.delete(table, &key).unwrap());
+        assert!(backend.select_by_key(table, &key).unwrap().is_none());
+        assert!(backend.update(table, &Row::new()).is_err());
+    }
+
+    pub fn bulk_writes(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+        let name = |id: i64| {
+            backend
+                .select_by_key(table, &Row::new().with("id", id))
+                .unwrap()
+                .and_then(|x| x.get("name").cloned())
+        };
+
+        let rows: Vec<Row> = (1..=3).map(|i| player(i, &format!("P{}", i))).collect();
+        backend.insert_many(table, &rows).unwrap();
+        backend.insert_many(table, &[]).unwrap();
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 3);
+
+        // Failing rows roll back the rows inserted before.
+        let rows = [player(4, "P4"), player(1, "P1")];
+        assert!(backend.insert_many(table, &rows).is_err());
+        assert_eq!(name(4), None);
+
+        // Conflicting keys update the row, others are inserted.
+        let rows = [player(1, "Ann"), player(4, "Dan")];
+        backend.upsert_many(table, &rows, &["id"]).unwrap();
+        assert_eq!(name(1), Some("Ann".into()));
+        assert_eq!(name(4), Some("Dan".into()));
+
+        // Conflicting unique values update the row, keeping its key.
+        let row = player(5, "Dan").with("active", false);
+        backend.upsert_many(table, &[row], &["name"]).unwrap();
+        assert_eq!(name(5), None);
+        let rows = backend
+            .select(table, &Filter::Eq("name".to_owned(), "Dan".into()))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("id"), Some(&SqlValue::Integer(4)));
+        assert_eq!(rows[0].get("active"), Some(&SqlValue::Bool(false)));
+
+        backend.upsert_many(table, &[], &["id"]).unwrap();
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 4);
+    }
+
+    pub fn conditional_updates(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+        let key = Row::new().with("id", 1i64);
+        backend.insert(table, &player(1, "Ann")).unwrap();
+
+        // Rows not matching the filter are kept.
+        let changed = Row::new().with("id", 1i64).with("active", false);
+        let filter = Filter::Eq("name".to_owned(), "Bob".into());
+        assert!(!backend.update_if(table, &changed, &filter).unwrap());
+        let row = backend.select_by_key(table, &key).unwrap().unwrap();
+        assert_eq!(row.get("active"), Some(&SqlValue::Bool(true)));
+
+        let filter = Filter::Eq("name".to_owned(), "Ann".into());
+        assert!(backend.update_if(table, &changed, &filter).unwrap());
+        let row = backend.select_by_key(table, &key).unwrap().unwrap();
+        assert_eq!(row.get("active"), Some(&SqlValue::Bool(false)));
+
+        // Rows holding the key only are checked against the filter.
+        assert!(backend.update_if(table, &key, &filter).unwrap());
+        assert!(!backend
+            .update_if(table, &key, &Filter::Eq("active".to_owned(), true.into()))
+            .unwrap());
+        assert!(!backend
+            .update_if(table, &Row::new().with("id", 2i64), &Filter::All)
+            .unwrap());
+    }
+
+    pub fn constraints(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let players = registry.get_table_definition("Players").unwrap();
+        let zombies = registry.get_table_definition("Zombies").unwrap();
+
+        backend.insert(players, &player(1, "Ann")).unwrap();
+
+        // Key and unique columns.
+        assert!(backend.insert(players, &player(1, "Bob")).is_err());
+        assert!(backend.insert(players, &player(2, "Ann")).is_err());
+        backend.insert(players, &player(2, "Bob")).unwrap();
+        assert!(backend
+            .update(players, &Row::new().with("id", 2i64).with("name", "Ann"))
+            .is_err());
+
+        // Max length and not null.
+        assert!(backend.insert(players, &player(3, "Bartholomew")).is_err());
+        assert!(backend
+            .insert(players, &Row::new().with("id", 4i64).with("active", true))
+            .is_err());
+        assert!(backend
+            .update(
+                players,
+                &Row::new().with("id", 1i64).with("active", None::<bool>)
+            )
+            .is_err());
+
+        // References.
+        let zombie = Row::new().with("id", 1i64).with("target", 1i64);
+        assert!(backend.insert(zombies, &zombie).is_ok());
+        let zombie = Row::new().with("id", 2i64).with("target", 7i64);
+        assert!(matches!(
+            backend.insert(zombies, &zombie),
+            Err(ErmError::Database(_))
+        ));
+        let zombie = Row::new().with("id", 3i64).with("target", None::<i64>);
+        assert!(backend.insert(zombies, &zombie).is_ok());
+
+        // Referenced rows cannot be deleted.
+        assert!(backend
+            .delete(players, &Row::new().with("id", 1i64))
+            .is_err());
+        assert!(backend
+            .select_by_key(players, &Row::new().with("id", 1i64))
+            .unwrap()
+            .is_some());
+    }
+
+    pub fn referential_actions(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let players = registry.get_table_definition("Players").unwrap();
+        let minions = registry.get_table_definition("Minions").unwrap();
+
+        backend.insert(players, &player(1, "Ann")).unwrap();
+        backend.insert(players, &player(2, "Bob")).unwrap();
+        let minion = |id: i64, master: i64, rival: &str| {
+            Row::new()
+                .with("id", id)
+                .with("master", master)
+                .with("rival", rival)
+        };
+        backend.insert(minions, &minion(1, 1, "Bob")).unwrap();
+        backend.insert(minions, &minion(2, 2, "Ann")).unwrap();
+        let rival = |id: i64| {
+            backend
+                .select_by_key(minions, &Row::new().with("id", id))
+                .unwrap()
+                .map(|x| x.get("rival").cloned().unwrap())
+        };
+
+        // Renaming a player renames the rival of the minions.
+        let renamed = Row::new().with("id", 2i64).with("name", "Bobby");
+        assert!(backend.update(players, &renamed).unwrap());
+        assert_eq!(rival(1), Some(SqlValue::from("Bobby")));
+
+        // Deleting a player deletes its minions and clears their rival.
+        assert!(backend
+            .delete(players, &Row::new().with("id", 1i64))
+            .unwrap());
+        assert_eq!(rival(1), None);
+        assert_eq!(rival(2), Some(SqlValue::Null));
+
+        // Actions without cascade keep failing, as long as a row is referenced.
+        let zombies = registry.get_table_definition("Zombies").unwrap();
+        let zombie = Row::new().with("id", 1i64).with("target", 2i64);
+        backend.insert(zombies, &zombie).unwrap();
+        assert!(backend
+            .delete(players, &Row::new().with("id", 2i64))
+            .is_err());
+        assert_eq!(rival(2), Some(SqlValue::Null));
+        assert!(backend
+            .select_by_key(minions, &Row::new().with("id", 2i64))
+            .unwrap()
+            .is_some());
+    }
+
+    pub fn child_tables(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let players = registry.get_table_definition("Players").unwrap();
+        let tags = players.child_tables()[0];
+
+        backend.insert(players, &player(1, "Ann")).unwrap();
+        let elements = [
+            Row::new().with("ordinal", 1u32).with("value", "bold"),
+            Row::new().with("ordinal", 0u32).with("value", "brave"),
+        ];
+        backend
+            .replace_children(tags, &1i64.into(), &elements)
+            .unwrap();
+
+        let rows = backend.select_children(tags, &[1i64.into()]).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].names(), vec!["owner_id", "ordinal", "value"]);
+        assert_eq!(rows[0].get("value"), Some(&SqlValue::from("brave")));
+        assert_eq!(rows[0].get("ordinal"), Some(&SqlValue::Unsigned(0)));
+
+        // Elements of unknown owners are rejected and do not replace the existing elements.
+        assert!(backend
+            .replace_children(tags, &2i64.into(), &elements)
+            .is_err());
+        backend
+            .replace_children(tags, &1i64.into(), &elements[..1])
+            .unwrap();
+        assert_eq!(
+            backend.select_children(tags, &[1i64.into()]).unwrap().len(),
+            1
+        );
+
+        // Elements are deleted with their owner.
+        backend
+            .delete(players, &Row::new().with("id", 1i64))
+            .unwrap();
+        assert!(backend
+            .select_children(tags, &[1i64.into()])
+            .unwrap()
+            .is_empty());
+    }
+
+    pub fn transactions(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+
+        backend.insert(table, &player(1, "Ann")).unwrap();
+
+        // A failing transaction leaves no trace, even when nested.
+        let result = backend.transaction(|db| {
+            db.insert(table, &player(2, "Bob"))?;
+            db.transaction(|db| db.insert(table, &player(3, "Cid")))?;
+            db.insert(table, &player(4, "Ann"))
+        });
+        assert!(result.is_err());
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 1);
+
+        // An inner transaction can fail without affecting the outer one.
+        backend
+            .transaction(|db| {
+                db.insert(table, &player(2, "Bob"))?;
+                assert!(db
+                    .transaction(|db| db.insert(table, &player(3, "Ann")))
+                    .is_err());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 2);
+
+        assert!(backend.commit().is_err());
+        assert!(backend.rollback().is_err());
+    }
+
+    pub fn transactions_of_other_threads(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let players = registry.get_table_definition("Players").unwrap();
+        let (began, wait) = std::sync::mpsc::channel();
+
+        // The insert of the second thread waits for the transaction rolled back by the first.
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                backend.begin().unwrap();
+                backend.insert(players, &player(1, "Ann")).unwrap();
+                began.send(()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                backend.rollback().unwrap();
+            });
+
+            scope.spawn(move || {
+                wait.recv().unwrap();
+                backend.insert(players, &player(2, "Bob")).unwrap();
+                assert!(backend.commit().is_err());
+            });
+        });
+
+        let rows = backend.select(players, &Filter::All).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("id"), Some(&SqlValue::Integer(2)));
+    }
+
+    pub fn many_rows(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+        let name = |id: i64| format!("P{}", id);
+        let rows: Vec<Row> = (1..=300).map(|id| player(id, &name(id))).collect();
+        backend.insert_many(table, &rows).unwrap();
+
+        // A rolled back transaction restores the deleted and updated rows in their order.
+        let result = backend.transaction(|db| {
+            for id in 1..=250i64 {
+                db.delete(table, &Row::new().with("id", id))?;
+            }
+            db.update(table, &Row::new().with("id", 300i64).with("name", "Last"))?;
+            db.insert(table, &player(301, "P260"))
+        });
+        assert!(result.is_err());
+        let ids: Vec<SqlValue> = backend
+            .select(table, &Filter::All)
+            .unwrap()
+            .iter()
+            .map(|x| x.get("id").unwrap().clone())
+            .collect();
+        assert_eq!(ids, (1..=300i64).map(SqlValue::from).collect::<Vec<_>>());
+        let last = backend.select(table, &col("name").eq("P300")).unwrap();
+        assert_eq!(last.len(), 1);
+
+        // Rows stay unique and can be found by key and unique columns after most were deleted.
+        for id in 1..=250i64 {
+            assert!(backend.delete(table, &Row::new().with("id", id)).unwrap());
+        }
+        assert!(backend.insert(table, &player(260, "New")).is_err());
+        assert!(backend.insert(table, &player(301, "P260")).is_err());
+        backend.insert(table, &player(1, "P1")).unwrap();
+
+        let found = backend
+            .select(table, &col("name").is_in(["P1", "P260", "P2"]))
+            .unwrap();
+        let ids: Vec<_> = found.iter().map(|x| x.get("id").unwrap().clone()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&1i64.into()) && ids.contains(&260i64.into()));
+        assert_eq!(backend.select(table, &Filter::All).unwrap().len(), 51);
+    }
+
+    pub fn unknown_columns(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+
+        assert!(backend
+            .insert(table, &player(1, "Ann").with("level", 3))
+            .is_err());
+        assert!(backend
+            .select(table, &Filter::Eq("level".to_owned(), 3.into()))
+            .is_err());
+    }
+
+    pub fn queries(backend: &dyn ErmBackend) {
+        let registry = prepare(backend);
+        let table = registry.get_table_definition("Players").unwrap();
+
+        let players = [
+            player(1, "Ann"),
+            player(2, "Bob").with("score", 3.0),
+            player(3, "Cid").with("score", None::<f32>),
+            player(4, "Dan").with("active", false),
+            player(5, "anna").with("score", 0.5),
+        ];
+        for row in players.iter() {
+            backend.insert(table, row).unwrap();
+        }
+
+        let ids = |select: Select| -> Vec<i64> {
+            backend
+                .query(table, &select)
+                .unwrap()
+                .iter()
+                .map(|x| match x.get("id") {
+                    Some(SqlValue::Integer(id)) => *id,
+                    _ => panic!("missing id"),
+                })
+                .collect()
+        };
+        let ordered = |filter: Filter| Select {
+            filter,
+            order: vec![("id".to_owned(), Order::Ascending)],
+            ..Select::default()
+        };
+
+        assert_eq!(ids(ordered(col("id").gt(3))), vec![4, 5]);
+        assert_eq!(ids(ordered(col("id").between(2, 4))), vec![2, 3, 4]);
+        assert_eq!(ids(ordered(col("id").is_in([1, 5, 7]))), vec![1, 5]);
+        assert!(ids(ordered(col("id").is_in(Vec::<i64>::new()))).is_empty());
+        assert_eq!(ids(ordered(col("name").like("an%"))), vec![1, 5]);
+        assert_eq!(ids(ordered(col("name").like("_o_"))), vec![2]);
+        assert_eq!(ids(ordered(col("active").eq(false))), vec![4]);
+        assert_eq!(ids(ordered(col("score").is_null())), vec![3]);
+        assert_eq!(ids(ordered(col("score").is_not_null())).len(), 4);
+
+        // Comparisons with null are never true, not even when negated.
+        assert_eq!(ids(ordered(col("score").lt(2.0))), vec![1, 4, 5]);
+        assert_eq!(ids(ordered(!col("score").lt(2.0))), vec![2]);
+        assert!(ids(ordered(col("score").eq(None::<f32>))).is_empty());
+
+        // Numbers compare by their value, whatever their type.
+        assert_eq!(ids(ordered(col("id").gt(2.5))), vec![3, 4, 5]);
+        assert_eq!(ids(ordered(col("id").eq(2u64))), vec![2]);
+        assert_eq!(ids(ordered(col("id").is_in([1.0, 5.0]))), vec![1, 5]);
+        assert_eq!(ids(ordered(col("score").ge(3i64))), vec![2]);
+
+        let filter = col("name")
+            .eq("Ann")
+            .or(col("id").ge(4).and(col("active").eq(true)));
+        assert_eq!(ids(ordered(filter)), vec![1, 5]);
+
+        let select = Select {
+            order: vec![
+                ("active".to_owned(), Order::Ascending),
+                ("score".to_owned(), Order::Descending),
+            ],
+            ..Select::default()
+        };
+        assert_eq!(ids(select.clone()), vec![4, 2, 1, 5, 3]);
+
+        let limited = Select {
+            limit: Some(2),
+            offset: Some(1),
+            ..select.clone()
+        };
+        assert_eq!(ids(limited), vec![2, 1]);
+
+        let skipped = Select {
+            offset: Some(3),
+            ..select
+        };
+        assert_eq!(ids(skipped), vec![5, 3]);
+
+        assert!(backend.query(table, &ordered(col("level").eq(1))).is_err());
+    }
+}
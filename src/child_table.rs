@@ -0,0 +1,117 @@
+use std::fmt::Display;
+
+use crate::prelude::SqlType;
+
+/// A column of a child table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildColumn {
+    pub sql_name: String,
+    pub sql_type: SqlType,
+}
+
+impl ChildColumn {
+    pub fn new(sql_name: &str, sql_type: SqlType) -> Self {
+        ChildColumn {
+            sql_name: sql_name.to_owned(),
+            sql_type,
+        }
+    }
+
+    /// Returns true, if the column references another table.
+    /// The column then holds the key of the referenced row.
+    pub fn is_reference(&self) -> bool {
+        matches!(self.sql_type, SqlType::One2One(_, _))
+    }
+}
+
+/// A table holding the elements of a collection field. Every row references
+/// the owning row by its key, lists additionally store the position of each element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildTable {
+    pub sql_name: String,
+
+    /// Holds the key of the owning row.
+    pub owner_column: ChildColumn,
+
+    /// Holds the position of the element, if the order of the elements matters.
+    pub ordinal_column: Option<ChildColumn>,
+
+    /// Holds the key of a map entry.
+    pub key_column: Option<ChildColumn>,
+
+    pub value_column: ChildColumn,
+}
+
+impl ChildTable {
+    /// Create a child table for a list. The owner column is set, once the
+    /// key of the owning table is known.
+    pub fn list(value_type: SqlType) -> Self {
+        ChildTable {
+            sql_name: String::default(),
+            owner_column: ChildColumn::new("owner", SqlType::None),
+            ordinal_column: Some(ChildColumn::new(
+                "ordinal",
+                SqlType::UnsingedInteger(32, true),
+            )),
+            key_column: None,
+            value_column: ChildColumn::new("value", value_type),
+        }
+    }
+
+    /// Create a child table for a map, one row per entry.
+    pub fn map(key_type: SqlType, value_type: SqlType) -> Self {
+        ChildTable {
+            sql_name: String::default(),
+            owner_column: ChildColumn::new("owner", SqlType::None),
+            ordinal_column: None,
+            key_column: Some(ChildColumn::new("key", key_type)),
+            value_column: ChildColumn::new("value", value_type),
+        }
+    }
+
+    /// Create a child table for a set, one row per value.
+    pub fn set(value_type: SqlType) -> Self {
+        ChildTable {
+            sql_name: String::default(),
+            owner_column: ChildColumn::new("owner", SqlType::None),
+            ordinal_column: None,
+            key_column: None,
+            value_column: ChildColumn::new("value", value_type),
+        }
+    }
+
+    /// Return the columns identifying a row: the owner and either the ordinal,
+    /// the key of the entry or, for sets, the value itself.
+    pub fn primary_key(&self) -> Vec<&ChildColumn> {
+        let position = self
+            .ordinal_column
+            .as_ref()
+            .or(self.key_column.as_ref())
+            .unwrap_or(&self.value_column);
+
+        vec![&self.owner_column, position]
+    }
+
+    /// Return all columns of this table.
+    pub fn columns(&self) -> Vec<&ChildColumn> {
+        let mut result = vec![&self.owner_column];
+        result.extend(self.ordinal_column.iter());
+        result.extend(self.key_column.iter());
+        result.push(&self.value_column);
+
+        result
+    }
+}
+
+impl Display for ChildTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns = self
+            .columns()
+            .iter()
+            .map(|x| format!("{} {}", x.sql_name, x.sql_type))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "Child table: {} ({})", self.sql_name, columns)
+    }
+}
@@ -1,7 +1,29 @@
-use crate::prelude::{FieldConstraint, SqlType};
+use crate::prelude::{ChildTable, FieldConstraint, ReferentialAction, SqlType};
 use bevy::{log::info, reflect::Type};
 use std::fmt::Display;
 
+/// Describes how the value of a field is written to its column.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ColumnStorage {
+    /// The value is mapped to a single column of the matching sql type.
+    #[default]
+    Inline,
+
+    /// The value is a struct stored as framed blob (see `framed_blob`).
+    FramedBlob,
+
+    /// The value is a vector or quaternion stored as one column per component.
+    /// Holds the names of the components, the sql type of the column applies to each of them.
+    Decomposed(Vec<String>),
+
+    /// The elements of a collection are stored in a child table.
+    /// The sql type of the column describes the elements.
+    ChildTable(ChildTable),
+
+    /// A list or array stored as blob with its elements packed back to back.
+    PackedBlob,
+}
+
 #[derive(Debug)]
 pub struct ColumnDefinition {
     pub rust_name: String,
@@ -11,6 +33,7 @@ pub struct ColumnDefinition {
     pub order : usize,
 
     pub constraints: Vec<FieldConstraint>,
+    pub storage: ColumnStorage,
 
     pub ty: Type,
 }
@@ -21,6 +44,7 @@ impl ColumnDefinition {
             rust_name: name.to_owned(),
             sql_name: sql_name.to_owned(),
             constraints: Vec::new(),
+            storage: ColumnStorage::Inline,
 
             sql_type: SqlType::Blob(true),
             order,
@@ -66,11 +90,44 @@ impl ColumnDefinition {
                 }
             }
 
+            FieldConstraint::Cascade(_, _) => {
+                self.constraints
+                    .retain(|e| !matches!(e, FieldConstraint::Cascade(_, _)));
+            }
+
+            FieldConstraint::OnDelete(_) => {
+                self.constraints
+                    .retain(|e| !matches!(e, FieldConstraint::OnDelete(_)));
+            }
+
+            FieldConstraint::OnUpdate(_) => {
+                self.constraints
+                    .retain(|e| !matches!(e, FieldConstraint::OnUpdate(_)));
+            }
+
             FieldConstraint::Unique => {
                 if self.is_unique() {
                     return;
                 }
             }
+
+            FieldConstraint::Version => {
+                if self.is_version() {
+                    return;
+                }
+            }
+
+            FieldConstraint::CreatedAt => {
+                if self.is_created_at() {
+                    return;
+                }
+            }
+
+            FieldConstraint::UpdatedAt => {
+                if self.is_updated_at() {
+                    return;
+                }
+            }
         }
 
         self.constraints.push(constraint);
@@ -98,6 +155,7 @@ impl ColumnDefinition {
             SqlType::DateTime(b) => b,
             SqlType::Blob(b) => b,
             SqlType::Boolean(b) => b,
+            SqlType::Json(b) => b,
             SqlType::One2One(_, b) => b,        // True here means eager loading, which also means not null.
             SqlType::Many2Many(_, b) => b,      // True here means eager loading, which also means not null.
         }
@@ -111,6 +169,37 @@ impl ColumnDefinition {
         }
     }
 
+    /// Returns true, if this column holds a struct stored as framed blob.
+    pub fn is_framed(&self) -> bool {
+        self.storage == ColumnStorage::FramedBlob
+    }
+
+    /// Returns true, if this field is stored as one column per component.
+    pub fn is_decomposed(&self) -> bool {
+        matches!(self.storage, ColumnStorage::Decomposed(_))
+    }
+
+    /// Return the child table, if the elements of this field are stored in one.
+    pub fn child_table(&self) -> Option<&ChildTable> {
+        match &self.storage {
+            ColumnStorage::ChildTable(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Return the names and types of the sql columns this field is stored in.
+    /// This is a single column, unless the field is decomposed or stored in a child table.
+    pub fn sql_columns(&self) -> Vec<(String, SqlType)> {
+        match &self.storage {
+            ColumnStorage::Decomposed(components) => components
+                .iter()
+                .map(|c| (format!("{}_{}", self.sql_name, c), self.sql_type.clone()))
+                .collect(),
+            ColumnStorage::ChildTable(_) => Vec::new(),
+            _ => vec![(self.sql_name.clone(), self.sql_type.clone())],
+        }
+    }
+
     /// Returns true, if this column has a relation to another table.
     pub fn is_reference(&self) -> bool {
         self.constraints
@@ -120,6 +209,63 @@ impl ColumnDefinition {
             > 0
     }
 
+    /// Returns true, if saving a value also saves the values referenced by this column.
+    pub fn cascades_save(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|e| matches!(e, FieldConstraint::Cascade(true, _)))
+    }
+
+    /// Returns true, if deleting a value also deletes the values referenced by this column.
+    pub fn cascades_delete(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|e| matches!(e, FieldConstraint::Cascade(_, true)))
+    }
+
+    /// Returns true, if this column holds the version of the row.
+    pub fn is_version(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|e| matches!(e, FieldConstraint::Version))
+    }
+
+    /// Returns true, if this column holds the time the row was inserted.
+    pub fn is_created_at(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|e| matches!(e, FieldConstraint::CreatedAt))
+    }
+
+    /// Returns true, if this column holds the time the row was last written.
+    pub fn is_updated_at(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|e| matches!(e, FieldConstraint::UpdatedAt))
+    }
+
+    /// The action taken on this column, once the referenced row is deleted.
+    pub fn on_delete(&self) -> ReferentialAction {
+        self.constraints
+            .iter()
+            .find_map(|e| match e {
+                FieldConstraint::OnDelete(action) => Some(*action),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The action taken on this column, once the referenced key changes.
+    pub fn on_update(&self) -> ReferentialAction {
+        self.constraints
+            .iter()
+            .find_map(|e| match e {
+                FieldConstraint::OnUpdate(action) => Some(*action),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
     /// Returns true, if this column has a max length attribute.
     pub fn has_max_length(&self) -> bool {
         self.constraints
@@ -6,7 +6,44 @@ pub enum FieldConstraint {
     Key,
     MaxLength(usize),
     Unique,
+    Version,
+    CreatedAt,
+    UpdatedAt,
     Reference(String, String), // Names the table and the column to use as relation
+    Cascade(bool, bool),       // Whether saving and deleting cascade along a relation
+    OnDelete(ReferentialAction), // What happens to the row, if the referenced row is deleted
+    OnUpdate(ReferentialAction), // What happens to the row, if the referenced key changes
+}
+
+/// The action a database takes on the rows referencing a row, once the row
+/// is deleted or its referenced key changes.
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd)]
+pub enum ReferentialAction {
+    /// The change fails, if the row is still referenced. Databases may defer the check
+    /// to the end of the statement.
+    #[default]
+    NoAction,
+
+    /// The change fails immediately, if the row is still referenced.
+    Restrict,
+
+    /// The referencing rows are deleted or take the new key.
+    Cascade,
+
+    /// The referencing rows are set to null.
+    SetNull,
+}
+
+impl ReferentialAction {
+    /// The sql keywords of the action.
+    pub fn sql(&self) -> &'static str {
+        match self {
+            ReferentialAction::NoAction => "NO ACTION",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::SetNull => "SET NULL",
+        }
+    }
 }
 
 impl Display for FieldConstraint {
@@ -15,7 +52,15 @@ impl Display for FieldConstraint {
             FieldConstraint::Key => write!(f, "key"),
             FieldConstraint::MaxLength(max) => write!(f, "length max: {}", max),
             FieldConstraint::Unique => write!(f, "unique"),
+            FieldConstraint::Version => write!(f, "version"),
+            FieldConstraint::CreatedAt => write!(f, "created at"),
+            FieldConstraint::UpdatedAt => write!(f, "updated at"),
             FieldConstraint::Reference(t, c) => write!(f, "reference ({} - {})", t, c),
+            FieldConstraint::Cascade(save, delete) => {
+                write!(f, "cascade (save: {}, delete: {})", save, delete)
+            }
+            FieldConstraint::OnDelete(action) => write!(f, "on delete {}", action.sql()),
+            FieldConstraint::OnUpdate(action) => write!(f, "on update {}", action.sql()),
         }
     }
 }
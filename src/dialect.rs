@@ -0,0 +1,542 @@
+use crate::prelude::{
+    ChildColumn, ChildTable, ColumnDefinition, ErmResult, ErmTypesRegistry, ReferentialAction,
+    SqlType, TableDefinition,
+};
+
+/// The sql dialects the ERM can generate statements for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    #[default]
+    Sqlite,
+    PostgreSql,
+    MySql,
+}
+
+impl SqlDialect {
+    /// Return the name of the column type for the given sql type.
+    /// Relations do not have a column type of their own, so None is returned for them.
+    pub fn type_name(&self, sql_type: &SqlType) -> Option<String> {
+        let result = match self {
+            SqlDialect::Sqlite => match sql_type {
+                SqlType::None | SqlType::One2One(_, _) | SqlType::Many2Many(_, _) => return None,
+                SqlType::Integer(_, _) | SqlType::UnsingedInteger(_, _) => "INTEGER",
+                SqlType::Float(_, _) => "REAL",
+                SqlType::Text(_) => "TEXT",
+                SqlType::Date(_) => "DATE",
+                SqlType::Time(_) => "TIME",
+                SqlType::DateTime(_) => "INTEGER",
+                SqlType::Blob(_) => "BLOB",
+                SqlType::Boolean(_) => "BOOLEAN",
+                SqlType::Json(_) => "TEXT",
+            },
+
+            SqlDialect::PostgreSql => match sql_type {
+                SqlType::None | SqlType::One2One(_, _) | SqlType::Many2Many(_, _) => return None,
+                SqlType::Integer(bits, _) => match bits {
+                    0..=16 => "SMALLINT",
+                    17..=32 => "INTEGER",
+                    33..=64 => "BIGINT",
+                    _ => "NUMERIC(39)",
+                },
+                // Postgres has no unsigned types, use the next larger signed type.
+                SqlType::UnsingedInteger(bits, _) => match bits {
+                    0..=8 => "SMALLINT",
+                    9..=16 => "INTEGER",
+                    17..=32 => "BIGINT",
+                    33..=64 => "NUMERIC(20)",
+                    _ => "NUMERIC(39)",
+                },
+                SqlType::Float(bits, _) => match bits {
+                    0..=32 => "REAL",
+                    _ => "DOUBLE PRECISION",
+                },
+                SqlType::Text(_) => "TEXT",
+                SqlType::Date(_) => "DATE",
+                SqlType::Time(_) => "TIME",
+                // Points in time are bound as seconds since the unix epoch.
+                SqlType::DateTime(_) => "BIGINT",
+                SqlType::Blob(_) => "BYTEA",
+                SqlType::Boolean(_) => "BOOLEAN",
+                SqlType::Json(_) => "JSONB",
+            },
+
+            SqlDialect::MySql => match sql_type {
+                SqlType::None | SqlType::One2One(_, _) | SqlType::Many2Many(_, _) => return None,
+                SqlType::Integer(bits, _) => match bits {
+                    0..=8 => "TINYINT",
+                    9..=16 => "SMALLINT",
+                    17..=32 => "INT",
+                    33..=64 => "BIGINT",
+                    _ => "DECIMAL(39)",
+                },
+                SqlType::UnsingedInteger(bits, _) => match bits {
+                    0..=8 => "TINYINT UNSIGNED",
+                    9..=16 => "SMALLINT UNSIGNED",
+                    17..=32 => "INT UNSIGNED",
+                    33..=64 => "BIGINT UNSIGNED",
+                    _ => "DECIMAL(39)",
+                },
+                SqlType::Float(bits, _) => match bits {
+                    0..=32 => "FLOAT",
+                    _ => "DOUBLE",
+                },
+                SqlType::Text(_) => "TEXT",
+                SqlType::Date(_) => "DATE",
+                SqlType::Time(_) => "TIME",
+                SqlType::DateTime(_) => "BIGINT",
+                SqlType::Blob(_) => "LONGBLOB",
+                SqlType::Boolean(_) => "BOOLEAN",
+                SqlType::Json(_) => "JSON",
+            },
+        };
+
+        Some(result.to_owned())
+    }
+
+    /// Returns true, if nested structs, collections and enums should be stored as json
+    /// by default. Only dialects with a proper json column type do this.
+    pub fn json_by_default(&self) -> bool {
+        matches!(self, SqlDialect::PostgreSql)
+    }
+}
+
+impl SqlDialect {
+    /// Quote an identifier, like the name of a table or column.
+    pub fn quote(&self, identifier: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", identifier.replace('`', "``")),
+            _ => format!("\"{}\"", identifier.replace('"', "\"\"")),
+        }
+    }
+
+    /// Return the placeholder for the parameter with the given index. Indices start at 1.
+    pub fn placeholder(&self, index: usize) -> String {
+        match self {
+            SqlDialect::Sqlite => format!("?{}", index),
+            SqlDialect::PostgreSql => format!("${}", index),
+            SqlDialect::MySql => "?".to_owned(),
+        }
+    }
+
+    /// The maximum number of parameters a single statement can bind.
+    pub fn max_parameters(&self) -> usize {
+        match self {
+            SqlDialect::Sqlite => 32766,
+            SqlDialect::PostgreSql | SqlDialect::MySql => 65535,
+        }
+    }
+
+    /// Create the statements to create a table and its child tables.
+    pub fn create_table(
+        &self,
+        table: &TableDefinition,
+        registry: &ErmTypesRegistry,
+    ) -> ErmResult<Vec<String>> {
+        let mut definitions = Vec::new();
+        for column in table.columns() {
+            if let SqlType::One2One(_, _) = column.sql_type {
+                let (target, key) = registry.referenced_key_of(column)?;
+                definitions.push(format!(
+                    "{}{}",
+                    self.reference_definition(
+                        &column.sql_name,
+                        &key.sql_type,
+                        column.is_not_null(),
+                        target,
+                        key,
+                    ),
+                    Self::referential_actions(column)
+                ));
+
+                continue;
+            }
+
+            for (name, sql_type) in column.sql_columns() {
+                definitions.push(self.column_definition(&name, &sql_type, column));
+            }
+        }
+
+        let keys: Vec<String> = table
+            .key_columns()
+            .iter()
+            .flat_map(|x| x.sql_columns())
+            .map(|x| self.quote(&x.0))
+            .collect();
+        if !keys.is_empty() {
+            definitions.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+        }
+
+        let mut result = vec![format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            self.quote(&table.sql_name),
+            definitions.join(", ")
+        )];
+
+        for child in table.child_tables() {
+            result.push(self.create_child_table(table, child, registry)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Create the statement to create a child table. Rows of child tables are deleted with their owner.
+    fn create_child_table(
+        &self,
+        owner: &TableDefinition,
+        child: &ChildTable,
+        registry: &ErmTypesRegistry,
+    ) -> ErmResult<String> {
+        let owner_key = owner.key_columns()[0];
+        let mut definitions = vec![format!(
+            "{} ON DELETE CASCADE",
+            self.reference_definition(
+                &child.owner_column.sql_name,
+                &child.owner_column.sql_type,
+                true,
+                owner,
+                owner_key,
+            )
+        )];
+
+        let entries: Vec<&ChildColumn> = child.columns().into_iter().skip(1).collect();
+        for column in entries {
+            if column.is_reference() {
+                let (target, key) = registry.referenced_key(&column.sql_type, None)?;
+                definitions.push(self.reference_definition(
+                    &column.sql_name,
+                    &key.sql_type,
+                    true,
+                    target,
+                    key,
+                ));
+
+                continue;
+            }
+
+            definitions.push(format!(
+                "{} {}{}",
+                self.quote(&column.sql_name),
+                self.type_name(&column.sql_type).unwrap_or_default(),
+                if column.sql_type.is_not_null() {
+                    " NOT NULL"
+                } else {
+                    ""
+                }
+            ));
+        }
+
+        let keys: Vec<String> = child
+            .primary_key()
+            .iter()
+            .map(|x| self.quote(&x.sql_name))
+            .collect();
+        definitions.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+
+        Ok(format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            self.quote(&child.sql_name),
+            definitions.join(", ")
+        ))
+    }
+
+    /// Define a column holding the key of a row in another table.
+    fn reference_definition(
+        &self,
+        name: &str,
+        key_type: &SqlType,
+        not_null: bool,
+        target: &TableDefinition,
+        key: &ColumnDefinition,
+    ) -> String {
+        format!(
+            "{} {}{} REFERENCES {} ({})",
+            self.quote(name),
+            self.type_name(key_type).unwrap_or_default(),
+            if not_null { " NOT NULL" } else { "" },
+            self.quote(&target.sql_name),
+            self.quote(&key.sql_name)
+        )
+    }
+
+    /// Return the actions of a reference, leaving out the default of no action.
+    fn referential_actions(column: &ColumnDefinition) -> String {
+        let mut result = String::new();
+        if column.on_delete() != ReferentialAction::NoAction {
+            result.push_str(&format!(" ON DELETE {}", column.on_delete().sql()));
+        }
+        if column.on_update() != ReferentialAction::NoAction {
+            result.push_str(&format!(" ON UPDATE {}", column.on_update().sql()));
+        }
+
+        result
+    }
+
+    /// Define a column holding a value.
+    fn column_definition(
+        &self,
+        name: &str,
+        sql_type: &SqlType,
+        column: &ColumnDefinition,
+    ) -> String {
+        let mut type_name = self.type_name(sql_type).unwrap_or_default();
+        let mut constraints = String::new();
+
+        if column.has_max_length() {
+            let length = column.get_max_length();
+            match self {
+                // Sqlite ignores the length of varchar, so check it explicitly.
+                SqlDialect::Sqlite => constraints.push_str(&format!(
+                    " CHECK (length({}) <= {})",
+                    self.quote(name),
+                    length
+                )),
+                _ => type_name = format!("VARCHAR({})", length),
+            }
+        }
+
+        if sql_type.is_not_null() || column.is_key() {
+            constraints.insert_str(0, " NOT NULL");
+        }
+
+        if column.is_unique() {
+            constraints.push_str(" UNIQUE");
+        }
+
+        format!("{} {}{}", self.quote(name), type_name, constraints)
+    }
+
+    /// Create a statement inserting a single row.
+    pub fn insert(&self, table: &str, columns: &[&str]) -> String {
+        self.insert_many(table, columns, 1)
+    }
+
+    /// Create a statement inserting the given number of rows. The parameters are the values
+    /// of the first row, followed by the values of the next rows.
+    pub fn insert_many(&self, table: &str, columns: &[&str], rows: usize) -> String {
+        let values = (0..rows)
+            .map(|row| {
+                let placeholders = (1..=columns.len())
+                    .map(|i| self.placeholder(row * columns.len() + i))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("({})", placeholders)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.quote(table),
+            self.column_list(columns),
+            values
+        )
+    }
+
+    /// Create a statement inserting the given number of rows, which updates the update columns
+    /// of the existing row instead, if a row conflicts with it in the conflict columns.
+    /// MySQL takes any key or unique column as conflict target.
+    pub fn upsert_many(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: usize,
+        conflict_columns: &[&str],
+        update_columns: &[&str],
+    ) -> String {
+        let insert = self.insert_many(table, columns, rows);
+        let assign = |x: &&str, value: String| format!("{} = {}", self.quote(x), value);
+
+        match self {
+            SqlDialect::MySql => {
+                let mut assignments = update_columns
+                    .iter()
+                    .map(|x| assign(x, format!("VALUES({})", self.quote(x))))
+                    .collect::<Vec<String>>();
+                // MySQL cannot do nothing on conflict, so a conflict column is kept instead.
+                if assignments.is_empty() {
+                    if let Some(x) = conflict_columns.first() {
+                        assignments.push(assign(x, self.quote(x)));
+                    }
+                }
+
+                format!(
+                    "{} ON DUPLICATE KEY UPDATE {}",
+                    insert,
+                    assignments.join(", ")
+                )
+            }
+            _ => {
+                let action = match update_columns.is_empty() {
+                    true => "NOTHING".to_owned(),
+                    false => format!(
+                        "UPDATE SET {}",
+                        update_columns
+                            .iter()
+                            .map(|x| assign(x, format!("excluded.{}", self.quote(x))))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                };
+
+                format!(
+                    "{} ON CONFLICT ({}) DO {}",
+                    insert,
+                    self.column_list(conflict_columns),
+                    action
+                )
+            }
+        }
+    }
+
+    /// Create a statement updating the given columns of the rows matching the key columns.
+    /// The parameters are the values of the columns followed by the values of the keys.
+    pub fn update(&self, table: &str, columns: &[&str], key_columns: &[&str]) -> String {
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{} = {}", self.quote(x), self.placeholder(i + 1)))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.quote(table),
+            assignments,
+            self.key_condition(key_columns, columns.len())
+        )
+    }
+
+    /// Create a statement deleting the rows matching the key columns.
+    pub fn delete(&self, table: &str, key_columns: &[&str]) -> String {
+        format!(
+            "DELETE FROM {} WHERE {}",
+            self.quote(table),
+            self.key_condition(key_columns, 0)
+        )
+    }
+
+    /// Create a statement selecting the given columns of the rows matching the key columns.
+    pub fn select(&self, table: &str, columns: &[&str], key_columns: &[&str]) -> String {
+        let mut result = format!(
+            "SELECT {} FROM {}",
+            self.column_list(columns),
+            self.quote(table)
+        );
+
+        if !key_columns.is_empty() {
+            result.push_str(&format!(" WHERE {}", self.key_condition(key_columns, 0)));
+        }
+
+        result
+    }
+
+    fn column_list(&self, columns: &[&str]) -> String {
+        columns
+            .iter()
+            .map(|x| self.quote(x))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Compare each key column to a parameter. Offset is the number of parameters before the keys.
+    fn key_condition(&self, key_columns: &[&str], offset: usize) -> String {
+        key_columns
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{} = {}", self.quote(x), self.placeholder(offset + i + 1)))
+            .collect::<Vec<String>>()
+            .join(" AND ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_types() {
+        assert_eq!(
+            SqlDialect::Sqlite.type_name(&SqlType::Json(true)),
+            Some("TEXT".to_owned())
+        );
+        assert_eq!(
+            SqlDialect::PostgreSql.type_name(&SqlType::Json(true)),
+            Some("JSONB".to_owned())
+        );
+        assert_eq!(
+            SqlDialect::MySql.type_name(&SqlType::Json(false)),
+            Some("JSON".to_owned())
+        );
+    }
+
+    #[test]
+    fn scalar_types() {
+        assert_eq!(
+            SqlDialect::PostgreSql.type_name(&SqlType::UnsingedInteger(32, true)),
+            Some("BIGINT".to_owned())
+        );
+        assert_eq!(
+            SqlDialect::MySql.type_name(&SqlType::Integer(64, true)),
+            Some("BIGINT".to_owned())
+        );
+        assert_eq!(
+            SqlDialect::Sqlite.type_name(&SqlType::Float(64, false)),
+            Some("REAL".to_owned())
+        );
+        assert_eq!(SqlDialect::Sqlite.type_name(&SqlType::None), None);
+
+        // Points in time are stored as the seconds of the clock.
+        for dialect in [SqlDialect::PostgreSql, SqlDialect::MySql] {
+            assert_eq!(
+                dialect.type_name(&SqlType::DateTime(true)),
+                Some("BIGINT".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            SqlDialect::Sqlite.insert("Players", &["id", "name"]),
+            "INSERT INTO \"Players\" (\"id\", \"name\") VALUES (?1, ?2)"
+        );
+        assert_eq!(
+            SqlDialect::PostgreSql.update("Players", &["name"], &["id"]),
+            "UPDATE \"Players\" SET \"name\" = $1 WHERE \"id\" = $2"
+        );
+        assert_eq!(
+            SqlDialect::MySql.delete("Players", &["id"]),
+            "DELETE FROM `Players` WHERE `id` = ?"
+        );
+        assert_eq!(
+            SqlDialect::Sqlite.select("Players", &["id"], &[]),
+            "SELECT \"id\" FROM \"Players\""
+        );
+    }
+
+    #[test]
+    fn bulk_statements() {
+        assert_eq!(
+            SqlDialect::PostgreSql.insert_many("Players", &["id", "name"], 2),
+            "INSERT INTO \"Players\" (\"id\", \"name\") VALUES ($1, $2), ($3, $4)"
+        );
+        assert_eq!(
+            SqlDialect::Sqlite.upsert_many("Players", &["id", "name"], 2, &["id"], &["name"]),
+            "INSERT INTO \"Players\" (\"id\", \"name\") VALUES (?1, ?2), (?3, ?4) \
+             ON CONFLICT (\"id\") DO UPDATE SET \"name\" = excluded.\"name\""
+        );
+        assert_eq!(
+            SqlDialect::PostgreSql.upsert_many("Players", &["id"], 1, &["id"], &[]),
+            "INSERT INTO \"Players\" (\"id\") VALUES ($1) ON CONFLICT (\"id\") DO NOTHING"
+        );
+        assert_eq!(
+            SqlDialect::MySql.upsert_many("Players", &["id", "name"], 1, &["id"], &["name"]),
+            "INSERT INTO `Players` (`id`, `name`) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+        assert_eq!(
+            SqlDialect::MySql.upsert_many("Players", &["id"], 1, &["id"], &[]),
+            "INSERT INTO `Players` (`id`) VALUES (?) ON DUPLICATE KEY UPDATE `id` = `id`"
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use std::any::TypeId;
 
+use bevy::math::{
+    Affine2, Affine3A, BVec2, BVec3, BVec4, DQuat, DVec2, DVec3, DVec4, Mat2, Mat3, Mat4, Vec3A,
+};
 use bevy::reflect::Type;
 use bevy::{
     prelude::*,
@@ -7,21 +10,61 @@ use bevy::{
     utils::HashMap,
 };
 
-use crate::prelude::{Key, Unique};
+use crate::lazy::is_lazy;
+use crate::prelude::{
+    fixed_blob_size, Cascade, ChildColumn, ChildTable, ColumnStorage, CreatedAt, Decompose,
+    ElementTable, ErmError, ErmResult, Framed, Json, Key, OnDelete, OnUpdate, PackedBlob,
+    ReferentialAction, SqlDialect, Unique, UpdatedAt, Version,
+};
 use crate::{
     prelude::SqlType,
     prelude::{
-        ColumnDefinition, ColumnName, FieldConstraint, MaxLength, Reference, TableDefinition,
-        TableName,
+        ColumnDefinition, ColumnName, FieldConstraint, MaxLength, Reference, SoftDelete,
+        TableDefinition, TableName,
     },
 };
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct ErmTypesRegistry {
     tables: HashMap<String, TableDefinition>,
+
+    /// Store all vectors and quaternions as one column per component,
+    /// as if they were marked with the decompose attribute.
+    pub decompose_math_types: bool,
+
+    /// The dialect used for the database. Dialects with a json column type
+    /// store collections and enums as json by default.
+    pub dialect: SqlDialect,
+
+    /// The number of levels eager relations are followed when loading values.
+    /// Relations beyond the limit hold only the key of the referenced row.
+    pub eager_depth: usize,
+
+    /// The maximum number of keys a single select takes, when relations or the elements
+    /// of collections are loaded for many values at once. The parameter limit of the
+    /// dialect of the backend applies as well.
+    pub batch_size: usize,
+}
+
+impl Default for ErmTypesRegistry {
+    fn default() -> Self {
+        ErmTypesRegistry {
+            tables: HashMap::default(),
+            decompose_math_types: false,
+            dialect: SqlDialect::default(),
+            eager_depth: Self::DEFAULT_EAGER_DEPTH,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+        }
+    }
 }
 
 impl ErmTypesRegistry {
+    /// The number of levels eager relations are followed by default.
+    pub const DEFAULT_EAGER_DEPTH: usize = 4;
+
+    /// The number of keys a single select takes by default, when loading in batches.
+    pub const DEFAULT_BATCH_SIZE: usize = 500;
+
     /// Retrieve table definition by rust or sql name.
     /// The sql name will be quicker, since the definitions are held in a
     /// hashmap using the sql_name as key. However, going through all tables
@@ -45,6 +88,94 @@ impl ErmTypesRegistry {
         Some(result[0])
     }
 
+    /// Retrieve table definition by the type id of the rust type.
+    pub fn get_table_definition_by_type_id(&self, type_id: TypeId) -> Option<&TableDefinition> {
+        self.tables.values().find(|x| x.ty.id() == type_id)
+    }
+
+    /// Return all table definitions, ordered by their sql name.
+    pub fn tables(&self) -> Vec<&TableDefinition> {
+        let mut result: Vec<&TableDefinition> = self.tables.values().collect();
+        result.sort_by(|a, b| a.sql_name.cmp(&b.sql_name));
+
+        result
+    }
+
+    /// Return the table and the key column a relation points to.
+    /// The key column can be named explicitly, otherwise the table needs exactly one key.
+    pub fn referenced_key(
+        &self,
+        sql_type: &SqlType,
+        key_field: Option<&str>,
+    ) -> ErmResult<(&TableDefinition, &ColumnDefinition)> {
+        let (SqlType::One2One(type_id, _) | SqlType::Many2Many(type_id, _)) = sql_type else {
+            return Err(ErmError::InvalidMapping(format!(
+                "{} is not a relation",
+                sql_type
+            )));
+        };
+
+        let Some(table) = self.get_table_definition_by_type_id(*type_id) else {
+            return Err(ErmError::InvalidMapping(
+                "The target of a relation is not registered with the ERM-Registry".to_owned(),
+            ));
+        };
+
+        let key = match key_field {
+            Some(name) => table.get(name),
+            None => match table.key_columns()[..] {
+                [key] => Some(key),
+                _ => None,
+            },
+        };
+
+        let Some(key) = key else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Table {} has no single key column to reference",
+                table.sql_name
+            )));
+        };
+
+        Ok((table, key))
+    }
+
+    /// Return the table and key column referenced by a relation column.
+    /// The key column is taken from the reference attribute, if the column has one.
+    pub fn referenced_key_of(
+        &self,
+        column: &ColumnDefinition,
+    ) -> ErmResult<(&TableDefinition, &ColumnDefinition)> {
+        let key_field = match column
+            .constraints
+            .iter()
+            .find(|x| matches!(x, FieldConstraint::Reference(_, _)))
+        {
+            Some(FieldConstraint::Reference(_, key)) => Some(key.as_str()),
+            _ => None,
+        };
+
+        self.referenced_key(&column.sql_type, key_field)
+    }
+
+    /// Return the reference graph of a table: the columns holding relations, each with the
+    /// table it references.
+    pub fn references<'a>(
+        &'a self,
+        table: &'a TableDefinition,
+    ) -> ErmResult<Vec<(&'a ColumnDefinition, &'a TableDefinition)>> {
+        let mut result = Vec::new();
+        for column in table.columns() {
+            if matches!(
+                column.sql_type,
+                SqlType::One2One(_, _) | SqlType::Many2Many(_, _)
+            ) {
+                result.push((column, self.referenced_key_of(column)?.0));
+            }
+        }
+
+        Ok(result)
+    }
+
     pub fn get_type_from_type_id(
         &mut self,
         type_registry: &AppTypeRegistry,
@@ -69,8 +200,40 @@ impl ErmTypesRegistry {
         None
     }
 
+    /// Returns true, if the type is an Option<T>.
+    pub(crate) fn is_option(ty: &TypeInfo) -> bool {
+        let TypeInfo::Enum(e) = ty else {
+            return false;
+        };
+
+        e.generics().len() == 1 && e.variant("Some").is_some() && e.variant("None").is_some()
+    }
+
+    /// Return the type wrapped by an Option<T>.
+    fn option_type(ty: &TypeInfo, app_registry: &AppTypeRegistry) -> Option<&'static TypeInfo> {
+        if !Self::is_option(ty) {
+            return None;
+        }
+
+        let TypeInfo::Enum(e) = ty else {
+            return None;
+        };
+
+        app_registry
+            .read()
+            .get(e.generics()[0].type_id())
+            .map(|t| t.type_info())
+    }
+
     /// Map a rust type to a sql type.
     fn rust_to_sql_type(ty: &TypeInfo, app_registry: &AppTypeRegistry) -> SqlType {
+        // Lazy relations.
+        if let TypeInfo::Struct(s) = ty {
+            if is_lazy(ty) {
+                return SqlType::One2One(s.generics()[0].type_id(), false);
+            }
+        }
+
         // Integers.
         if *ty.ty() == Type::of::<u8>() {
             return SqlType::UnsingedInteger(8, true);
@@ -165,6 +328,86 @@ impl ErmTypesRegistry {
             return SqlType::Blob(true);
         }
 
+        // Vector (Aligned and double precision)
+        if *ty.ty() == Type::of::<Vec3A>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<DVec2>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<DVec3>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<DVec4>() {
+            return SqlType::Blob(true);
+        }
+
+        // Bool vectors
+        if *ty.ty() == Type::of::<BVec2>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<BVec3>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<BVec4>() {
+            return SqlType::Blob(true);
+        }
+
+        // Double precision quat
+        if *ty.ty() == Type::of::<DQuat>() {
+            return SqlType::Blob(true);
+        }
+
+        // Matrices
+        if *ty.ty() == Type::of::<Mat2>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Mat3>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Mat4>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Affine2>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Affine3A>() {
+            return SqlType::Blob(true);
+        }
+
+        // Transform
+        if *ty.ty() == Type::of::<Transform>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<GlobalTransform>() {
+            return SqlType::Blob(true);
+        }
+
+        // Rects
+        if *ty.ty() == Type::of::<Rect>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<IRect>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<URect>() {
+            return SqlType::Blob(true);
+        }
+
+        // Colors
+        if *ty.ty() == Type::of::<Color>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Srgba>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<LinearRgba>() {
+            return SqlType::Blob(true);
+        }
+        if *ty.ty() == Type::of::<Hsla>() {
+            return SqlType::Blob(true);
+        }
+
         // Check for option:
         if let TypeInfo::Enum(e) = ty {
             
@@ -189,6 +432,7 @@ impl ErmTypesRegistry {
                         SqlType::DateTime(_) => return SqlType::DateTime(false),
                         SqlType::Blob(_) => return SqlType::Blob(false),
                         SqlType::Boolean(_) => return SqlType::Boolean(false),
+                        SqlType::Json(_) => return SqlType::Json(false),
                     }
                 };
 
@@ -204,7 +448,7 @@ impl ErmTypesRegistry {
 
                     match option_type {
                         SqlType::None => panic!("Invalid sql type!"),
-                        SqlType::One2One(t, _) => return SqlType::Many2Many(t, true),
+                        SqlType::One2One(t, eager) => return SqlType::Many2Many(t, eager),
                         SqlType::Many2Many(_, _) => {
                             panic!("Dont know how to handle nested relations!")
                         }
@@ -219,6 +463,7 @@ impl ErmTypesRegistry {
                         SqlType::DateTime(_) => return SqlType::DateTime(false),
                         SqlType::Blob(_) => return SqlType::Blob(false),
                         SqlType::Boolean(_) => return SqlType::Boolean(false),
+                        SqlType::Json(_) => return SqlType::Json(false),
                     }
                 };
 
@@ -235,17 +480,89 @@ impl ErmTypesRegistry {
         SqlType::Blob(true)
     }
 
+    /// Returns true, if the type is stored as json for dialects that prefer json.
+    /// These are collections and enums, except for relations to other tables.
+    fn is_json_default(ty: &TypeInfo, app_registry: &AppTypeRegistry) -> bool {
+        match ty {
+            TypeInfo::Map(_) | TypeInfo::Set(_) => true,
+            TypeInfo::List(_) | TypeInfo::Array(_) => !matches!(
+                Self::rust_to_sql_type(ty, app_registry),
+                SqlType::Many2Many(_, _)
+            ),
+            TypeInfo::Enum(_) if Self::is_option(ty) => Self::option_type(ty, app_registry)
+                .is_some_and(|t| Self::is_json_default(t, app_registry)),
+            TypeInfo::Enum(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Return the component names and the sql type of each component,
+    /// if the type can be decomposed into one column per component.
+    fn decomposed_components(
+        ty: &TypeInfo,
+        app_registry: &AppTypeRegistry,
+    ) -> Option<(Vec<String>, SqlType)> {
+        // Unwrap options, all components become nullable.
+        if let Some(inner) = Self::option_type(ty, app_registry) {
+            let (components, sql_type) = Self::decomposed_components(inner, app_registry)?;
+            let sql_type = match sql_type {
+                SqlType::Float(s, _) => SqlType::Float(s, false),
+                SqlType::Integer(s, _) => SqlType::Integer(s, false),
+                SqlType::UnsingedInteger(s, _) => SqlType::UnsingedInteger(s, false),
+                other => other,
+            };
+
+            return Some((components, sql_type));
+        }
+
+        let xy = || vec!["x".to_owned(), "y".to_owned()];
+        let xyz = || vec!["x".to_owned(), "y".to_owned(), "z".to_owned()];
+        let xyzw = || vec!["x".to_owned(), "y".to_owned(), "z".to_owned(), "w".to_owned()];
+
+        let t = *ty.ty();
+        let result = if t == Type::of::<Vec2>() {
+            (xy(), SqlType::Float(32, true))
+        } else if t == Type::of::<Vec3>() || t == Type::of::<Vec3A>() {
+            (xyz(), SqlType::Float(32, true))
+        } else if t == Type::of::<Vec4>() || t == Type::of::<Quat>() {
+            (xyzw(), SqlType::Float(32, true))
+        } else if t == Type::of::<DVec2>() {
+            (xy(), SqlType::Float(64, true))
+        } else if t == Type::of::<DVec3>() {
+            (xyz(), SqlType::Float(64, true))
+        } else if t == Type::of::<DVec4>() || t == Type::of::<DQuat>() {
+            (xyzw(), SqlType::Float(64, true))
+        } else if t == Type::of::<IVec2>() {
+            (xy(), SqlType::Integer(32, true))
+        } else if t == Type::of::<IVec3>() {
+            (xyz(), SqlType::Integer(32, true))
+        } else if t == Type::of::<IVec4>() {
+            (xyzw(), SqlType::Integer(32, true))
+        } else if t == Type::of::<UVec2>() {
+            (xy(), SqlType::UnsingedInteger(32, true))
+        } else if t == Type::of::<UVec3>() {
+            (xyz(), SqlType::UnsingedInteger(32, true))
+        } else if t == Type::of::<UVec4>() {
+            (xyzw(), SqlType::UnsingedInteger(32, true))
+        } else {
+            return None;
+        };
+
+        Some(result)
+    }
+
     fn field_definition(
+        &self,
         f: &NamedField,
         app_registry: &AppTypeRegistry,
         order : usize,
-    ) -> Option<ColumnDefinition> {
+    ) -> ErmResult<Option<ColumnDefinition>> {
         let Some(type_info) = f.type_info() else {
             info!(
                 "Field {} has no type info or it is not a StructInfo.",
                 f.name()
             );
-            return None;
+            return Ok(None);
         };
 
         // Rust name
@@ -272,6 +589,18 @@ impl ErmTypesRegistry {
             def.add(FieldConstraint::Unique);
         }
 
+        if f.get_attribute::<Version>().is_some() {
+            def.add(FieldConstraint::Version);
+        }
+
+        if f.get_attribute::<CreatedAt>().is_some() {
+            def.add(FieldConstraint::CreatedAt);
+        }
+
+        if f.get_attribute::<UpdatedAt>().is_some() {
+            def.add(FieldConstraint::UpdatedAt);
+        }
+
         if let Some(rf) = f.get_attribute::<Reference>() {
             def.add(FieldConstraint::Reference(
                 rf.rust_name.clone(),
@@ -279,20 +608,243 @@ impl ErmTypesRegistry {
             ));
         }
 
+        if let Some(cascade) = f.get_attribute::<Cascade>() {
+            def.add(FieldConstraint::Cascade(cascade.save, cascade.delete));
+        }
+
+        if let Some(on_delete) = f.get_attribute::<OnDelete>() {
+            def.add(FieldConstraint::OnDelete(on_delete.action));
+        }
+
+        if let Some(on_update) = f.get_attribute::<OnUpdate>() {
+            def.add(FieldConstraint::OnUpdate(on_update.action));
+        }
+
         // Rename column
         if let Some(attrib) = f.get_attribute::<ColumnName>() {
             def.sql_name = attrib.sql_name.clone();
         }
 
+        // A field can only be stored in one way.
+        let storage_attributes = [
+            f.get_attribute::<Framed>().is_some(),
+            f.get_attribute::<Json>().is_some(),
+            f.get_attribute::<Decompose>().is_some(),
+            f.get_attribute::<ElementTable>().is_some(),
+            f.get_attribute::<PackedBlob>().is_some(),
+        ];
+        if storage_attributes.iter().filter(|x| **x).count() > 1 {
+            return Err(ErmError::InvalidMapping(format!(
+                "Field {} has more than one storage attribute",
+                f.name()
+            )));
+        }
+
+        // Framed structs are stored as blob, even if the struct is a registered type.
+        if f.get_attribute::<Framed>().is_some() {
+            def.storage = ColumnStorage::FramedBlob;
+            def.sql_type = SqlType::Blob(!Self::is_option(type_info));
+
+            return Ok(Some(def));
+        }
+
+        // Json, either marked explicitly or by default of the dialect.
+        if f.get_attribute::<Json>().is_some()
+            || (self.dialect.json_by_default() && Self::is_json_default(type_info, app_registry))
+        {
+            def.sql_type = SqlType::Json(!Self::is_option(type_info));
+
+            return Ok(Some(def));
+        }
+
+        // Vectors and quaternions, either marked explicitly or by the global policy.
+        let decompose = f.get_attribute::<Decompose>().is_some();
+        if decompose || self.decompose_math_types {
+            if let Some((components, sql_type)) =
+                Self::decomposed_components(type_info, app_registry)
+            {
+                def.storage = ColumnStorage::Decomposed(components);
+                def.sql_type = sql_type;
+
+                return Ok(Some(def));
+            }
+
+            if decompose {
+                info!(
+                    "Field {} cannot be decomposed, it is not a vector or quaternion.",
+                    f.name()
+                );
+            }
+        }
+
+        // Collections of values. Collections of registered types are relations.
+        if let Some(element) = Self::element_type(type_info) {
+            if Self::element_type(element).is_some() {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} is a nested collection, these can only be stored as json",
+                    f.name()
+                )));
+            }
+
+            let element_type = Self::rust_to_sql_type(element, app_registry);
+            if !matches!(element_type, SqlType::One2One(_, _)) {
+                return Self::collection_definition(f, def, element, element_type).map(Some);
+            }
+        }
+
+        // Maps and sets, registered types on either side become references.
+        if let TypeInfo::Map(m) = type_info {
+            let key = Self::entry_type(f, m.key_ty().id(), app_registry)?;
+            let value = Self::entry_type(f, m.value_ty().id(), app_registry)?;
+
+            def.storage = ColumnStorage::ChildTable(ChildTable::map(key, value.clone()));
+            def.sql_type = value;
+
+            return Ok(Some(def));
+        }
+
+        if let TypeInfo::Set(set) = type_info {
+            let value = Self::entry_type(f, set.value_ty().id(), app_registry)?;
+
+            def.storage = ColumnStorage::ChildTable(ChildTable::set(value.clone()));
+            def.sql_type = value;
+
+            return Ok(Some(def));
+        }
+
+        // An empty collection cannot be told apart from None in a child table.
+        let optional = Self::option_type(type_info, app_registry);
+        if optional.is_some_and(|t| matches!(t, TypeInfo::Map(_) | TypeInfo::Set(_))) {
+            return Err(ErmError::InvalidMapping(format!(
+                "Field {} is an optional collection, which can only be stored as json",
+                f.name()
+            )));
+        }
+
+        if let Some(element) = optional.and_then(Self::element_type) {
+            if !matches!(
+                Self::rust_to_sql_type(element, app_registry),
+                SqlType::One2One(_, _)
+            ) {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} is an optional collection, which can only be stored as json",
+                    f.name()
+                )));
+            }
+        }
+
+        if f.get_attribute::<ElementTable>().is_some() || f.get_attribute::<PackedBlob>().is_some()
+        {
+            return Err(ErmError::InvalidMapping(format!(
+                "Field {} is not a collection of values",
+                f.name()
+            )));
+        }
+
         def.sql_type = Self::rust_to_sql_type(type_info, app_registry);
 
-        Some(def)
+        // Timestamps are seconds since the unix epoch, held by i64 fields.
+        if def.is_created_at() || def.is_updated_at() {
+            if let SqlType::Integer(64, not_null) = def.sql_type {
+                def.sql_type = SqlType::DateTime(not_null);
+            }
+        }
+
+        // Many to many relations are stored in a join table holding the keys of the targets.
+        if let SqlType::Many2Many(type_id, _) = def.sql_type {
+            def.storage =
+                ColumnStorage::ChildTable(ChildTable::list(SqlType::One2One(type_id, true)));
+        }
+
+        Ok(Some(def))
+    }
+
+    /// Return the type of the elements, if the type is a list or an array.
+    fn element_type(ty: &TypeInfo) -> Option<&'static TypeInfo> {
+        match ty {
+            TypeInfo::List(l) => l.item_info(),
+            TypeInfo::Array(a) => a.item_info(),
+            _ => None,
+        }
+    }
+
+    /// Map the key or value type of a map or set to the sql type of its child table column.
+    fn entry_type(
+        f: &NamedField,
+        type_id: TypeId,
+        app_registry: &AppTypeRegistry,
+    ) -> ErmResult<SqlType> {
+        let Some(type_info) = app_registry.read().get(type_id).map(|t| t.type_info()) else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Entries of field {} are not registered with the app type registry",
+                f.name()
+            )));
+        };
+
+        if Self::is_option(type_info)
+            || matches!(
+                type_info,
+                TypeInfo::List(_) | TypeInfo::Array(_) | TypeInfo::Map(_) | TypeInfo::Set(_)
+            )
+        {
+            return Err(ErmError::InvalidMapping(format!(
+                "Entries of field {} are nested or optional, these can only be stored as json",
+                f.name()
+            )));
+        }
+
+        Ok(Self::rust_to_sql_type(type_info, app_registry))
+    }
+
+    /// Map a list or array of values either to a child table or a packed blob.
+    fn collection_definition(
+        f: &NamedField,
+        mut def: ColumnDefinition,
+        element: &TypeInfo,
+        element_type: SqlType,
+    ) -> ErmResult<ColumnDefinition> {
+        if f.get_attribute::<PackedBlob>().is_some() {
+            if fixed_blob_size(element.type_id()).is_none() {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Elements of field {} have no fixed size and cannot be packed",
+                    f.name()
+                )));
+            }
+
+            def.storage = ColumnStorage::PackedBlob;
+            def.sql_type = SqlType::Blob(true);
+
+            return Ok(def);
+        }
+
+        // The owner column of the child table is set, once the key of the table is known.
+        def.storage = ColumnStorage::ChildTable(ChildTable::list(element_type.clone()));
+        def.sql_type = element_type;
+
+        Ok(def)
     }
 
     /// Reflect over the type T and add a new table definition.
     /// Adds the table definition to the ERM-Registry and returns the sql name.
     /// Remember to use the reflect marco and reflect over Default, like so: #[reflect(Default)]
+    /// Errors are logged, use `try_register_type` to handle them.
     pub fn register_type<T>(&mut self, app_registry: &AppTypeRegistry) -> Option<String>
+    where
+        T: Reflect + Default + TypePath + bevy::prelude::Struct,
+    {
+        match self.try_register_type::<T>(app_registry) {
+            Ok(sql_name) => Some(sql_name),
+            Err(ErmError::AlreadyRegistered(_)) => None,
+            Err(e) => {
+                error!("Could not register type {}: {}", T::short_type_path(), e);
+                None
+            }
+        }
+    }
+
+    /// Reflect over the type T and add a new table definition.
+    /// Adds the table definition to the ERM-Registry and returns the sql name.
+    pub fn try_register_type<T>(&mut self, app_registry: &AppTypeRegistry) -> ErmResult<String>
     // We expect T to be a struct! Unnamed tuples cannot be mapped to a typical relational datamodel. 
     // All SQL implementation i've encountered so far required a table to explicitly name its fields.
     where
@@ -302,23 +854,33 @@ impl ErmTypesRegistry {
         let registry = app_registry.read();
 
         // Get type registration from registry.
-        let t = registry.get(type_id)?;
+        let Some(t) = registry.get(type_id) else {
+            return Err(ErmError::Unsupported(format!(
+                "Type {} is not registered with the app type registry",
+                T::short_type_path()
+            )));
+        };
 
         // We expect structs. Tuple structs lack field names,
         // and we need field names to create SQL-Tables.
         let TypeInfo::Struct(strct) = t.type_info() else {
-            return None;
+            return Err(ErmError::Unsupported(format!(
+                "Type {} is not a struct with named fields",
+                T::short_type_path()
+            )));
         };
 
         let Some(ref_default) = t.data::<ReflectDefault>() else {
-            panic!(
+            return Err(ErmError::Unsupported(format!(
                 "Type {} has no reflect default",
-                strct.ty().ident().unwrap()
-            );
+                T::short_type_path()
+            )));
         };
 
         // Rust name
-        let rust_name = strct.ty().ident()?;
+        let Some(rust_name) = strct.ty().ident() else {
+            return Err(ErmError::Unsupported("Type has no identifier".to_owned()));
+        };
 
         // Check for TableName attribute.
         let mut r = TableDefinition::new(rust_name, rust_name, strct.ty(), ref_default);
@@ -328,8 +890,8 @@ impl ErmTypesRegistry {
         };
 
         // Did we already reflect over this table.
-        if self.tables.get(&sql_name).is_some() {
-            return None;
+        if self.get_table_definition(&sql_name).is_some() {
+            return Err(ErmError::AlreadyRegistered(sql_name));
         }
 
         r.sql_name = sql_name.clone();
@@ -339,15 +901,132 @@ impl ErmTypesRegistry {
                 continue;
             };
 
-            let Some(field) = Self::field_definition(f, app_registry, i) else {
+            let Some(field) = self.field_definition(f, app_registry, i)? else {
                 continue;
             };
 
+            let relation = matches!(
+                field.sql_type,
+                SqlType::One2One(_, _) | SqlType::Many2Many(_, _)
+            );
+            if (field.cascades_save() || field.cascades_delete()) && !relation {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} is not a relation and cannot cascade",
+                    field.rust_name
+                )));
+            }
+
+            // Actions apply to the foreign key of relations stored in a column.
+            let actions = field.constraints.iter().any(|x| {
+                matches!(x, FieldConstraint::OnDelete(_) | FieldConstraint::OnUpdate(_))
+            });
+            if actions && !matches!(field.sql_type, SqlType::One2One(_, _)) {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} does not hold a single relation and cannot have referential actions",
+                    field.rust_name
+                )));
+            }
+
+            let set_null =
+                [field.on_delete(), field.on_update()].contains(&ReferentialAction::SetNull);
+            if set_null && field.is_not_null() {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} is not null and cannot be set to null",
+                    field.rust_name
+                )));
+            }
+
+            // Versions are counted up, so they need to be integers with a value.
+            if field.is_version() {
+                let integer = matches!(
+                    field.sql_type,
+                    SqlType::Integer(_, true) | SqlType::UnsingedInteger(_, true)
+                );
+                if !integer || field.is_key() || r.version_column().is_some() {
+                    return Err(ErmError::InvalidMapping(format!(
+                        "Field {} cannot be the version of table {}, versions are single integers \
+                         which are not part of the key",
+                        field.rust_name, sql_name
+                    )));
+                }
+            }
+
+            let timestamp = field.is_created_at() || field.is_updated_at();
+            if timestamp
+                && (!matches!(field.sql_type, SqlType::DateTime(_))
+                    || field.is_key()
+                    || (field.is_created_at() && field.is_updated_at()))
+            {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} cannot hold a timestamp of table {}, timestamps are i64 fields \
+                     which are not part of the key and hold either the time of creation or update",
+                    field.rust_name, sql_name
+                )));
+            }
+
             r.add(field);
         }
 
+        // Deleted rows are marked by a flag or a timestamp, which is null for rows not deleted.
+        if let Some(soft_delete) = strct.get_attribute::<SoftDelete>() {
+            let Some(column) = r.get(&soft_delete.column) else {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Table {} has no column {} to mark deleted rows",
+                    sql_name, soft_delete.column
+                )));
+            };
+
+            let marker = matches!(
+                column.sql_type,
+                SqlType::Boolean(true)
+                    | SqlType::Integer(_, false)
+                    | SqlType::UnsingedInteger(_, false)
+                    | SqlType::DateTime(false)
+            );
+            if !marker || column.is_key() || !matches!(column.storage, ColumnStorage::Inline) {
+                return Err(ErmError::InvalidMapping(format!(
+                    "Field {} cannot mark deleted rows of table {}, it needs to be a bool \
+                     or an optional timestamp",
+                    column.rust_name, sql_name
+                )));
+            }
+            r.soft_delete = Some(column.sql_name.clone());
+        }
+
+        Self::link_child_tables(&mut r)?;
         self.tables.insert(rust_name.to_owned(), r);
 
-        Some(sql_name)
+        Ok(sql_name)
+    }
+
+    /// Name the child tables of a table and let them reference the key of the table.
+    fn link_child_tables(table: &mut TableDefinition) -> ErmResult<()> {
+        if table.child_tables().is_empty() {
+            return Ok(());
+        }
+
+        let keys = table.key_columns();
+        if keys.len() != 1 {
+            return Err(ErmError::InvalidMapping(format!(
+                "Table {} needs exactly one key column to store collections in child tables",
+                table.sql_name
+            )));
+        }
+
+        let owner_column = ChildColumn::new(
+            &format!("owner_{}", keys[0].sql_name),
+            keys[0].sql_type.clone(),
+        );
+
+        let table_name = table.sql_name.clone();
+        for column in table.fields.values_mut() {
+            let sql_name = column.sql_name.clone();
+            if let ColumnStorage::ChildTable(child) = &mut column.storage {
+                child.sql_name = format!("{}_{}", table_name, sql_name);
+                child.owner_column = owner_column.clone();
+            }
+        }
+
+        Ok(())
     }
 }
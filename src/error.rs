@@ -0,0 +1,59 @@
+use std::fmt::Display;
+
+/// Errors reported by the ERM.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErmError {
+    /// A blob could not be decoded. The string describes what went wrong.
+    InvalidBlob(String),
+
+    /// A json value could not be read or written.
+    InvalidJson(String),
+
+    /// The value or type cannot be mapped by the ERM.
+    Unsupported(String),
+
+    /// A field cannot be mapped, because its mapping is ambiguous or contradicting.
+    InvalidMapping(String),
+
+    /// The type has already been registered. Holds the sql name of the table.
+    AlreadyRegistered(String),
+
+    /// A value cannot be converted from or to its column. Holds the table, the column and what went wrong.
+    InvalidValue(String, String, String),
+
+    /// A row lacks a column required to read a value. Holds the table and the column.
+    MissingColumn(String, String),
+
+    /// The database reported an error. Holds the message of the database.
+    Database(String),
+
+    /// The row was changed by someone else since its value was read, its version differs.
+    /// Holds the table and the key of the row.
+    ConcurrencyConflict(String, String),
+}
+
+pub type ErmResult<T> = Result<T, ErmError>;
+
+impl Display for ErmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErmError::InvalidBlob(msg) => write!(f, "invalid blob: {}", msg),
+            ErmError::InvalidJson(msg) => write!(f, "invalid json: {}", msg),
+            ErmError::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            ErmError::InvalidMapping(msg) => write!(f, "invalid mapping: {}", msg),
+            ErmError::AlreadyRegistered(name) => write!(f, "table {} is already registered", name),
+            ErmError::InvalidValue(table, column, msg) => {
+                write!(f, "invalid value for {}.{}: {}", table, column, msg)
+            }
+            ErmError::MissingColumn(table, column) => {
+                write!(f, "missing column {}.{}", table, column)
+            }
+            ErmError::Database(msg) => write!(f, "database error: {}", msg),
+            ErmError::ConcurrencyConflict(table, key) => {
+                write!(f, "row {} of {} was changed concurrently", key, table)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErmError {}
@@ -0,0 +1,374 @@
+use std::any::TypeId;
+
+use bevy::reflect::{
+    prelude::ReflectDefault, DynamicEnum, DynamicTuple, DynamicVariant, PartialReflect, Reflect,
+    ReflectMut, ReflectRef, TypeInfo, TypeRegistry,
+};
+
+use crate::error::{ErmError, ErmResult};
+use crate::from_blob::{reflect_from_blob, reflect_into_blob};
+
+/// Version of the framed blob layout. Written as the first byte of every framed blob.
+pub const FRAMED_BLOB_VERSION: u8 = 1;
+
+// Framed blobs store structs field by field, tagged with the field name:
+//
+//  [version: u8] [field count: u16]
+//  per field: [name length: u8] [name] [payload length: u32] [payload]
+//
+// Since every field is tagged and carries its own length, fields can be added, removed
+// or reordered without breaking blobs written by older versions of a struct.
+// All numbers are little endian.
+
+/// Encode a struct into the framed blob format.
+pub fn to_framed_blob(value: &dyn PartialReflect) -> ErmResult<Vec<u8>> {
+    let ReflectRef::Struct(strct) = value.reflect_ref() else {
+        return Err(ErmError::Unsupported(format!(
+            "{} is not a struct and cannot be framed",
+            value.reflect_type_path()
+        )));
+    };
+
+    if strct.field_len() > u16::MAX as usize {
+        return Err(ErmError::Unsupported(format!(
+            "{} has too many fields",
+            value.reflect_type_path()
+        )));
+    }
+
+    let mut result = vec![FRAMED_BLOB_VERSION];
+    result.extend_from_slice(&(strct.field_len() as u16).to_le_bytes());
+
+    for (i, field) in strct.iter_fields().enumerate() {
+        let name = strct.name_at(i).unwrap_or_default();
+
+        if name.len() > u8::MAX as usize {
+            return Err(ErmError::Unsupported(format!(
+                "Field name {} is too long",
+                name
+            )));
+        }
+
+        let payload = encode_value(field)?;
+        let Ok(length) = u32::try_from(payload.len()) else {
+            return Err(ErmError::Unsupported(format!(
+                "Field {} is too large to be framed",
+                name
+            )));
+        };
+
+        result.push(name.len() as u8);
+        result.extend_from_slice(name.as_bytes());
+        result.extend_from_slice(&length.to_le_bytes());
+        result.extend_from_slice(&payload);
+    }
+
+    Ok(result)
+}
+
+/// Create a new instance of the type with the given id from a framed blob.
+/// Fields missing in the blob keep the value provided by `ReflectDefault`,
+/// fields unknown to the type are skipped.
+pub fn from_framed_blob(
+    value: &[u8],
+    type_id: TypeId,
+    registry: &TypeRegistry,
+) -> ErmResult<Box<dyn Reflect>> {
+    let Some(registration) = registry.get(type_id) else {
+        return Err(ErmError::Unsupported(
+            "Type is not registered with the type registry".to_owned(),
+        ));
+    };
+
+    let Some(reflect_default) = registration.data::<ReflectDefault>() else {
+        return Err(ErmError::Unsupported(format!(
+            "Type {} has no reflect default",
+            registration.type_info().type_path()
+        )));
+    };
+
+    let mut result = reflect_default.default();
+    apply_framed_blob(result.as_partial_reflect_mut(), value, registry)?;
+
+    Ok(result)
+}
+
+/// Apply a framed blob to an existing struct. Only fields present in both,
+/// the blob and the struct, are written.
+pub fn apply_framed_blob(
+    target: &mut dyn PartialReflect,
+    value: &[u8],
+    registry: &TypeRegistry,
+) -> ErmResult<()> {
+    let type_path = target.reflect_type_path().to_owned();
+    let ReflectMut::Struct(strct) = target.reflect_mut() else {
+        return Err(ErmError::Unsupported(format!(
+            "{} is not a struct and cannot be framed",
+            type_path
+        )));
+    };
+
+    let mut reader = Reader { data: value };
+    let version = reader.read(1)?[0];
+    if version != FRAMED_BLOB_VERSION {
+        return Err(ErmError::InvalidBlob(format!(
+            "Unknown framed blob version {}",
+            version
+        )));
+    }
+
+    let count = u16::from_le_bytes(reader.read(2)?.try_into().unwrap());
+    for _ in 0..count {
+        let name_len = reader.read(1)?[0] as usize;
+        let name = String::from_utf8_lossy(reader.read(name_len)?).into_owned();
+        let payload_len = u32::from_le_bytes(reader.read(4)?.try_into().unwrap()) as usize;
+        let payload = reader.read(payload_len)?;
+
+        // Fields that have been removed from the struct are skipped.
+        let Some(field) = strct.field_mut(&name) else {
+            continue;
+        };
+
+        decode_value(field, payload, registry)?;
+    }
+
+    Ok(())
+}
+
+/// Encode a single value. Structs are framed recursively, options are prefixed with a tag byte.
+fn encode_value(value: &dyn PartialReflect) -> ErmResult<Vec<u8>> {
+    if let Some(result) = reflect_into_blob(value) {
+        return Ok(result);
+    }
+
+    match value.reflect_ref() {
+        ReflectRef::Struct(_) => to_framed_blob(value),
+        ReflectRef::Enum(e) if is_option(value) => match e.field_at(0) {
+            Some(inner) => Ok([vec![1], encode_value(inner)?].concat()),
+            None => Ok(vec![0]),
+        },
+        _ => Err(ErmError::Unsupported(format!(
+            "{} has no blob encoding",
+            value.reflect_type_path()
+        ))),
+    }
+}
+
+/// Decode a single value into the target.
+fn decode_value(
+    target: &mut dyn PartialReflect,
+    value: &[u8],
+    registry: &TypeRegistry,
+) -> ErmResult<()> {
+    if reflect_from_blob(target, value)? {
+        return Ok(());
+    }
+
+    if is_option(target) {
+        let Some((tag, payload)) = value.split_first() else {
+            return Err(ErmError::InvalidBlob("Missing option tag".to_owned()));
+        };
+
+        if *tag == 0 {
+            target.apply(&DynamicEnum::new("None", DynamicVariant::Unit));
+            return Ok(());
+        }
+
+        let mut inner = option_default(target, registry)?;
+        decode_value(inner.as_mut(), payload, registry)?;
+
+        let mut tuple = DynamicTuple::default();
+        tuple.insert_boxed(inner);
+        target.apply(&DynamicEnum::new("Some", DynamicVariant::Tuple(tuple)));
+        return Ok(());
+    }
+
+    if let ReflectMut::Struct(_) = target.reflect_mut() {
+        return apply_framed_blob(target, value, registry);
+    }
+
+    Err(ErmError::Unsupported(format!(
+        "{} has no blob encoding",
+        target.reflect_type_path()
+    )))
+}
+
+/// Return the current value of Some(..) or a default for the option's type parameter.
+pub(crate) fn option_default(
+    option: &dyn PartialReflect,
+    registry: &TypeRegistry,
+) -> ErmResult<Box<dyn PartialReflect>> {
+    if let ReflectRef::Enum(e) = option.reflect_ref() {
+        if let Some(inner) = e.field_at(0) {
+            return Ok(inner.clone_value());
+        }
+    }
+
+    let Some(TypeInfo::Enum(info)) = option.get_represented_type_info() else {
+        return Err(ErmError::Unsupported("Option without type info".to_owned()));
+    };
+
+    let inner_type = info.generics()[0].type_id();
+    let Some(reflect_default) = registry
+        .get(inner_type)
+        .and_then(|t| t.data::<ReflectDefault>())
+    else {
+        return Err(ErmError::Unsupported(format!(
+            "{} has no reflect default",
+            info.generics()[0].type_path()
+        )));
+    };
+
+    Ok(reflect_default.default().into_partial_reflect())
+}
+
+/// Returns true, if the value is an Option<T>.
+pub(crate) fn is_option(value: &dyn PartialReflect) -> bool {
+    let Some(TypeInfo::Enum(e)) = value.get_represented_type_info() else {
+        return false;
+    };
+
+    e.generics().len() == 1 && e.variant("Some").is_some() && e.variant("None").is_some()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, len: usize) -> ErmResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(ErmError::InvalidBlob(format!(
+                "Expected {} more bytes, got {}",
+                len,
+                self.data.len()
+            )));
+        }
+
+        let (result, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+
+    mod v1 {
+        use bevy::prelude::*;
+
+        #[derive(Reflect, Default, Debug, PartialEq)]
+        #[reflect(Default)]
+        pub struct Save {
+            pub level: u32,
+            pub name: String,
+            pub position: Vec3,
+        }
+    }
+
+    mod v2 {
+        use bevy::prelude::*;
+
+        #[derive(Reflect, Debug, PartialEq)]
+        #[reflect(Default)]
+        pub struct Save {
+            pub name: String,
+            pub position: Vec3,
+            pub health: f32,
+            pub stats: Stats,
+            pub title: Option<String>,
+        }
+
+        impl Default for Save {
+            fn default() -> Self {
+                Save {
+                    name: String::default(),
+                    position: Vec3::ZERO,
+                    health: 100.0,
+                    stats: Stats::default(),
+                    title: None,
+                }
+            }
+        }
+
+        #[derive(Reflect, Default, Debug, PartialEq)]
+        #[reflect(Default)]
+        pub struct Stats {
+            pub strength: i32,
+            pub luck: i32,
+        }
+    }
+
+    fn registry() -> TypeRegistry {
+        let mut registry = TypeRegistry::default();
+        registry.register::<v1::Save>();
+        registry.register::<v2::Save>();
+        registry.register::<v2::Stats>();
+        registry.register::<Option<String>>();
+
+        registry
+    }
+
+    #[test]
+    fn round_trip() {
+        let registry = registry();
+        let subject = v2::Save {
+            name: "Hero".to_owned(),
+            position: Vec3::new(1.0, 2.0, 3.0),
+            health: 42.0,
+            stats: v2::Stats {
+                strength: 7,
+                luck: -3,
+            },
+            title: Some("Dragon slayer".to_owned()),
+        };
+
+        let blob = to_framed_blob(&subject).unwrap();
+        assert_eq!(blob[0], FRAMED_BLOB_VERSION);
+
+        let test = from_framed_blob(&blob, TypeId::of::<v2::Save>(), &registry).unwrap();
+        assert_eq!(test.downcast_ref::<v2::Save>(), Some(&subject));
+    }
+
+    #[test]
+    fn added_and_removed_fields() {
+        let registry = registry();
+        let old = v1::Save {
+            level: 12,
+            name: "Hero".to_owned(),
+            position: Vec3::new(1.0, 2.0, 3.0),
+        };
+
+        let blob = to_framed_blob(&old).unwrap();
+        let test = from_framed_blob(&blob, TypeId::of::<v2::Save>(), &registry).unwrap();
+        let test = test.downcast_ref::<v2::Save>().unwrap();
+
+        // Known fields are read, 'level' is skipped, new fields come from default.
+        assert_eq!(test.name, "Hero");
+        assert_eq!(test.position, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(test.health, 100.0);
+        assert_eq!(test.stats, v2::Stats::default());
+        assert_eq!(test.title, None);
+    }
+
+    #[test]
+    fn invalid_blobs() {
+        let registry = registry();
+        let blob = to_framed_blob(&v1::Save::default()).unwrap();
+
+        let truncated = from_framed_blob(
+            &blob[0..blob.len() - 1],
+            TypeId::of::<v1::Save>(),
+            &registry,
+        );
+        assert!(matches!(truncated, Err(ErmError::InvalidBlob(_))));
+
+        let mut wrong_version = blob.clone();
+        wrong_version[0] = FRAMED_BLOB_VERSION + 1;
+        let wrong_version = from_framed_blob(&wrong_version, TypeId::of::<v1::Save>(), &registry);
+        assert!(matches!(wrong_version, Err(ErmError::InvalidBlob(_))));
+    }
+}
@@ -1,4 +1,12 @@
+use bevy::color::ColorToComponents;
+use bevy::math::{
+    Affine2, Affine3A, BVec2, BVec3, BVec4, DQuat, DVec2, DVec3, DVec4, Mat2, Mat3, Mat4, Vec3A,
+};
 use bevy::prelude::*;
+use bevy::reflect::{PartialReflect, ReflectMut, ReflectRef, TypeInfo};
+use std::any::TypeId;
+
+use crate::error::{ErmError, ErmResult};
 
 pub trait FromBlob {
     fn from_blob(value: &[u8]) -> Self;
@@ -8,6 +16,287 @@ pub trait IntoBlob {
     fn into_blob(self) -> Vec<u8>;
 }
 
+/// Calls the given macro with every type that implements both `IntoBlob` and `FromBlob`.
+macro_rules! for_each_blob_type {
+    ($m:ident) => {
+        $m!(
+            bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64,
+            String, Vec2, Vec3, Vec3A, Vec4, Quat, IVec2, IVec3, IVec4, UVec2, UVec3, UVec4,
+            DVec2, DVec3, DVec4, DQuat, BVec2, BVec3, BVec4, Mat2, Mat3, Mat4, Affine2, Affine3A,
+            Transform, GlobalTransform, Srgba, LinearRgba, Hsla, Color, Rect, IRect, URect
+        );
+    };
+}
+
+/// Encode a reflected value, if its type is one of the blob types.
+pub fn reflect_into_blob(value: &dyn PartialReflect) -> Option<Vec<u8>> {
+    macro_rules! encode {
+        ($($t:ty),*) => {
+            $(
+                if let Some(v) = value.try_downcast_ref::<$t>() {
+                    return Some(v.clone().into_blob());
+                }
+            )*
+        };
+    }
+    for_each_blob_type!(encode);
+
+    None
+}
+
+/// Decode a blob into a reflected value. Returns false, if the type of the target
+/// is not one of the blob types.
+pub fn reflect_from_blob(target: &mut dyn PartialReflect, value: &[u8]) -> ErmResult<bool> {
+    macro_rules! decode {
+        ($($t:ty),*) => {
+            $(
+                if let Some(v) = target.try_downcast_mut::<$t>() {
+                    // Fixed size types panic on short input, so check first.
+                    let expected = <$t>::default().into_blob().len();
+                    if value.len() < expected {
+                        return Err(ErmError::InvalidBlob(format!(
+                            "{} needs {} bytes, got {}",
+                            stringify!($t),
+                            expected,
+                            value.len()
+                        )));
+                    }
+
+                    validate_blob::<$t>(value)?;
+                    *v = <$t>::from_blob(value);
+                    return Ok(true);
+                }
+            )*
+        };
+    }
+    for_each_blob_type!(decode);
+
+    Ok(false)
+}
+
+/// Check the content of a blob, which cannot be decoded by `FromBlob` without panicking.
+fn validate_blob<T: 'static>(value: &[u8]) -> ErmResult<()> {
+    if TypeId::of::<T>() == TypeId::of::<Color>() && value[0] > COLOR_SPACES {
+        return Err(ErmError::InvalidBlob(format!(
+            "Unknown color space {}",
+            value[0]
+        )));
+    }
+
+    Ok(())
+}
+
+/// Create a new value of the type with the given id from a blob.
+/// Returns None, if the type is not one of the blob types.
+pub fn boxed_from_blob(
+    type_id: TypeId,
+    value: &[u8],
+) -> ErmResult<Option<Box<dyn PartialReflect>>> {
+    macro_rules! decode {
+        ($($t:ty),*) => {
+            $(
+                if type_id == TypeId::of::<$t>() {
+                    let mut result = <$t>::default();
+                    reflect_from_blob(&mut result, value)?;
+
+                    return Ok(Some(Box::new(result)));
+                }
+            )*
+        };
+    }
+    for_each_blob_type!(decode);
+
+    Ok(None)
+}
+
+/// Return the number of bytes used by a blob type with a fixed size.
+/// Returns None for types of variable size and types that are not blob types.
+pub fn fixed_blob_size(type_id: TypeId) -> Option<usize> {
+    if type_id == TypeId::of::<String>() {
+        return None;
+    }
+
+    macro_rules! size {
+        ($($t:ty),*) => {
+            $(
+                if type_id == TypeId::of::<$t>() {
+                    return Some(<$t>::default().into_blob().len());
+                }
+            )*
+        };
+    }
+    for_each_blob_type!(size);
+
+    None
+}
+
+/// Encode a list or array of fixed size blob types into one blob.
+/// The elements are stored back to back, without any separator.
+pub fn reflect_into_packed_blob(value: &dyn PartialReflect) -> ErmResult<Vec<u8>> {
+    let elements: Vec<&dyn PartialReflect> = match value.reflect_ref() {
+        ReflectRef::List(l) => l.iter().collect(),
+        ReflectRef::Array(a) => a.iter().collect(),
+        _ => {
+            return Err(ErmError::Unsupported(format!(
+                "{} is not a list or array",
+                value.reflect_type_path()
+            )))
+        }
+    };
+
+    let mut result = Vec::new();
+    for element in elements {
+        let item_type = element.get_represented_type_info().map(|x| x.type_id());
+        if item_type.and_then(fixed_blob_size).is_none() {
+            return Err(ErmError::Unsupported(format!(
+                "Elements of {} have no fixed size",
+                value.reflect_type_path()
+            )));
+        }
+
+        let Some(blob) = reflect_into_blob(element) else {
+            return Err(ErmError::Unsupported(format!(
+                "{} has no blob encoding",
+                element.reflect_type_path()
+            )));
+        };
+
+        result.extend(blob);
+    }
+
+    Ok(result)
+}
+
+/// Decode a packed blob into a list or array. Lists are replaced completely,
+/// arrays require the blob to hold exactly as many elements as the array.
+pub fn reflect_from_packed_blob(target: &mut dyn PartialReflect, value: &[u8]) -> ErmResult<()> {
+    let item_type = match target.get_represented_type_info() {
+        Some(TypeInfo::List(l)) => l.item_ty().id(),
+        Some(TypeInfo::Array(a)) => a.item_ty().id(),
+        _ => {
+            return Err(ErmError::Unsupported(format!(
+                "{} is not a list or array",
+                target.reflect_type_path()
+            )))
+        }
+    };
+
+    let Some(size) = fixed_blob_size(item_type) else {
+        return Err(ErmError::Unsupported(format!(
+            "Elements of {} have no fixed size",
+            target.reflect_type_path()
+        )));
+    };
+
+    if value.len() % size != 0 {
+        return Err(ErmError::InvalidBlob(format!(
+            "{} bytes are not a multiple of the element size {}",
+            value.len(),
+            size
+        )));
+    }
+
+    match target.reflect_mut() {
+        ReflectMut::List(l) => {
+            l.drain();
+            for chunk in value.chunks(size) {
+                let element = boxed_from_blob(item_type, chunk)?.expect("Not a blob type");
+                l.push(element);
+            }
+        }
+        ReflectMut::Array(a) => {
+            if a.len() * size != value.len() {
+                return Err(ErmError::InvalidBlob(format!(
+                    "Expected {} elements, got {}",
+                    a.len(),
+                    value.len() / size
+                )));
+            }
+
+            for (i, chunk) in value.chunks(size).enumerate() {
+                reflect_from_blob(a.get_mut(i).expect("Index out of range"), chunk)?;
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+// Primitives
+macro_rules! impl_blob_for_number {
+    ($($t:ty),*) => {
+        $(
+            impl IntoBlob for $t {
+                fn into_blob(self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+            }
+
+            impl FromBlob for $t {
+                fn from_blob(value: &[u8]) -> Self {
+                    <$t>::from_le_bytes(
+                        value[0..size_of::<$t>()]
+                            .try_into()
+                            .expect("Not enough bites"),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+impl_blob_for_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// usize and isize are stored with 64 bits, regardless of the platform.
+impl IntoBlob for usize {
+    fn into_blob(self) -> Vec<u8> {
+        (self as u64).into_blob()
+    }
+}
+
+impl FromBlob for usize {
+    fn from_blob(value: &[u8]) -> Self {
+        u64::from_blob(value) as usize
+    }
+}
+
+impl IntoBlob for isize {
+    fn into_blob(self) -> Vec<u8> {
+        (self as i64).into_blob()
+    }
+}
+
+impl FromBlob for isize {
+    fn from_blob(value: &[u8]) -> Self {
+        i64::from_blob(value) as isize
+    }
+}
+
+impl IntoBlob for bool {
+    fn into_blob(self) -> Vec<u8> {
+        vec![self as u8]
+    }
+}
+
+impl FromBlob for bool {
+    fn from_blob(value: &[u8]) -> Self {
+        value[0] != 0
+    }
+}
+
+impl IntoBlob for String {
+    fn into_blob(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl FromBlob for String {
+    fn from_blob(value: &[u8]) -> Self {
+        String::from_utf8_lossy(value).into_owned()
+    }
+}
+
 // Vec
 impl IntoBlob for Vec2 {
     fn into_blob(self) -> Vec<u8> {
@@ -281,9 +570,331 @@ impl FromBlob for IRect {
     }
 }
 
+// Helpers for types made up of many components of the same kind.
+fn f32s_into_blob(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn f32s_from_blob<const N: usize>(value: &[u8]) -> [f32; N] {
+    std::array::from_fn(|i| {
+        f32::from_le_bytes(value[i * 4..i * 4 + 4].try_into().expect("Not enough bites"))
+    })
+}
+
+fn f64s_into_blob(values: &[f64]) -> Vec<u8> {
+    values.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn f64s_from_blob<const N: usize>(value: &[u8]) -> [f64; N] {
+    std::array::from_fn(|i| {
+        f64::from_le_bytes(value[i * 8..i * 8 + 8].try_into().expect("Not enough bites"))
+    })
+}
+
+fn bools_from_blob<const N: usize>(value: &[u8]) -> [bool; N] {
+    std::array::from_fn(|i| value[i] != 0)
+}
+
+// Vec3A
+impl IntoBlob for Vec3A {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_array())
+    }
+}
+
+impl FromBlob for Vec3A {
+    fn from_blob(value: &[u8]) -> Self {
+        Vec3A::from_array(f32s_from_blob(value))
+    }
+}
+
+// DVec
+impl IntoBlob for DVec2 {
+    fn into_blob(self) -> Vec<u8> {
+        f64s_into_blob(&self.to_array())
+    }
+}
+
+impl FromBlob for DVec2 {
+    fn from_blob(value: &[u8]) -> Self {
+        DVec2::from_array(f64s_from_blob(value))
+    }
+}
+
+impl IntoBlob for DVec3 {
+    fn into_blob(self) -> Vec<u8> {
+        f64s_into_blob(&self.to_array())
+    }
+}
+
+impl FromBlob for DVec3 {
+    fn from_blob(value: &[u8]) -> Self {
+        DVec3::from_array(f64s_from_blob(value))
+    }
+}
+
+impl IntoBlob for DVec4 {
+    fn into_blob(self) -> Vec<u8> {
+        f64s_into_blob(&self.to_array())
+    }
+}
+
+impl FromBlob for DVec4 {
+    fn from_blob(value: &[u8]) -> Self {
+        DVec4::from_array(f64s_from_blob(value))
+    }
+}
+
+// DQuat
+impl IntoBlob for DQuat {
+    fn into_blob(self) -> Vec<u8> {
+        f64s_into_blob(&self.to_array())
+    }
+}
+
+impl FromBlob for DQuat {
+    fn from_blob(value: &[u8]) -> Self {
+        DQuat::from_array(f64s_from_blob(value))
+    }
+}
+
+// BVec
+impl IntoBlob for BVec2 {
+    fn into_blob(self) -> Vec<u8> {
+        vec![self.x as u8, self.y as u8]
+    }
+}
+
+impl FromBlob for BVec2 {
+    fn from_blob(value: &[u8]) -> Self {
+        BVec2::from_array(bools_from_blob(value))
+    }
+}
+
+impl IntoBlob for BVec3 {
+    fn into_blob(self) -> Vec<u8> {
+        vec![self.x as u8, self.y as u8, self.z as u8]
+    }
+}
+
+impl FromBlob for BVec3 {
+    fn from_blob(value: &[u8]) -> Self {
+        BVec3::from_array(bools_from_blob(value))
+    }
+}
+
+impl IntoBlob for BVec4 {
+    fn into_blob(self) -> Vec<u8> {
+        vec![self.x as u8, self.y as u8, self.z as u8, self.w as u8]
+    }
+}
+
+impl FromBlob for BVec4 {
+    fn from_blob(value: &[u8]) -> Self {
+        BVec4::from_array(bools_from_blob(value))
+    }
+}
+
+// Matrices are stored column major.
+impl IntoBlob for Mat2 {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_cols_array())
+    }
+}
+
+impl FromBlob for Mat2 {
+    fn from_blob(value: &[u8]) -> Self {
+        Mat2::from_cols_array(&f32s_from_blob(value))
+    }
+}
+
+impl IntoBlob for Mat3 {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_cols_array())
+    }
+}
+
+impl FromBlob for Mat3 {
+    fn from_blob(value: &[u8]) -> Self {
+        Mat3::from_cols_array(&f32s_from_blob(value))
+    }
+}
+
+impl IntoBlob for Mat4 {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_cols_array())
+    }
+}
+
+impl FromBlob for Mat4 {
+    fn from_blob(value: &[u8]) -> Self {
+        Mat4::from_cols_array(&f32s_from_blob(value))
+    }
+}
+
+// Affine
+impl IntoBlob for Affine2 {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_cols_array())
+    }
+}
+
+impl FromBlob for Affine2 {
+    fn from_blob(value: &[u8]) -> Self {
+        Affine2::from_cols_array(&f32s_from_blob(value))
+    }
+}
+
+impl IntoBlob for Affine3A {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_cols_array())
+    }
+}
+
+impl FromBlob for Affine3A {
+    fn from_blob(value: &[u8]) -> Self {
+        Affine3A::from_cols_array(&f32s_from_blob(value))
+    }
+}
+
+// Transform
+impl IntoBlob for Transform {
+    fn into_blob(self) -> Vec<u8> {
+        [
+            self.translation.into_blob(),
+            self.rotation.into_blob(),
+            self.scale.into_blob(),
+        ]
+        .concat()
+    }
+}
+
+impl FromBlob for Transform {
+    fn from_blob(value: &[u8]) -> Self {
+        Transform {
+            translation: Vec3::from_blob(&value[0..12]),
+            rotation: Quat::from_blob(&value[12..28]),
+            scale: Vec3::from_blob(&value[28..40]),
+        }
+    }
+}
+
+impl IntoBlob for GlobalTransform {
+    fn into_blob(self) -> Vec<u8> {
+        self.affine().into_blob()
+    }
+}
+
+impl FromBlob for GlobalTransform {
+    fn from_blob(value: &[u8]) -> Self {
+        GlobalTransform::from(Affine3A::from_blob(value))
+    }
+}
+
+// URect
+impl IntoBlob for URect {
+    fn into_blob(self) -> Vec<u8> {
+        [
+            u32::to_le_bytes(self.min.x),
+            u32::to_le_bytes(self.min.y),
+            u32::to_le_bytes(self.max.x),
+            u32::to_le_bytes(self.max.y),
+        ]
+        .concat()
+    }
+}
+
+impl FromBlob for URect {
+    fn from_blob(value: &[u8]) -> Self {
+        let x1 = u32::from_le_bytes(value[0..4].try_into().expect("Not enough bites"));
+        let y1 = u32::from_le_bytes(value[4..8].try_into().expect("Not enough bites"));
+        let x2 = u32::from_le_bytes(value[8..12].try_into().expect("Not enough bites"));
+        let y2 = u32::from_le_bytes(value[12..16].try_into().expect("Not enough bites"));
+
+        URect::new(x1, y1, x2, y2)
+    }
+}
+
+// Colors
+impl IntoBlob for LinearRgba {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_f32_array())
+    }
+}
+
+impl FromBlob for LinearRgba {
+    fn from_blob(value: &[u8]) -> Self {
+        LinearRgba::from_f32_array(f32s_from_blob(value))
+    }
+}
+
+impl IntoBlob for Hsla {
+    fn into_blob(self) -> Vec<u8> {
+        f32s_into_blob(&self.to_f32_array())
+    }
+}
+
+impl FromBlob for Hsla {
+    fn from_blob(value: &[u8]) -> Self {
+        Hsla::from_f32_array(f32s_from_blob(value))
+    }
+}
+
+/// The highest tag of a color space. The color enum is stored as one byte naming the color
+/// space followed by its four components.
+const COLOR_SPACES: u8 = 9;
+
+impl IntoBlob for Color {
+    fn into_blob(self) -> Vec<u8> {
+        let (space, components) = match self {
+            Color::Srgba(c) => (0u8, c.to_f32_array()),
+            Color::LinearRgba(c) => (1, c.to_f32_array()),
+            Color::Hsla(c) => (2, c.to_f32_array()),
+            Color::Hsva(c) => (3, c.to_f32_array()),
+            Color::Hwba(c) => (4, c.to_f32_array()),
+            Color::Laba(c) => (5, c.to_f32_array()),
+            Color::Lcha(c) => (6, c.to_f32_array()),
+            Color::Oklaba(c) => (7, c.to_f32_array()),
+            Color::Oklcha(c) => (8, c.to_f32_array()),
+            Color::Xyza(c) => (9, c.to_f32_array()),
+        };
+
+        [vec![space], f32s_into_blob(&components)].concat()
+    }
+}
+
+/// Blobs naming an unknown color space decode to the default color,
+/// `reflect_from_blob` rejects them.
+impl FromBlob for Color {
+    fn from_blob(value: &[u8]) -> Self {
+        let components = f32s_from_blob(&value[1..]);
+
+        match value[0] {
+            0 => Color::Srgba(Srgba::from_f32_array(components)),
+            1 => Color::LinearRgba(LinearRgba::from_f32_array(components)),
+            2 => Color::Hsla(Hsla::from_f32_array(components)),
+            3 => Color::Hsva(Hsva::from_f32_array(components)),
+            4 => Color::Hwba(Hwba::from_f32_array(components)),
+            5 => Color::Laba(Laba::from_f32_array(components)),
+            6 => Color::Lcha(Lcha::from_f32_array(components)),
+            7 => Color::Oklaba(Oklaba::from_f32_array(components)),
+            8 => Color::Oklcha(Oklcha::from_f32_array(components)),
+            9 => Color::Xyza(Xyza::from_f32_array(components)),
+            _ => Color::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{FromBlob, IntoBlob};
+    use super::{
+        reflect_from_blob, reflect_from_packed_blob, reflect_into_blob, reflect_into_packed_blob,
+        FromBlob, IntoBlob,
+    };
+    use bevy::math::{
+        Affine2, Affine3A, BVec2, BVec3, BVec4, DQuat, DVec2, DVec3, DVec4, Mat2, Mat3, Mat4,
+        Vec3A,
+    };
     use bevy::prelude::*;
 
     #[test]
@@ -488,4 +1099,198 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_vec3a() {
+        for x in 1..10 {
+            for y in 1..10 {
+                for z in 1..10 {
+                    let subject = Vec3A::new(x as f32, y as f32, z as f32);
+                    let blob = subject.into_blob();
+                    let test = Vec3A::from_blob(&blob);
+
+                    assert_eq!(subject, test);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dvec() {
+        for x in 1..10 {
+            for y in 1..10 {
+                let subject = DVec2::new(x as f64 * 0.1, y as f64);
+                assert_eq!(subject, DVec2::from_blob(&subject.into_blob()));
+
+                let subject = DVec3::new(x as f64, y as f64 * 0.1, -1.0);
+                assert_eq!(subject, DVec3::from_blob(&subject.into_blob()));
+
+                let subject = DVec4::new(x as f64, y as f64, 0.5, -1.0);
+                assert_eq!(subject, DVec4::from_blob(&subject.into_blob()));
+
+                let subject = DQuat::from_xyzw(x as f64, y as f64, 0.5, -1.0);
+                assert_eq!(subject, DQuat::from_blob(&subject.into_blob()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bvec() {
+        for x in [true, false] {
+            for y in [true, false] {
+                let subject = BVec2::new(x, y);
+                assert_eq!(subject, BVec2::from_blob(&subject.into_blob()));
+
+                let subject = BVec3::new(x, y, !x);
+                assert_eq!(subject, BVec3::from_blob(&subject.into_blob()));
+
+                let subject = BVec4::new(x, y, !x, !y);
+                assert_eq!(subject, BVec4::from_blob(&subject.into_blob()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_matrices() {
+        for x in 1..10 {
+            let subject = Mat2::from_cols_array(&[x as f32, 2.0, 3.0, 4.0]);
+            assert_eq!(subject, Mat2::from_blob(&subject.into_blob()));
+
+            let subject = Mat3::from_rotation_z(x as f32);
+            assert_eq!(subject, Mat3::from_blob(&subject.into_blob()));
+
+            let subject = Mat4::from_scale_rotation_translation(
+                Vec3::splat(x as f32),
+                Quat::from_rotation_y(x as f32),
+                Vec3::new(1.0, 2.0, x as f32),
+            );
+            assert_eq!(subject, Mat4::from_blob(&subject.into_blob()));
+
+            let subject = Affine2::from_angle_translation(x as f32, Vec2::new(1.0, x as f32));
+            assert_eq!(subject, Affine2::from_blob(&subject.into_blob()));
+
+            let subject = Affine3A::from_rotation_translation(
+                Quat::from_rotation_x(x as f32),
+                Vec3::new(x as f32, 2.0, 3.0),
+            );
+            assert_eq!(subject, Affine3A::from_blob(&subject.into_blob()));
+        }
+    }
+
+    #[test]
+    fn test_transform() {
+        for x in 1..10 {
+            for y in 1..10 {
+                let subject = Transform::from_xyz(x as f32, y as f32, 1.0)
+                    .with_rotation(Quat::from_rotation_z(y as f32))
+                    .with_scale(Vec3::splat(x as f32));
+                let blob = subject.into_blob();
+                let test = Transform::from_blob(&blob);
+
+                assert_eq!(subject, test);
+
+                let subject = GlobalTransform::from(subject);
+                let blob = subject.into_blob();
+                let test = GlobalTransform::from_blob(&blob);
+
+                assert_eq!(subject, test);
+            }
+        }
+    }
+
+    #[test]
+    fn test_urect() {
+        for x in 1..10 {
+            for y in 1..10 {
+                for z in 1..10 {
+                    for w in 1..10 {
+                        let subject = URect::new(x, y, z, w);
+                        let blob = subject.into_blob();
+                        let test = URect::from_blob(&blob);
+
+                        assert_eq!(subject, test);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_colors() {
+        for x in 1..10 {
+            let value = x as f32 * 0.1;
+
+            let subject = LinearRgba::new(value, 0.2, 0.3, 1.0);
+            assert_eq!(subject, LinearRgba::from_blob(&subject.into_blob()));
+
+            let subject = Hsla::new(value * 360.0, 0.5, value, 0.5);
+            assert_eq!(subject, Hsla::from_blob(&subject.into_blob()));
+
+            // The color enum has to keep its color space.
+            for subject in [
+                Color::srgba(value, 0.2, 0.3, 1.0),
+                Color::linear_rgba(value, 0.2, 0.3, 1.0),
+                Color::hsla(value * 360.0, 0.5, value, 0.5),
+                Color::hsva(value * 360.0, 0.5, value, 0.5),
+                Color::hwba(value * 360.0, 0.5, value, 0.5),
+                Color::laba(value, 0.2, 0.3, 1.0),
+                Color::lcha(value, 0.2, 0.3, 1.0),
+                Color::oklaba(value, 0.2, 0.3, 1.0),
+                Color::oklcha(value, 0.2, 0.3, 1.0),
+                Color::xyza(value, 0.2, 0.3, 1.0),
+            ] {
+                assert_eq!(subject, Color::from_blob(&subject.into_blob()));
+            }
+        }
+
+        // Unknown color spaces decode to the default color instead of panicking.
+        let mut blob = Color::BLACK.into_blob();
+        blob[0] = 42;
+        assert_eq!(Color::default(), Color::from_blob(&blob));
+    }
+
+    #[test]
+    fn test_reflect_blob() {
+        let subject = Transform::from_xyz(1.0, 2.0, 3.0);
+        let blob = reflect_into_blob(&subject).unwrap();
+
+        let mut test = Transform::default();
+        assert!(reflect_from_blob(&mut test, &blob).unwrap());
+        assert_eq!(subject, test);
+
+        // Short blobs are reported instead of panicking.
+        assert!(reflect_from_blob(&mut test, &blob[0..8]).is_err());
+
+        // So are colors with an unknown color space.
+        let mut blob = Color::WHITE.into_blob();
+        blob[0] = 10;
+        let mut test = Color::default();
+        assert!(matches!(
+            reflect_from_blob(&mut test, &blob),
+            Err(crate::error::ErmError::InvalidBlob(_))
+        ));
+    }
+
+    #[test]
+    fn test_packed_blob() {
+        let subject = vec![Vec3::X, Vec3::Y, Vec3::new(1.0, 2.0, 3.0)];
+        let blob = reflect_into_packed_blob(&subject).unwrap();
+        assert_eq!(blob.len(), 36);
+
+        let mut test: Vec<Vec3> = vec![Vec3::ZERO];
+        reflect_from_packed_blob(&mut test, &blob).unwrap();
+        assert_eq!(subject, test);
+
+        let subject = [1u16, 2, 3, 4];
+        let blob = reflect_into_packed_blob(&subject).unwrap();
+        let mut test = [0u16; 4];
+        reflect_from_packed_blob(&mut test, &blob).unwrap();
+        assert_eq!(subject, test);
+
+        // Arrays must match in length, strings have no fixed size.
+        let mut wrong_length = [0u16; 3];
+        assert!(reflect_from_packed_blob(&mut wrong_length, &blob).is_err());
+        assert!(reflect_into_packed_blob(&vec!["a".to_owned()]).is_err());
+        assert!(reflect_from_packed_blob(&mut vec!["a".to_owned()], &blob).is_err());
+    }
 }
@@ -0,0 +1,537 @@
+use std::{any::TypeId, slice::from_ref};
+
+use bevy::reflect::{PartialReflect, ReflectRef, TypeRegistry};
+
+use crate::{
+    identity::{identity_of, Identity},
+    lazy::is_lazy,
+    prelude::{
+        to_row, Clock, ErmBackend, ErmError, ErmResult, ErmTypesRegistry, ReferentialAction, Row,
+        SqlValue, TableDefinition,
+    },
+    relations::select_in,
+    repository::{update_row, write_children},
+    row_mapping::{field_of, key_of, unwrap_option},
+    soft_delete::{self, is_deleted},
+    timestamps::{stamp_insert, stamp_update},
+};
+
+/// A value to write, together with the values it references and which have to be written first.
+struct Node<'v> {
+    table: &'v TableDefinition,
+    key: SqlValue,
+    value: &'v dyn PartialReflect,
+    dependencies: Vec<usize>,
+}
+
+/// The rows written or deleted, and the types of the tables whose rows the referential
+/// actions of the database may have changed along with them.
+#[derive(Default)]
+pub(crate) struct Changes {
+    pub(crate) rows: Vec<Identity>,
+    pub(crate) types: Vec<TypeId>,
+}
+
+impl Changes {
+    /// Add the written or deleted row.
+    pub(crate) fn add(&mut self, table: &TableDefinition, row: &Row) {
+        self.rows
+            .push(Identity(table.ty.id(), identity_of(table, row)));
+    }
+
+    /// Add the types of the tables referencing the table with an action taken,
+    /// once its rows are updated or deleted.
+    pub(crate) fn add_acting(
+        &mut self,
+        registry: &ErmTypesRegistry,
+        table: &TableDefinition,
+        deleted: bool,
+    ) {
+        for other in registry.tables() {
+            let acting = other.columns().into_iter().any(|column| {
+                let action = match deleted {
+                    true => column.on_delete(),
+                    false => column.on_update(),
+                };
+
+                matches!(
+                    action,
+                    ReferentialAction::Cascade | ReferentialAction::SetNull
+                ) && registry
+                    .referenced_key_of(column)
+                    .is_ok_and(|(target, _)| target.sql_name == table.sql_name)
+            });
+
+            if acting && !self.types.contains(&other.ty.id()) {
+                self.types.push(other.ty.id());
+            }
+        }
+    }
+}
+
+/// Writes and deletes graphs of values, following the relations marked with `@Cascade`.
+/// The reference graph of the ERM-Registry decides the order of the statements, all statements
+/// of a graph run in a single transaction.
+pub struct GraphWriter<'a> {
+    db: &'a dyn ErmBackend,
+    registry: &'a ErmTypesRegistry,
+    type_registry: &'a TypeRegistry,
+    clock: Clock,
+}
+
+impl<'a> GraphWriter<'a> {
+    pub fn new(
+        db: &'a dyn ErmBackend,
+        registry: &'a ErmTypesRegistry,
+        type_registry: &'a TypeRegistry,
+    ) -> Self {
+        GraphWriter {
+            db,
+            registry,
+            type_registry,
+            clock: Clock::system(),
+        }
+    }
+
+    /// Take the time written to timestamps and soft delete columns from the clock.
+    pub fn with_clock(mut self, clock: &Clock) -> Self {
+        self.clock = clock.clone();
+        self
+    }
+
+    /// Insert or update the value and the values referenced by relations cascading saves.
+    /// Referenced values are written before the values referencing them, values reached more
+    /// than once are written once. Values referencing each other cannot be ordered and are
+    /// rejected with `ErmError::InvalidValue`, before anything is written.
+    pub fn save(&self, value: &dyn PartialReflect, table: &TableDefinition) -> ErmResult<()> {
+        self.save_tracked(value, table, &mut Changes::default())
+    }
+
+    /// Save the value like `save` and add the rows written to the changes.
+    pub(crate) fn save_tracked(
+        &self,
+        value: &dyn PartialReflect,
+        table: &TableDefinition,
+        changes: &mut Changes,
+    ) -> ErmResult<()> {
+        let mut nodes = Vec::new();
+        self.collect(value, table, &mut nodes)?;
+        let order = order(&nodes)?;
+
+        let now = self.clock.now();
+        self.db.transaction(|db| {
+            for node in order.into_iter().map(|x| &nodes[x]) {
+                let mut row = to_row(node.value, node.table, self.registry, self.type_registry)?;
+                let mut updated = row.clone();
+                stamp_update(node.table, &mut updated, now);
+                if !update_row(db, node.table, &updated)? {
+                    stamp_insert(node.table, &mut row, now);
+                    db.insert(node.table, &row)?;
+                }
+                changes.add(node.table, &row);
+                changes.add_acting(self.registry, node.table, false);
+
+                write_children(
+                    db,
+                    node.value,
+                    &row,
+                    node.table,
+                    self.registry,
+                    self.type_registry,
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Delete the row with the given key and the rows referenced by relations cascading deletes.
+    /// Referenced rows are deleted after the row referencing them, so deleting fails, if they
+    /// are still referenced by other rows. Tables with a soft delete column mark their rows as
+    /// deleted instead. Returns false, if there is no row with the key or it was deleted before.
+    pub fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool> {
+        self.delete_tracked(table, key, &mut Changes::default())
+    }
+
+    /// Delete the row like `delete` and add the rows deleted to the changes.
+    pub(crate) fn delete_tracked(
+        &self,
+        table: &TableDefinition,
+        key: &Row,
+        changes: &mut Changes,
+    ) -> ErmResult<bool> {
+        self.db.transaction(|db| {
+            let Some(row) = db
+                .select_by_key(table, key)?
+                .filter(|x| !is_deleted(table, x))
+            else {
+                return Ok(false);
+            };
+
+            self.delete_row(db, table, &row, self.clock.now(), &mut Vec::new(), changes)
+        })
+    }
+
+    /// Add the value and the values it references to the nodes, unless it was added before.
+    /// Returns the index of the value's node.
+    fn collect<'v>(
+        &self,
+        value: &'v dyn PartialReflect,
+        table: &'v TableDefinition,
+        nodes: &mut Vec<Node<'v>>,
+    ) -> ErmResult<usize>
+    where
+        'a: 'v,
+    {
+        let key = key_of(value, table, self.registry, self.type_registry)?;
+        if let Some(index) = nodes
+            .iter()
+            .position(|x| x.table.sql_name == table.sql_name && x.key == key)
+        {
+            return Ok(index);
+        }
+
+        let index = nodes.len();
+        nodes.push(Node {
+            table,
+            key,
+            value,
+            dependencies: Vec::new(),
+        });
+
+        for (column, target) in self.registry.references(table)? {
+            if !column.cascades_save() {
+                continue;
+            }
+
+            for referenced in referenced_values(field_of(value, table, column)?) {
+                let dependency = self.collect(referenced, target, nodes)?;
+                nodes[index].dependencies.push(dependency);
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Delete the row, then the rows its relations cascade to. Rows are deleted once,
+    /// so rows referencing each other end the recursion.
+    fn delete_row(
+        &self,
+        db: &dyn ErmBackend,
+        table: &TableDefinition,
+        row: &Row,
+        now: i64,
+        deleted: &mut Vec<(String, SqlValue)>,
+        changes: &mut Changes,
+    ) -> ErmResult<bool> {
+        let Some(key) = table
+            .key_columns()
+            .first()
+            .and_then(|x| row.get(&x.sql_name))
+            .cloned()
+        else {
+            return Err(ErmError::MissingColumn(
+                table.sql_name.clone(),
+                "key".to_owned(),
+            ));
+        };
+
+        let id = (table.sql_name.clone(), key);
+        if deleted.contains(&id) {
+            return Ok(false);
+        }
+        deleted.push(id.clone());
+
+        // Read the referenced rows first, the elements of child tables go with the row.
+        let mut targets = Vec::new();
+        for (column, target) in self.registry.references(table)? {
+            if !column.cascades_delete() {
+                continue;
+            }
+
+            let keys: Vec<SqlValue> = match column.child_table() {
+                Some(child) => db
+                    .select_children(child, from_ref(&id.1))?
+                    .into_iter()
+                    .filter_map(|x| x.get(&child.value_column.sql_name).cloned())
+                    .collect(),
+                None => row.get(&column.sql_name).cloned().into_iter().collect(),
+            };
+            let keys: Vec<SqlValue> = keys.into_iter().filter(|x| !x.is_null()).collect();
+            if keys.is_empty() {
+                continue;
+            }
+
+            let (_, key_column) = self.registry.referenced_key_of(column)?;
+            targets.push((
+                target,
+                select_in(db, target, &key_column.sql_name, &keys, self.registry)?,
+            ));
+        }
+
+        let result = soft_delete::delete_row(db, table, row, now)?;
+        changes.add(table, row);
+        // Soft deletes only change the deletion mark, which is never referenced.
+        if table.soft_delete_column().is_none() {
+            changes.add_acting(self.registry, table, true);
+        }
+        for (target, rows) in targets {
+            for row in rows.iter() {
+                self.delete_row(db, target, row, now, deleted, changes)?;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Return the values held by a relation field: the elements of a list, the value of an option
+/// or a loaded lazy relation, or the field itself.
+fn referenced_values(field: &dyn PartialReflect) -> Vec<&dyn PartialReflect> {
+    match field.reflect_ref() {
+        ReflectRef::List(list) => list.iter().filter_map(referenced_value).collect(),
+        _ => referenced_value(field).into_iter().collect(),
+    }
+}
+
+fn referenced_value(value: &dyn PartialReflect) -> Option<&dyn PartialReflect> {
+    if !value.get_represented_type_info().is_some_and(is_lazy) {
+        return unwrap_option(value);
+    }
+
+    let ReflectRef::Struct(strct) = value.reflect_ref() else {
+        return None;
+    };
+
+    strct.field("value").and_then(unwrap_option)
+}
+
+/// Order the nodes, so every node follows the nodes it depends on.
+fn order(nodes: &[Node]) -> ErmResult<Vec<usize>> {
+    fn visit(
+        index: usize,
+        nodes: &[Node],
+        visiting: &mut Vec<usize>,
+        result: &mut Vec<usize>,
+    ) -> ErmResult<()> {
+        if result.contains(&index) {
+            return Ok(());
+        }
+
+        let node = &nodes[index];
+        if visiting.contains(&index) {
+            return Err(ErmError::InvalidValue(
+                node.table.sql_name.clone(),
+                node.table
+                    .key_columns()
+                    .first()
+                    .map(|x| x.sql_name.clone())
+                    .unwrap_or_default(),
+                format!(
+                    "The value with key {} is part of a reference cycle and cannot be ordered",
+                    node.key
+                ),
+            ));
+        }
+
+        visiting.push(index);
+        for dependency in node.dependencies.iter() {
+            visit(*dependency, nodes, visiting, result)?;
+        }
+        visiting.pop();
+
+        result.push(index);
+        Ok(())
+    }
+
+    let mut result = Vec::new();
+    for index in 0..nodes.len() {
+        visit(index, nodes, &mut Vec::new(), &mut result)?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("GameModes"))]
+    struct GameMode {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Cascade::all())]
+        pub spawn_points: Vec<SpawnPoint>,
+        #[reflect(@Cascade::save())]
+        pub reward: Option<Reward>,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("SpawnPoints"))]
+    struct SpawnPoint {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@MaxLength::new(8))]
+        pub name: String,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Rewards"))]
+    struct Reward {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub points: i32,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Regions"), no_field_bounds)]
+    struct Region {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Cascade::all())]
+        pub neighbours: Vec<Region>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct Invalid {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Cascade::all())]
+        pub name: String,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry
+            .register_type::<SpawnPoint>(&app_registry)
+            .is_some());
+        assert!(registry.register_type::<Reward>(&app_registry).is_some());
+        assert!(registry.register_type::<GameMode>(&app_registry).is_some());
+        assert!(registry.register_type::<Region>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn spawn_point(id: i64) -> SpawnPoint {
+        SpawnPoint {
+            id,
+            name: format!("Spawn {}", id),
+        }
+    }
+
+    fn game_mode() -> GameMode {
+        GameMode {
+            id: 1,
+            spawn_points: vec![spawn_point(1), spawn_point(2)],
+            reward: Some(Reward { id: 1, points: 10 }),
+        }
+    }
+
+    fn save(
+        game_modes: Repository<GameMode>,
+        spawn_points: Repository<SpawnPoint>,
+        rewards: Repository<Reward>,
+    ) {
+        let mut value = game_mode();
+        game_modes.save(&value).unwrap();
+        let found = game_modes.find(1i64).unwrap().unwrap();
+        assert_eq!(found.spawn_points, value.spawn_points);
+        assert_eq!(spawn_points.all().unwrap().len(), 2);
+        assert_eq!(rewards.find(1i64).unwrap(), value.reward);
+
+        // Saving again updates the rows, the same value may be reached twice.
+        value.spawn_points = vec![spawn_point(3), spawn_point(1), spawn_point(3)];
+        value.spawn_points[1].name = "First".to_owned();
+        value.reward.as_mut().unwrap().points = 20;
+        game_modes.save(&value).unwrap();
+        let found = game_modes.find(1i64).unwrap().unwrap();
+        assert_eq!(found.spawn_points, value.spawn_points);
+        assert_eq!(spawn_points.all().unwrap().len(), 3);
+        assert_eq!(rewards.find(1i64).unwrap(), value.reward);
+
+        // Deleting cascades to the spawn points, but not to the reward.
+        assert!(game_modes.delete_by_key(1i64).unwrap());
+        assert!(!game_modes.delete_by_key(1i64).unwrap());
+        assert_eq!(spawn_points.all().unwrap(), vec![spawn_point(2)]);
+        assert_eq!(rewards.all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cascading_save() {
+        let mut app = prepare_app();
+        app.add_systems(PostStartup, save);
+
+        app.update();
+    }
+
+    fn rollback(game_modes: Repository<GameMode>, spawn_points: Repository<SpawnPoint>) {
+        // The name of the second spawn point is too long, nothing is written.
+        let mut value = game_mode();
+        value.spawn_points[1].name = "Far too long".to_owned();
+        assert!(game_modes.save(&value).is_err());
+        assert!(game_modes.all().unwrap().is_empty());
+        assert!(spawn_points.all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn failing_save() {
+        let mut app = prepare_app();
+        app.add_systems(PostStartup, rollback);
+
+        app.update();
+    }
+
+    fn cycle(regions: Repository<Region>) {
+        let value = Region {
+            id: 1,
+            neighbours: vec![Region {
+                id: 2,
+                neighbours: vec![Region::default(), Region { id: 1, ..default() }],
+            }],
+        };
+
+        assert!(matches!(
+            regions.save(&value),
+            Err(ErmError::InvalidValue(_, _, _))
+        ));
+        assert!(regions.all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reference_cycle() {
+        let mut app = prepare_app();
+        app.add_systems(PostStartup, cycle);
+
+        app.update();
+    }
+
+    #[test]
+    fn invalid_cascade() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.register_type::<Invalid>();
+
+        let app_registry = app.world().resource::<AppTypeRegistry>();
+        let mut registry = ErmTypesRegistry::default();
+        assert!(matches!(
+            registry.try_register_type::<Invalid>(app_registry),
+            Err(ErmError::InvalidMapping(_))
+        ));
+    }
+
+    fn prepare_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<GameMode>();
+        app.register_type::<Region>();
+        app.add_systems(Startup, startup);
+
+        app
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    mem::discriminant,
+    sync::{Arc, Mutex, MutexGuard, RwLock, Weak},
+};
+
+use bevy::{prelude::Resource, reflect::Reflect};
+
+use crate::{
+    graph::Changes,
+    prelude::{Row, SqlValue, TableDefinition},
+    sql_value::Number,
+};
+
+/// A value shared by everyone who loaded the same row.
+pub type Shared<T> = Arc<RwLock<T>>;
+
+/// Identifies a row by the type of its table and the values of its key columns.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Identity(pub(crate) TypeId, pub(crate) Vec<SqlValue>);
+
+// Keys holding NaN never equal themselves, their rows are not shared.
+impl Eq for Identity {}
+
+impl Hash for Identity {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        for value in self.1.iter() {
+            hash_value(value, state);
+        }
+    }
+}
+
+/// A single value used as key of a hash map, e.g. to find rows by the value of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyValue(pub(crate) SqlValue);
+
+// Values holding NaN never equal themselves, they are never found.
+impl Eq for KeyValue {}
+
+impl Hash for KeyValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+/// Hash the value consistently with its equality, numbers by their value.
+fn hash_value<H: Hasher>(value: &SqlValue, state: &mut H) {
+    if let Some(number) = value.number() {
+        match number.integral() {
+            Number::Integer(v) => v.hash(state),
+            Number::Float(v) => v.to_bits().hash(state),
+        }
+        return;
+    }
+
+    discriminant(value).hash(state);
+    match value {
+        SqlValue::Text(v) | SqlValue::Json(v) => v.hash(state),
+        SqlValue::Blob(v) => v.hash(state),
+        SqlValue::Bool(v) => v.hash(state),
+        _ => {}
+    }
+}
+
+/// Maps the rows loaded through repositories to the values shared for them, so loading
+/// a row twice returns the same value. The map holds no values itself: once the last
+/// handle of a value is dropped, the row is loaded again.
+#[derive(Resource, Default)]
+pub struct IdentityMap {
+    entries: Mutex<HashMap<Identity, Weak<dyn Any + Send + Sync>>>,
+}
+
+impl IdentityMap {
+    pub fn new() -> Self {
+        IdentityMap::default()
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<Identity, Weak<dyn Any + Send + Sync>>> {
+        // The entries stay consistent, even if a thread panicked holding the lock.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Return the value shared for the row of T with the given key, if it is still in use.
+    pub fn get<T: Reflect>(&self, key: &[SqlValue]) -> Option<Shared<T>> {
+        self.entries()
+            .get(&Identity(TypeId::of::<T>(), key.to_vec()))
+            .and_then(Weak::upgrade)
+            .and_then(|x| x.downcast::<RwLock<T>>().ok())
+    }
+
+    /// Share the loaded values, each given with the key of its row. Values of rows which
+    /// are shared already are dropped in favour of the shared value.
+    pub fn share<T: Reflect>(&self, values: Vec<(Vec<SqlValue>, T)>) -> Vec<Shared<T>> {
+        let mut entries = self.entries();
+        entries.retain(|_, x| x.strong_count() > 0);
+
+        let mut result = Vec::new();
+        for (key, value) in values {
+            let identity = Identity(TypeId::of::<T>(), key);
+            let shared = entries
+                .get(&identity)
+                .and_then(Weak::upgrade)
+                .and_then(|x| x.downcast::<RwLock<T>>().ok());
+
+            let shared = shared.unwrap_or_else(|| {
+                let shared = Arc::new(RwLock::new(value));
+                let any: Arc<dyn Any + Send + Sync> = shared.clone();
+                entries.insert(identity, Arc::downgrade(&any));
+                shared
+            });
+            result.push(shared);
+        }
+
+        result
+    }
+
+    /// Forget the row of T with the given key. Values shared before are kept by their
+    /// holders, the next load of the row returns a new value.
+    pub fn remove<T: Reflect>(&self, key: &[SqlValue]) {
+        self.entries()
+            .remove(&Identity(TypeId::of::<T>(), key.to_vec()));
+    }
+
+    /// Forget the changed rows and all rows of the types changed along with them.
+    pub(crate) fn forget(&self, changes: &Changes) {
+        let mut entries = self.entries();
+        for identity in changes.rows.iter() {
+            entries.remove(identity);
+        }
+
+        if !changes.types.is_empty() {
+            entries.retain(|x, _| !changes.types.contains(&x.0));
+        }
+    }
+
+    /// Forget all rows.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// The number of rows whose values are still in use.
+    pub fn len(&self) -> usize {
+        self.entries()
+            .values()
+            .filter(|x| x.strong_count() > 0)
+            .count()
+    }
+
+    /// Returns true, if no shared value is in use.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Return the values of the key columns of a row, converted to the types of the columns.
+pub(crate) fn identity_of(table: &TableDefinition, row: &Row) -> Vec<SqlValue> {
+    table
+        .key_columns()
+        .iter()
+        .flat_map(|x| x.sql_columns())
+        .map(|(name, sql_type)| {
+            row.get(&name)
+                .cloned()
+                .unwrap_or_default()
+                .into_type(&sql_type)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Zombies"))]
+    struct Zombie {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub target: Lazy<Player>,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        assert!(registry.register_type::<Zombie>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn player(id: i64, name: &str) -> Player {
+        Player {
+            id,
+            name: name.to_owned(),
+        }
+    }
+
+    fn identities(
+        players: Repository<Player>,
+        zombies: Repository<Zombie>,
+        identity_map: Res<IdentityMap>,
+    ) {
+        players.insert(&player(1, "Ann")).unwrap();
+        players.insert(&player(2, "Bob")).unwrap();
+        for id in [1, 2] {
+            let zombie = Zombie {
+                id,
+                target: Lazy::new(1i64),
+            };
+            zombies.insert(&zombie).unwrap();
+        }
+
+        // Both zombies share the player they target.
+        let loaded = zombies.all().unwrap();
+        let first = players.find_shared(loaded[0].target.key().clone()).unwrap();
+        let second = players.find_shared(loaded[1].target.key().clone()).unwrap();
+        assert!(Arc::ptr_eq(
+            first.as_ref().unwrap(),
+            second.as_ref().unwrap()
+        ));
+
+        let first = first.unwrap();
+        first.write().unwrap().name = "Anne".to_owned();
+        let all = players
+            .query_shared(ErmQuery::new().order_by("id"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&all[0], &first));
+        assert_eq!(all[0].read().unwrap().name, "Anne");
+        assert_eq!(*all[1].read().unwrap(), player(2, "Bob"));
+        assert_eq!(identity_map.len(), 2);
+
+        // Writing a row drops its value from the map, the next load reads the row.
+        players.update(&player(1, "Anna")).unwrap();
+        let updated = players.find_shared(1i64).unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&updated, &first));
+        assert_eq!(updated.read().unwrap().name, "Anna");
+
+        // Writing other rows keeps the shared value.
+        players.save(&player(3, "Cid")).unwrap();
+        zombies.delete_by_key(2i64).unwrap();
+        let found = players.find_shared(1i64).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&found, &updated));
+
+        players.delete_by_key(2i64).unwrap();
+        assert!(players.find_shared(2i64).unwrap().is_none());
+
+        // Values nobody holds are loaded again.
+        drop((first, updated, found, all));
+        assert!(identity_map.is_empty());
+    }
+
+    #[test]
+    fn identity_map() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Zombie>();
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, identities);
+
+        app.update();
+    }
+
+    #[test]
+    fn float_keys() {
+        let identity_map = IdentityMap::new();
+        let shared = identity_map.share(vec![(vec![SqlValue::Float(0.5)], 1u32)]);
+        assert!(Arc::ptr_eq(
+            &identity_map.get::<u32>(&[SqlValue::Float(0.5)]).unwrap(),
+            &shared[0]
+        ));
+        assert!(identity_map.get::<u32>(&[SqlValue::Integer(0)]).is_none());
+
+        // Types are kept apart.
+        assert!(identity_map.get::<i32>(&[SqlValue::Float(0.5)]).is_none());
+        identity_map.remove::<u32>(&[SqlValue::Float(0.5)]);
+        assert!(identity_map.get::<u32>(&[SqlValue::Float(0.5)]).is_none());
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::reflect::{
+    serde::{TypedReflectDeserializer, TypedReflectSerializer},
+    PartialReflect, TypeRegistry,
+};
+use serde::de::DeserializeSeed;
+
+use crate::error::{ErmError, ErmResult};
+
+/// Serialize a value to json using bevy's reflect serializer.
+/// The json contains the plain value, without any type information,
+/// so it stays readable for humans and ad-hoc sql.
+pub fn to_json(value: &dyn PartialReflect, registry: &TypeRegistry) -> ErmResult<String> {
+    let serializer = TypedReflectSerializer::new(value, registry);
+
+    serde_json::to_string(&serializer).map_err(|e| ErmError::InvalidJson(e.to_string()))
+}
+
+/// Deserialize json into an existing value. The type of the target is used
+/// to read the json, so it must be registered with the type registry.
+pub fn apply_json(
+    target: &mut dyn PartialReflect,
+    value: &str,
+    registry: &TypeRegistry,
+) -> ErmResult<()> {
+    let Some(type_info) = target.get_represented_type_info() else {
+        return Err(ErmError::Unsupported(format!(
+            "{} has no type info",
+            target.reflect_type_path()
+        )));
+    };
+
+    let Some(registration) = registry.get(type_info.type_id()) else {
+        return Err(ErmError::Unsupported(format!(
+            "{} is not registered with the type registry",
+            type_info.type_path()
+        )));
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_str(value);
+    let result = TypedReflectDeserializer::new(registration, registry)
+        .deserialize(&mut deserializer)
+        .map_err(|e| ErmError::InvalidJson(e.to_string()))?;
+
+    target
+        .try_apply(result.as_ref())
+        .map_err(|e| ErmError::InvalidJson(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+    use bevy::utils::HashMap;
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Default)]
+    struct Inventory {
+        slots: Vec<u32>,
+        items: HashMap<String, u32>,
+        mode: Mode,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    enum Mode {
+        #[default]
+        Normal,
+        Locked(String),
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut registry = TypeRegistry::default();
+        registry.register::<Inventory>();
+
+        let subject = Inventory {
+            slots: vec![1, 2, 3],
+            items: HashMap::from_iter([("sword".to_owned(), 1), ("arrow".to_owned(), 20)]),
+            mode: Mode::Locked("quest".to_owned()),
+        };
+
+        let json = to_json(&subject.slots, &registry).unwrap();
+        assert_eq!(json, "[1,2,3]");
+
+        let json = to_json(&subject, &registry).unwrap();
+        let mut test = Inventory::default();
+        apply_json(&mut test, &json, &registry).unwrap();
+        assert_eq!(subject, test);
+
+        assert!(matches!(
+            apply_json(&mut test, "{ not json", &registry),
+            Err(ErmError::InvalidJson(_))
+        ));
+    }
+}
@@ -0,0 +1,363 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    prelude::{error, App, Component, Entity, Event, EventReader, EventWriter, Plugin, Update},
+    reflect::{
+        prelude::ReflectDefault, DynamicEnum, DynamicVariant, PartialReflect, Reflect, ReflectMut,
+        ReflectRef, TypeInfo, TypePath,
+    },
+};
+
+use crate::prelude::{ErmError, ErmResult, Repository, SqlValue};
+
+/// A relation which is loaded on demand. It holds the key of the referenced row and,
+/// once resolved, the referenced value. A null key is a relation to no row at all,
+/// which can be told apart from a relation that was not loaded yet.
+/// Fields of this type are lazy One2One relations, lists of it are lazy Many2Many relations.
+#[derive(Reflect, Component, Debug, Clone, PartialEq)]
+#[reflect(Default)]
+pub struct Lazy<T> {
+    key: SqlValue,
+    value: Option<T>,
+}
+
+impl<T> Default for Lazy<T> {
+    fn default() -> Self {
+        Lazy {
+            key: SqlValue::Null,
+            value: None,
+        }
+    }
+}
+
+impl<T> Lazy<T> {
+    /// A relation to the row with the given key, which is not loaded yet.
+    pub fn new(key: impl Into<SqlValue>) -> Self {
+        Lazy {
+            key: key.into(),
+            value: None,
+        }
+    }
+
+    /// A relation to the row with the given key, holding the value of the row.
+    pub fn loaded(key: impl Into<SqlValue>, value: T) -> Self {
+        Lazy {
+            key: key.into(),
+            value: Some(value),
+        }
+    }
+
+    /// The key of the referenced row.
+    pub fn key(&self) -> &SqlValue {
+        &self.key
+    }
+
+    /// Returns true, if the relation references no row.
+    pub fn is_null(&self) -> bool {
+        self.key.is_null()
+    }
+
+    /// Returns true, if the referenced value was loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// The referenced value, if it was loaded.
+    pub fn get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// The referenced value, if it was loaded.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.as_mut()
+    }
+
+    /// Set the loaded value.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+    }
+
+    /// Drop the loaded value, keeping the key.
+    pub fn unload(&mut self) {
+        self.value = None;
+    }
+}
+
+/// Returns true, if the type is a Lazy<T>.
+pub(crate) fn is_lazy(ty: &TypeInfo) -> bool {
+    let TypeInfo::Struct(s) = ty else {
+        return false;
+    };
+
+    s.generics().len() == 1
+        && s.type_path_table().module_path() == Some(module_path!())
+        && s.type_path_table().ident() == Some("Lazy")
+}
+
+/// Return the key held by a Lazy<T>, or None, if the value is not a Lazy<T>.
+pub(crate) fn lazy_key(value: &dyn PartialReflect) -> Option<SqlValue> {
+    if !value.get_represented_type_info().is_some_and(is_lazy) {
+        return None;
+    }
+
+    let ReflectRef::Struct(strct) = value.reflect_ref() else {
+        return None;
+    };
+
+    strct
+        .field("key")
+        .and_then(|x| x.try_downcast_ref::<SqlValue>())
+        .cloned()
+}
+
+/// Set the key of a Lazy<T> and drop its value, as the key might reference another row now.
+pub(crate) fn set_lazy_key(target: &mut dyn PartialReflect, key: &SqlValue) -> ErmResult<()> {
+    let ReflectMut::Struct(strct) = target.reflect_mut() else {
+        return Err(ErmError::Unsupported(
+            "Value is not a lazy relation".to_owned(),
+        ));
+    };
+
+    let Some(field) = strct
+        .field_mut("key")
+        .and_then(|x| x.try_downcast_mut::<SqlValue>())
+    else {
+        return Err(ErmError::Unsupported("Lazy relation has no key".to_owned()));
+    };
+    *field = key.clone();
+
+    if let Some(value) = strct.field_mut("value") {
+        value.apply(&DynamicEnum::new("None", DynamicVariant::Unit));
+    }
+
+    Ok(())
+}
+
+/// Ask for the Lazy<T> component of the entity to be resolved.
+#[derive(Event)]
+pub struct ResolveLazy<T> {
+    pub entity: Entity,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ResolveLazy<T> {
+    pub fn new(entity: Entity) -> Self {
+        ResolveLazy {
+            entity,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Sent, when the Lazy<T> component of the entity was resolved.
+#[derive(Event)]
+pub struct LazyLoaded<T> {
+    pub entity: Entity,
+
+    /// False, if the relation is null or the referenced row does not exist.
+    pub found: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+/// Resolve the Lazy<T> components of entities, when asked to by a ResolveLazy<T> event.
+pub fn resolve_lazy<T: Reflect + TypePath>(
+    mut requests: EventReader<ResolveLazy<T>>,
+    mut lazies: bevy::prelude::Query<&mut Lazy<T>>,
+    repository: Repository<T>,
+    mut loaded: EventWriter<LazyLoaded<T>>,
+) {
+    for request in requests.read() {
+        let Ok(mut lazy) = lazies.get_mut(request.entity) else {
+            error!("Entity {} has no lazy relation to resolve", request.entity);
+            continue;
+        };
+
+        match repository.resolve(&mut lazy) {
+            Ok(value) => {
+                loaded.send(LazyLoaded {
+                    entity: request.entity,
+                    found: value.is_some(),
+                    marker: PhantomData,
+                });
+            }
+            Err(e) => error!("The lazy relation could not be resolved: {}", e),
+        }
+    }
+}
+
+/// Adds the events and the system resolving Lazy<T> components.
+pub struct LazyPlugin<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for LazyPlugin<T> {
+    fn default() -> Self {
+        LazyPlugin(PhantomData)
+    }
+}
+
+impl<T: Reflect + TypePath> Plugin for LazyPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResolveLazy<T>>()
+            .add_event::<LazyLoaded<T>>()
+            .add_systems(Update, resolve_lazy::<T>);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Zombies"))]
+    struct Zombie {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub target: Lazy<Player>,
+        pub victims: Vec<Lazy<Player>>,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        assert!(registry.register_type::<Zombie>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn prepare_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Player>();
+        app.register_type::<Zombie>();
+        app.add_systems(Startup, startup);
+
+        app
+    }
+
+    fn player(id: i64, name: &str) -> Player {
+        Player {
+            id,
+            name: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn lazy_relations() {
+        let mut app = prepare_app();
+        app.update();
+
+        let registry = app.world().resource::<ErmTypesRegistry>();
+        let table = registry.get_table_definition("Zombies").unwrap();
+
+        let target = table.get("target").unwrap();
+        assert!(matches!(target.sql_type, SqlType::One2One(_, false)));
+        assert!(!target.is_eager());
+        assert!(!target.is_not_null());
+
+        let victims = table.get("victims").unwrap();
+        assert!(matches!(victims.sql_type, SqlType::Many2Many(_, false)));
+        assert!(!victims.is_eager());
+        assert!(victims.child_table().is_some());
+    }
+
+    fn resolve(players: Repository<Player>, zombies: Repository<Zombie>) {
+        players.insert(&player(1, "Ann")).unwrap();
+        players.insert(&player(2, "Bob")).unwrap();
+
+        let zombie = Zombie {
+            id: 1,
+            target: Lazy::loaded(1i64, player(1, "Ann")),
+            victims: vec![Lazy::new(2i64), Lazy::new(1i64)],
+        };
+        zombies.insert(&zombie).unwrap();
+        zombies
+            .insert(&Zombie {
+                id: 2,
+                ..Zombie::default()
+            })
+            .unwrap();
+
+        // Loading keeps the keys only.
+        let mut found = zombies.find(1i64).unwrap().unwrap();
+        assert_eq!(found.target, Lazy::new(1i64));
+        assert!(!found.target.is_loaded());
+        assert_eq!(found.victims, vec![Lazy::new(2i64), Lazy::new(1i64)]);
+
+        assert_eq!(
+            players.resolve(&mut found.target).unwrap(),
+            Some(&player(1, "Ann"))
+        );
+        assert!(found.target.is_loaded());
+        assert_eq!(
+            players.resolve(&mut found.victims[0]).unwrap(),
+            Some(&player(2, "Bob"))
+        );
+
+        // Null relations are not loaded.
+        let mut empty = zombies.find(2i64).unwrap().unwrap();
+        assert!(empty.target.is_null());
+        assert_eq!(players.resolve(&mut empty.target).unwrap(), None);
+        assert!(empty.victims.is_empty());
+    }
+
+    #[test]
+    fn resolve_relations() {
+        let mut app = prepare_app();
+        app.add_systems(PostStartup, resolve);
+
+        app.update();
+    }
+
+    #[derive(Resource)]
+    struct Entities(Entity, Entity);
+
+    fn spawn(mut commands: Commands, players: Repository<Player>) {
+        players.insert(&player(1, "Ann")).unwrap();
+
+        let found = commands.spawn(Lazy::<Player>::new(1i64)).id();
+        let missing = commands.spawn(Lazy::<Player>::new(7i64)).id();
+        commands.insert_resource(Entities(found, missing));
+    }
+
+    #[test]
+    fn resolve_components() {
+        let mut app = prepare_app();
+        app.add_plugins(LazyPlugin::<Player>::default());
+        app.add_systems(PostStartup, spawn);
+        app.update();
+
+        let entities = app.world().resource::<Entities>();
+        let (found, missing) = (entities.0, entities.1);
+        app.world_mut()
+            .send_event(ResolveLazy::<Player>::new(found));
+        app.world_mut()
+            .send_event(ResolveLazy::<Player>::new(missing));
+        app.update();
+
+        let lazy = app.world().get::<Lazy<Player>>(found).unwrap();
+        assert_eq!(lazy.get(), Some(&player(1, "Ann")));
+        assert!(!app
+            .world()
+            .get::<Lazy<Player>>(missing)
+            .unwrap()
+            .is_loaded());
+
+        let events = app.world().resource::<Events<LazyLoaded<Player>>>();
+        let loaded: Vec<(Entity, bool)> = events
+            .iter_current_update_events()
+            .map(|x| (x.entity, x.found))
+            .collect();
+        assert_eq!(loaded, vec![(found, true), (missing, false)]);
+    }
+}
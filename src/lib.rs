@@ -1,29 +1,88 @@
 mod attributes;
+mod backend;
+mod child_table;
 mod column_definition;
 mod constraints;
+mod dialect;
 mod erm_types_registry;
+mod error;
+mod framed_blob;
 mod from_blob;
+mod graph;
+mod identity;
+mod json;
+mod lazy;
+mod memory;
 mod plugin;
+mod query;
+mod relations;
+mod repository;
+mod row_mapping;
+mod soft_delete;
 mod sql_types;
+mod sql_value;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod table_definition;
+mod timestamps;
+mod tracking;
 
 pub mod prelude {
     pub use crate::plugin::BevyERMPlugin;
 
     pub use crate::erm_types_registry::ErmTypesRegistry;
 
+    pub use crate::backend::{BackendFactory, ErmBackend, ErmDatabase};
+    pub use crate::query::{col, query_as, Column, ErmQuery, Filter, Order, Select};
+    pub use crate::repository::{IntoKey, Repository};
+    pub use crate::graph::GraphWriter;
+    pub use crate::identity::{IdentityMap, Shared};
+    pub use crate::timestamps::Clock;
+    pub use crate::tracking::{Snapshots, DEFAULT_SNAPSHOT_CAPACITY};
+    pub use crate::lazy::{resolve_lazy, Lazy, LazyLoaded, LazyPlugin, ResolveLazy};
+    pub use crate::memory::MemoryDatabase;
+
     pub use crate::table_definition::TableDefinition;
     pub use crate::table_definition::TableName;
+    pub use crate::table_definition::SoftDelete;
 
+    pub use crate::attributes::Cascade;
     pub use crate::attributes::ColumnName;
+    pub use crate::attributes::CreatedAt;
+    pub use crate::attributes::Decompose;
+    pub use crate::attributes::ElementTable;
+    pub use crate::attributes::Framed;
+    pub use crate::attributes::Json;
     pub use crate::attributes::Key;
     pub use crate::attributes::MaxLength;
+    pub use crate::attributes::NotNull;
+    pub use crate::attributes::OnDelete;
+    pub use crate::attributes::OnUpdate;
+    pub use crate::attributes::PackedBlob;
     pub use crate::attributes::Reference;
     pub use crate::attributes::Unique;
+    pub use crate::attributes::UpdatedAt;
+    pub use crate::attributes::Version;
 
+    pub use crate::child_table::{ChildColumn, ChildTable};
     pub use crate::column_definition::ColumnDefinition;
-    pub use crate::constraints::FieldConstraint;
+    pub use crate::column_definition::ColumnStorage;
+    pub use crate::constraints::{FieldConstraint, ReferentialAction};
+    pub use crate::dialect::SqlDialect;
     pub use crate::sql_types::SqlType;
+    pub use crate::sql_value::{Row, SqlValue};
+
+    #[cfg(feature = "sqlite")]
+    pub use crate::sqlite::SqliteDatabase;
+
+    pub use crate::error::ErmError;
+    pub use crate::error::ErmResult;
+
+    pub use crate::framed_blob::*;
+    pub use crate::json::*;
+    pub use crate::row_mapping::{
+        apply_child_rows, apply_row, from_row, from_row_as, to_child_rows, to_row,
+    };
 
     pub use crate::from_blob::*;
 }
@@ -73,7 +132,7 @@ mod tests {
     fn prepare_app() -> App {
         let mut app = App::new();
         app.insert_resource(AppTypeRegistry::default());
-        app.add_plugins(BevyERMPlugin);
+        app.add_plugins(BevyERMPlugin::default());
 
         app.register_type::<Player>();
         app.register_type::<Zombie>();
@@ -280,4 +339,565 @@ mod tests {
 
         app.update();
     }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Saves"))]
+    struct SaveGame {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Framed)]
+        pub spawn: SpawnPoint,
+        #[reflect(@Framed)]
+        pub last_spawn: Option<SpawnPoint>,
+    }
+
+    fn framed_columns(erm_types_registry: ResMut<ErmTypesRegistry>) {
+        let table_def = erm_types_registry.get_table_definition("Saves").unwrap();
+
+        // Framed structs are stored as blob, even though SpawnPoint is a table.
+        let spawn = table_def.get("spawn").unwrap();
+        assert!(spawn.is_framed());
+        assert!(!spawn.is_eager());
+        assert_eq!(spawn.sql_type, SqlType::Blob(true));
+
+        let last_spawn = table_def.get("last_spawn").unwrap();
+        assert!(last_spawn.is_framed());
+        assert_eq!(last_spawn.sql_type, SqlType::Blob(false));
+
+        assert!(!table_def.get("id").unwrap().is_framed());
+    }
+
+    #[test]
+    fn framed_blob_columns() {
+        let mut app = prepare_app();
+        app.register_type::<SaveGame>();
+        app.add_systems(Startup, startup);
+        app.add_systems(
+            Startup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                erm_types_registry.register_type::<SaveGame>(bevy_types_registry.as_mut());
+            },
+        );
+        app.add_systems(PostStartup, framed_columns);
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Props"))]
+    struct Prop {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub transform: Transform,
+        pub tint: Option<Color>,
+        pub bounds: URect,
+    }
+
+    #[test]
+    fn math_types_as_blob() {
+        let mut app = prepare_app();
+        app.register_type::<Prop>();
+        app.register_type::<Transform>();
+        app.add_systems(
+            Startup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                erm_types_registry.register_type::<Prop>(bevy_types_registry.as_mut());
+            },
+        );
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Props").unwrap();
+
+                // Transform is a registered struct, but it is stored as blob and not as relation.
+                assert_eq!(
+                    table_def.get("transform").unwrap().sql_type,
+                    SqlType::Blob(true)
+                );
+                assert!(!table_def.get("transform").unwrap().is_eager());
+                assert_eq!(table_def.get("tint").unwrap().sql_type, SqlType::Blob(false));
+                assert_eq!(table_def.get("bounds").unwrap().sql_type, SqlType::Blob(true));
+            },
+        );
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Waypoints"))]
+    struct Waypoint {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Decompose)]
+        pub location: Vec3,
+        #[reflect(@Decompose)]
+        pub cell: Option<IVec2>,
+        pub rotation: Quat,
+    }
+
+    fn register_waypoint(
+        mut bevy_types_registry: ResMut<AppTypeRegistry>,
+        mut erm_types_registry: ResMut<ErmTypesRegistry>,
+    ) {
+        erm_types_registry.register_type::<Waypoint>(bevy_types_registry.as_mut());
+    }
+
+    #[test]
+    fn decomposed_columns() {
+        let mut app = prepare_app();
+        app.register_type::<Waypoint>();
+        app.add_systems(Startup, register_waypoint);
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Waypoints").unwrap();
+
+                let location = table_def.get("location").unwrap();
+                assert!(location.is_decomposed());
+                assert_eq!(
+                    location.sql_columns(),
+                    vec![
+                        ("location_x".to_owned(), SqlType::Float(32, true)),
+                        ("location_y".to_owned(), SqlType::Float(32, true)),
+                        ("location_z".to_owned(), SqlType::Float(32, true)),
+                    ]
+                );
+
+                let cell = table_def.get("cell").unwrap();
+                assert!(cell.is_decomposed());
+                assert_eq!(
+                    cell.sql_columns(),
+                    vec![
+                        ("cell_x".to_owned(), SqlType::Integer(32, false)),
+                        ("cell_y".to_owned(), SqlType::Integer(32, false)),
+                    ]
+                );
+
+                // Without attribute or policy, math types stay blobs.
+                let rotation = table_def.get("rotation").unwrap();
+                assert!(!rotation.is_decomposed());
+                assert_eq!(rotation.sql_columns().len(), 1);
+                assert_eq!(rotation.sql_type, SqlType::Blob(true));
+            },
+        );
+
+        app.update();
+    }
+
+    #[test]
+    fn decompose_policy() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default().with_decomposed_math_types());
+        app.register_type::<Waypoint>();
+        app.add_systems(Startup, register_waypoint);
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Waypoints").unwrap();
+
+                let rotation = table_def.get("rotation").unwrap();
+                assert!(rotation.is_decomposed());
+                assert_eq!(rotation.sql_columns().len(), 4);
+                assert_eq!(rotation.sql_columns()[3].0, "rotation_w");
+                assert_eq!(rotation.sql_type, SqlType::Float(32, true));
+            },
+        );
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    enum Difficulty {
+        #[default]
+        Easy,
+        Hard(u32),
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Quests"))]
+    struct Quest {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Json)]
+        pub tags: Vec<String>,
+        #[reflect(@Json)]
+        pub rewards: Option<bevy::utils::HashMap<String, u32>>,
+        pub difficulty: Difficulty,
+    }
+
+    fn register_quest(
+        mut bevy_types_registry: ResMut<AppTypeRegistry>,
+        mut erm_types_registry: ResMut<ErmTypesRegistry>,
+    ) {
+        erm_types_registry.register_type::<Quest>(bevy_types_registry.as_mut());
+    }
+
+    #[test]
+    fn json_columns() {
+        let mut app = prepare_app();
+        app.register_type::<Quest>();
+        app.add_systems(Startup, register_quest);
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Quests").unwrap();
+
+                assert_eq!(table_def.get("tags").unwrap().sql_type, SqlType::Json(true));
+                assert_eq!(
+                    table_def.get("rewards").unwrap().sql_type,
+                    SqlType::Json(false)
+                );
+
+                // Sqlite does not store enums as json by default.
+                assert_ne!(
+                    table_def.get("difficulty").unwrap().sql_type,
+                    SqlType::Json(true)
+                );
+            },
+        );
+
+        app.update();
+    }
+
+    #[test]
+    fn json_dialect_default() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default().with_dialect(SqlDialect::PostgreSql));
+        app.register_type::<Quest>();
+        app.add_systems(Startup, register_quest);
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Quests").unwrap();
+
+                assert_eq!(
+                    table_def.get("difficulty").unwrap().sql_type,
+                    SqlType::Json(true)
+                );
+                assert_eq!(
+                    table_def.get("id").unwrap().sql_type,
+                    SqlType::Integer(64, true)
+                );
+            },
+        );
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Heroes"))]
+    struct Hero {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub scores: Vec<i32>,
+        #[reflect(@ElementTable)]
+        pub titles: std::collections::VecDeque<String>,
+        #[reflect(@PackedBlob)]
+        pub path: Vec<Vec3>,
+        #[reflect(@PackedBlob)]
+        pub stats: [u8; 4],
+        #[reflect(@Json)]
+        pub nested: Vec<Vec<i32>>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct NestedList {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub nested: Vec<Vec<i32>>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct OptionalList {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub values: Option<Vec<i32>>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct PackedStrings {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@PackedBlob)]
+        pub values: Vec<String>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct TwoStorages {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@PackedBlob, @Json)]
+        pub values: Vec<i32>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct KeylessList {
+        pub values: Vec<i32>,
+    }
+
+    #[test]
+    fn value_collections() {
+        let mut app = prepare_app();
+        app.register_type::<Hero>();
+        app.add_systems(
+            Startup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                erm_types_registry
+                    .try_register_type::<Hero>(bevy_types_registry.as_mut())
+                    .unwrap();
+            },
+        );
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Heroes").unwrap();
+
+                // Lists of values default to child tables with an ordinal column.
+                let scores = table_def.get("scores").unwrap();
+                assert!(scores.sql_columns().is_empty());
+                let child = scores.child_table().unwrap();
+                assert_eq!(child.sql_name, "Heroes_scores");
+                assert_eq!(child.owner_column.sql_name, "owner_id");
+                assert_eq!(child.owner_column.sql_type, SqlType::Integer(64, true));
+                assert!(child.ordinal_column.is_some());
+                assert_eq!(child.value_column.sql_type, SqlType::Integer(32, true));
+
+                let titles = table_def.get("titles").unwrap().child_table().unwrap();
+                assert_eq!(titles.value_column.sql_type, SqlType::Text(true));
+                assert_eq!(table_def.child_tables().len(), 2);
+
+                let path = table_def.get("path").unwrap();
+                assert_eq!(path.storage, ColumnStorage::PackedBlob);
+                assert_eq!(path.sql_type, SqlType::Blob(true));
+                assert_eq!(
+                    table_def.get("stats").unwrap().storage,
+                    ColumnStorage::PackedBlob
+                );
+
+                assert_eq!(table_def.get("nested").unwrap().sql_type, SqlType::Json(true));
+            },
+        );
+
+        app.update();
+    }
+
+    #[test]
+    fn ambiguous_collections() {
+        let mut app = prepare_app();
+        app.register_type::<NestedList>();
+        app.register_type::<OptionalList>();
+        app.register_type::<PackedStrings>();
+        app.register_type::<TwoStorages>();
+        app.register_type::<KeylessList>();
+        app.add_systems(
+            Startup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let registry = bevy_types_registry.as_mut();
+
+                for result in [
+                    erm_types_registry.try_register_type::<NestedList>(registry),
+                    erm_types_registry.try_register_type::<OptionalList>(registry),
+                    erm_types_registry.try_register_type::<PackedStrings>(registry),
+                    erm_types_registry.try_register_type::<TwoStorages>(registry),
+                    erm_types_registry.try_register_type::<KeylessList>(registry),
+                ] {
+                    assert!(matches!(result, Err(ErmError::InvalidMapping(_))));
+                }
+
+                // Failed registrations do not leave a table behind.
+                assert!(erm_types_registry.get_table_definition("NestedList").is_none());
+                assert!(erm_types_registry
+                    .register_type::<OptionalList>(registry)
+                    .is_none());
+            },
+        );
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default, Hash, PartialEq, Eq)]
+    #[reflect(Default, Hash, PartialEq, @TableName::new("Items"))]
+    struct Item {
+        #[reflect(@Key)]
+        pub id: i64,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Backpacks"))]
+    struct Backpack {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub inventory: bevy::utils::HashMap<String, u32>,
+        pub unlocked: bevy::utils::HashSet<String>,
+        pub stacks: bevy::utils::HashMap<Item, u32>,
+        pub visited: bevy::utils::HashMap<u32, SpawnPoint>,
+        pub favorites: bevy::utils::HashSet<Item>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct OptionalMap {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub values: Option<bevy::utils::HashMap<String, u32>>,
+    }
+
+    #[test]
+    fn maps_and_sets() {
+        let mut app = prepare_app();
+        app.register_type::<Backpack>();
+        app.register_type::<OptionalMap>();
+        app.register_type::<Item>();
+        app.add_systems(Startup, startup);
+        app.add_systems(
+            Startup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let registry = bevy_types_registry.as_mut();
+                erm_types_registry.try_register_type::<Item>(registry).unwrap();
+                erm_types_registry
+                    .try_register_type::<Backpack>(registry)
+                    .unwrap();
+
+                assert!(matches!(
+                    erm_types_registry.try_register_type::<OptionalMap>(registry),
+                    Err(ErmError::InvalidMapping(_))
+                ));
+            },
+        );
+        app.add_systems(
+            PostStartup,
+            |erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let table_def = erm_types_registry.get_table_definition("Backpacks").unwrap();
+                assert_eq!(table_def.child_tables().len(), 5);
+
+                let inventory = table_def.get("inventory").unwrap().child_table().unwrap();
+                assert_eq!(inventory.sql_name, "Backpacks_inventory");
+                assert_eq!(inventory.owner_column.sql_type, SqlType::Integer(64, true));
+                assert!(inventory.ordinal_column.is_none());
+                let key = inventory.key_column.as_ref().unwrap();
+                assert_eq!(key.sql_type, SqlType::Text(true));
+                assert_eq!(
+                    inventory.value_column.sql_type,
+                    SqlType::UnsingedInteger(32, true)
+                );
+                assert_eq!(inventory.primary_key()[1].sql_name, "key");
+
+                let unlocked = table_def.get("unlocked").unwrap().child_table().unwrap();
+                assert!(unlocked.key_column.is_none());
+                assert_eq!(unlocked.value_column.sql_type, SqlType::Text(true));
+                assert_eq!(unlocked.primary_key()[1].sql_name, "value");
+
+                // Registered tables on either side are references to the table.
+                let stacks = table_def.get("stacks").unwrap().child_table().unwrap();
+                assert!(stacks.key_column.as_ref().unwrap().is_reference());
+                assert!(!stacks.value_column.is_reference());
+
+                let visited = table_def.get("visited").unwrap().child_table().unwrap();
+                assert!(!visited.key_column.as_ref().unwrap().is_reference());
+                assert!(visited.value_column.is_reference());
+
+                let favorites = table_def.get("favorites").unwrap().child_table().unwrap();
+                let SqlType::One2One(type_id, _) = favorites.value_column.sql_type else {
+                    panic!("Expected a reference");
+                };
+                assert_eq!(
+                    erm_types_registry
+                        .get_table_definition_by_type_id(type_id)
+                        .unwrap()
+                        .sql_name,
+                    "Items"
+                );
+            },
+        );
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Pets"))]
+    struct Pet {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(
+            @OnDelete::new(ReferentialAction::Cascade),
+            @OnUpdate::new(ReferentialAction::Restrict)
+        )]
+        pub owner: Option<Player>,
+        #[reflect(@OnDelete::new(ReferentialAction::SetNull))]
+        pub home: Option<SpawnPoint>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct RequiredSetNull {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@OnDelete::new(ReferentialAction::SetNull))]
+        pub spawn: SpawnPoint,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct ListAction {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@OnDelete::new(ReferentialAction::Cascade))]
+        pub spawn_points: Vec<SpawnPoint>,
+    }
+
+    #[test]
+    fn referential_actions() {
+        let mut app = prepare_app();
+        app.register_type::<Pet>();
+        app.register_type::<RequiredSetNull>();
+        app.register_type::<ListAction>();
+        app.add_systems(Startup, startup);
+        app.add_systems(
+            PostStartup,
+            |mut bevy_types_registry: ResMut<AppTypeRegistry>,
+             mut erm_types_registry: ResMut<ErmTypesRegistry>| {
+                let registry = bevy_types_registry.as_mut();
+                assert!(erm_types_registry.register_type::<Pet>(registry).is_some());
+
+                let table_def = erm_types_registry.get_table_definition("Pets").unwrap();
+                let owner = table_def.get("owner").unwrap();
+                assert_eq!(owner.on_delete(), ReferentialAction::Cascade);
+                assert_eq!(owner.on_update(), ReferentialAction::Restrict);
+                let home = table_def.get("home").unwrap();
+                assert_eq!(home.on_delete(), ReferentialAction::SetNull);
+                assert_eq!(home.on_update(), ReferentialAction::NoAction);
+
+                let statements = SqlDialect::MySql
+                    .create_table(table_def, &erm_types_registry)
+                    .unwrap();
+                assert!(statements[0].contains(
+                    "`owner` BIGINT REFERENCES `Players` (`id`) ON DELETE CASCADE ON UPDATE RESTRICT,"
+                ));
+                assert!(statements[0]
+                    .contains("`home` BIGINT REFERENCES `SpawnPoints` (`id`) ON DELETE SET NULL,"));
+
+                // Not null relations cannot be set to null, lists have no foreign key of their own.
+                for result in [
+                    erm_types_registry.try_register_type::<RequiredSetNull>(registry),
+                    erm_types_registry.try_register_type::<ListAction>(registry),
+                ] {
+                    assert!(matches!(result, Err(ErmError::InvalidMapping(_))));
+                }
+            },
+        );
+
+        app.update();
+    }
 }
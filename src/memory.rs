@@ -0,0 +1,1011 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Condvar, Mutex, MutexGuard},
+    thread::{self, ThreadId},
+};
+
+use bevy::prelude::Resource;
+
+use crate::{
+    backend::{check_upsert, split_keys, upsert_columns},
+    identity::KeyValue,
+    prelude::{
+        ChildTable, ErmBackend, ErmError, ErmResult, ErmTypesRegistry, Filter, ReferentialAction,
+        Row, Select, SqlDialect, SqlType, SqlValue, TableDefinition,
+    },
+};
+
+/// A column of a table held in memory, with the constraints to enforce.
+#[derive(Debug, Clone)]
+struct MemoryColumn {
+    name: String,
+    sql_type: SqlType,
+    not_null: bool,
+    unique: bool,
+    max_length: Option<usize>,
+
+    /// The table and column holding the referenced value.
+    reference: Option<(String, String)>,
+
+    /// The actions taken, once the referenced row is deleted or its referenced value changes.
+    on_delete: ReferentialAction,
+    on_update: ReferentialAction,
+}
+
+impl MemoryColumn {
+    fn new(name: &str, sql_type: &SqlType) -> Self {
+        MemoryColumn {
+            name: name.to_owned(),
+            sql_type: sql_type.clone(),
+            not_null: sql_type.is_not_null(),
+            unique: false,
+            max_length: None,
+            reference: None,
+            on_delete: ReferentialAction::NoAction,
+            on_update: ReferentialAction::NoAction,
+        }
+    }
+}
+
+/// The slots of the rows by the values they hold in the columns of the index.
+/// Rows holding null in any of the columns are left out, as null equals nothing.
+#[derive(Debug, Clone)]
+struct MemoryIndex {
+    columns: Vec<String>,
+    slots: HashMap<Vec<KeyValue>, BTreeSet<usize>>,
+}
+
+impl MemoryIndex {
+    fn new(columns: Vec<String>) -> Self {
+        MemoryIndex {
+            columns,
+            slots: HashMap::new(),
+        }
+    }
+
+    fn values_of(&self, row: &Row) -> Option<Vec<KeyValue>> {
+        self.columns
+            .iter()
+            .map(|x| row.get(x).filter(|x| !x.is_null()).cloned().map(KeyValue))
+            .collect()
+    }
+
+    fn add(&mut self, slot: usize, row: &Row) {
+        if let Some(values) = self.values_of(row) {
+            self.slots.entry(values).or_default().insert(slot);
+        }
+    }
+
+    fn remove(&mut self, slot: usize, row: &Row) {
+        let Some(values) = self.values_of(row) else {
+            return;
+        };
+
+        if let Some(slots) = self.slots.get_mut(&values) {
+            slots.remove(&slot);
+            if slots.is_empty() {
+                self.slots.remove(&values);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MemoryTable {
+    name: String,
+    columns: Vec<MemoryColumn>,
+    keys: Vec<String>,
+
+    /// The rows by their slot. Deleted rows leave an empty slot, until the table is compacted.
+    rows: Vec<Option<Row>>,
+    empty: usize,
+
+    /// Indexes of the key, the unique columns and the columns referencing other tables.
+    indexes: Vec<MemoryIndex>,
+}
+
+impl MemoryTable {
+    fn new(name: &str, columns: Vec<MemoryColumn>, keys: Vec<String>) -> Self {
+        let mut indexes = vec![MemoryIndex::new(keys.clone())];
+        for column in columns.iter() {
+            if column.unique || column.reference.is_some() {
+                indexes.push(MemoryIndex::new(vec![column.name.clone()]));
+            }
+        }
+
+        MemoryTable {
+            name: name.to_owned(),
+            columns,
+            keys,
+            rows: Vec::new(),
+            empty: 0,
+            indexes,
+        }
+    }
+
+    fn column(&self, name: &str) -> ErmResult<&MemoryColumn> {
+        self.columns.iter().find(|x| x.name == name).ok_or_else(|| {
+            ErmError::Database(format!("Table {} has no column {}", self.name, name))
+        })
+    }
+
+    fn key_of(&self, row: &Row) -> Vec<SqlValue> {
+        self.keys
+            .iter()
+            .map(|x| row.get(x).cloned().unwrap_or_default())
+            .collect()
+    }
+
+    fn key_columns(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
+    /// Return the row in the slot.
+    fn row(&self, slot: usize) -> ErmResult<&Row> {
+        self.rows
+            .get(slot)
+            .and_then(Option::as_ref)
+            .ok_or_else(|| ErmError::Database(format!("Table {} has no row {}", self.name, slot)))
+    }
+
+    /// Return the slots of all rows.
+    fn all_slots(&self) -> Vec<usize> {
+        (0..self.rows.len())
+            .filter(|x| self.rows[*x].is_some())
+            .collect()
+    }
+
+    /// Return the slots of the rows holding the values in the columns, in the order
+    /// the rows were inserted. Null values match no row.
+    fn slots(&self, columns: &[&str], values: &[SqlValue]) -> Vec<usize> {
+        if values.iter().any(SqlValue::is_null) {
+            return Vec::new();
+        }
+
+        if let Some(index) = self.indexes.iter().find(|x| x.columns == columns) {
+            let values: Vec<KeyValue> = values.iter().cloned().map(KeyValue).collect();
+            return index
+                .slots
+                .get(&values)
+                .map(|x| x.iter().copied().collect())
+                .unwrap_or_default();
+        }
+
+        self.all_slots()
+            .into_iter()
+            .filter(|x| {
+                let row = self.rows[*x].as_ref();
+                columns
+                    .iter()
+                    .zip(values)
+                    .all(|(c, v)| row.and_then(|x| x.get(c)) == Some(v))
+            })
+            .collect()
+    }
+
+    /// Return the slot of the row with the given key.
+    fn position(&self, key: &[SqlValue]) -> Option<usize> {
+        self.slots(&self.key_columns(), key).first().copied()
+    }
+
+    /// Return the slots of the rows which may match the typed filter, using an index
+    /// where possible. The rows still have to be matched against the filter.
+    fn candidates(&self, filter: &Filter) -> Vec<usize> {
+        let indexed = |column: &str| self.indexes.iter().any(|x| x.columns == [column]);
+
+        match filter {
+            Filter::Eq(column, value) if indexed(column) => {
+                self.slots(&[column], std::slice::from_ref(value))
+            }
+            Filter::In(column, values) if indexed(column) => {
+                let slots: BTreeSet<usize> = values
+                    .iter()
+                    .flat_map(|x| self.slots(&[column], std::slice::from_ref(x)))
+                    .collect();
+                slots.into_iter().collect()
+            }
+            Filter::And(filters) => {
+                let equal: HashMap<&str, &SqlValue> = filters
+                    .iter()
+                    .filter_map(|x| match x {
+                        Filter::Eq(column, value) => Some((column.as_str(), value)),
+                        _ => None,
+                    })
+                    .collect();
+
+                for index in self.indexes.iter().filter(|x| !x.columns.is_empty()) {
+                    let values: Option<Vec<SqlValue>> = index
+                        .columns
+                        .iter()
+                        .map(|x| equal.get(x.as_str()).map(|x| (*x).clone()))
+                        .collect();
+                    if let Some(values) = values {
+                        let columns: Vec<&str> = index.columns.iter().map(String::as_str).collect();
+                        return self.slots(&columns, &values);
+                    }
+                }
+
+                filters
+                    .iter()
+                    .map(|x| self.candidates(x))
+                    .min_by_key(Vec::len)
+                    .unwrap_or_else(|| self.all_slots())
+            }
+            _ => self.all_slots(),
+        }
+    }
+
+    /// Put the row into the slot, or empty the slot, and return the row it held before.
+    /// Empty slots at the end are dropped right away.
+    fn put(&mut self, slot: usize, row: Option<Row>) -> Option<Row> {
+        while self.rows.len() <= slot {
+            self.rows.push(None);
+            self.empty += 1;
+        }
+
+        let previous = self.rows[slot].take();
+        if let Some(previous) = &previous {
+            for index in self.indexes.iter_mut() {
+                index.remove(slot, previous);
+            }
+            self.empty += 1;
+        }
+
+        if let Some(row) = &row {
+            for index in self.indexes.iter_mut() {
+                index.add(slot, row);
+            }
+            self.empty -= 1;
+        }
+
+        self.rows[slot] = row;
+        while matches!(self.rows.last(), Some(None)) {
+            self.rows.pop();
+            self.empty -= 1;
+        }
+
+        previous
+    }
+
+    /// Drop the empty slots, if they make up most of the table. The slots of rows change.
+    fn compact(&mut self) {
+        if self.empty < 64 || self.empty * 2 < self.rows.len() {
+            return;
+        }
+
+        let rows: Vec<Row> = std::mem::take(&mut self.rows)
+            .into_iter()
+            .flatten()
+            .collect();
+        for index in self.indexes.iter_mut() {
+            index.slots.clear();
+        }
+
+        self.empty = 0;
+        for (slot, row) in rows.into_iter().enumerate() {
+            self.put(slot, Some(row));
+        }
+    }
+
+    /// Convert the values of a row to the types of the columns. Missing columns are null.
+    fn typed(&self, row: &Row) -> ErmResult<Row> {
+        for name in row.names() {
+            self.column(name)?;
+        }
+
+        let mut result = Row::new();
+        for column in self.columns.iter() {
+            let value = row.get(&column.name).cloned().unwrap_or_default();
+            result.push(&column.name, value.into_type(&column.sql_type));
+        }
+
+        Ok(result)
+    }
+
+    /// Convert the values of a filter to the types of the columns.
+    fn typed_filter(&self, filter: &Filter) -> ErmResult<Filter> {
+        filter.map(
+            &|name| Ok(self.column(name)?.name.clone()),
+            &|name, value| Ok(value.clone().into_type(&self.column(name)?.sql_type)),
+        )
+    }
+}
+
+#[derive(Default)]
+struct MemoryState {
+    tables: HashMap<String, MemoryTable>,
+
+    /// The rows replaced by the changes of the open transactions, each with its table and slot.
+    /// Putting them back in reverse order undoes the changes.
+    log: Vec<(String, usize, Option<Row>)>,
+
+    /// The length of the log, when each open transaction began.
+    savepoints: Vec<usize>,
+
+    /// The thread which began the open transactions.
+    owner: Option<ThreadId>,
+}
+
+impl MemoryState {
+    fn table(&self, name: &str) -> ErmResult<&MemoryTable> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| ErmError::Database(format!("No such table: {}", name)))
+    }
+
+    fn table_mut(&mut self, name: &str) -> ErmResult<&mut MemoryTable> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| ErmError::Database(format!("No such table: {}", name)))
+    }
+
+    /// Put the row into the slot of the table, or empty the slot, and log the change.
+    fn put(&mut self, table: &str, slot: usize, row: Option<Row>) -> ErmResult<Option<Row>> {
+        let previous = self.table_mut(table)?.put(slot, row);
+        self.log.push((table.to_owned(), slot, previous.clone()));
+
+        Ok(previous)
+    }
+
+    /// Undo the changes logged after the given length of the log.
+    fn undo(&mut self, length: usize) {
+        while self.log.len() > length {
+            let Some((table, slot, row)) = self.log.pop() else {
+                break;
+            };
+
+            if let Some(table) = self.tables.get_mut(&table) {
+                table.put(slot, row);
+            }
+        }
+    }
+
+    /// Forget the log, once no transaction is open, and compact the tables.
+    fn settle(&mut self) {
+        if !self.savepoints.is_empty() {
+            return;
+        }
+
+        self.log.clear();
+        for table in self.tables.values_mut() {
+            table.compact();
+        }
+    }
+
+    /// Check the constraints of a typed row. The row in the slot is the row being
+    /// replaced, it is ignored when checking for duplicates.
+    fn check(&self, table: &MemoryTable, row: &Row, replaced: Option<usize>) -> ErmResult<()> {
+        let duplicate = |columns: &[&str]| {
+            let values: Vec<SqlValue> = columns
+                .iter()
+                .map(|x| row.get(x).cloned().unwrap_or_default())
+                .collect();
+
+            table
+                .slots(columns, &values)
+                .into_iter()
+                .any(|x| Some(x) != replaced)
+        };
+
+        for column in table.columns.iter() {
+            let value = row.get(&column.name).unwrap_or(&SqlValue::Null);
+            if value.is_null() {
+                if column.not_null {
+                    return Err(ErmError::Database(format!(
+                        "NOT NULL constraint failed: {}.{}",
+                        table.name, column.name
+                    )));
+                }
+
+                continue;
+            }
+
+            if let (Some(max), SqlValue::Text(text)) = (column.max_length, value) {
+                if text.chars().count() > max {
+                    return Err(ErmError::Database(format!(
+                        "Value of {}.{} exceeds the maximum length of {}",
+                        table.name, column.name, max
+                    )));
+                }
+            }
+
+            if column.unique && duplicate(&[&column.name]) {
+                return Err(ErmError::Database(format!(
+                    "UNIQUE constraint failed: {}.{}",
+                    table.name, column.name
+                )));
+            }
+
+            if let Some((target, key)) = &column.reference {
+                let target = self.table(target)?;
+                if target.slots(&[key], std::slice::from_ref(value)).is_empty() {
+                    return Err(ErmError::Database(format!(
+                        "FOREIGN KEY constraint failed: {}.{}",
+                        table.name, column.name
+                    )));
+                }
+            }
+        }
+
+        if duplicate(&table.key_columns()) {
+            return Err(ErmError::Database(format!(
+                "UNIQUE constraint failed: key of {}",
+                table.name
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Return the columns referencing the table, each with the name of its table.
+    fn referencing(&self, table: &str) -> Vec<(String, MemoryColumn)> {
+        let mut result = Vec::new();
+        for other in self.tables.values() {
+            for column in other.columns.iter() {
+                if column.reference.as_ref().is_some_and(|x| x.0 == table) {
+                    result.push((other.name.clone(), column.clone()));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Return the slot of the first row holding the value in the column.
+    fn find(&self, table: &str, column: &str, value: &SqlValue) -> ErmResult<Option<usize>> {
+        Ok(self
+            .table(table)?
+            .slots(&[column], std::slice::from_ref(value))
+            .first()
+            .copied())
+    }
+
+    /// Fail, as the change of a row still referenced by another row is not allowed.
+    fn referenced(table: &str, other: &str, column: &MemoryColumn) -> ErmResult<()> {
+        Err(ErmError::Database(format!(
+            "FOREIGN KEY constraint failed: {}.{} references {}",
+            other, column.name, table
+        )))
+    }
+
+    /// Check a row and add it to the table.
+    fn insert(&mut self, table: &str, row: &Row) -> ErmResult<()> {
+        let target = self.table(table)?;
+        let row = target.typed(row)?;
+        self.check(target, &row, None)?;
+
+        let slot = target.rows.len();
+        self.put(table, slot, Some(row))?;
+        Ok(())
+    }
+
+    /// Replace the row in the slot by a typed row and apply the update actions
+    /// of the rows referencing a changed value.
+    fn update(&mut self, table: &str, slot: usize, row: Row) -> ErmResult<()> {
+        let target = self.table(table)?;
+        self.check(target, &row, Some(slot))?;
+        let old = target.row(slot)?.clone();
+        self.put(table, slot, Some(row.clone()))?;
+
+        for (other, column) in self.referencing(table) {
+            let Some((_, key)) = &column.reference else {
+                continue;
+            };
+
+            let before = old.get(key).unwrap_or(&SqlValue::Null);
+            let after = row.get(key).unwrap_or(&SqlValue::Null);
+            if before.is_null() || before == after {
+                continue;
+            }
+
+            let value = match column.on_update {
+                ReferentialAction::Cascade => after.clone(),
+                ReferentialAction::SetNull => SqlValue::Null,
+                _ => {
+                    if self.find(&other, &column.name, before)?.is_some() {
+                        return Self::referenced(table, &other, &column);
+                    }
+
+                    continue;
+                }
+            };
+
+            while let Some(slot) = self.find(&other, &column.name, before)? {
+                let mut changed = self.table(&other)?.row(slot)?.clone();
+                changed.push(&column.name, value.clone());
+                self.update(&other, slot, changed)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete a row and apply the delete actions of the rows referencing it.
+    fn delete(&mut self, table: &str, slot: usize) -> ErmResult<()> {
+        let row = self.table(table)?.row(slot)?.clone();
+        self.put(table, slot, None)?;
+
+        for (other, column) in self.referencing(table) {
+            let Some((_, key)) = &column.reference else {
+                continue;
+            };
+
+            let value = row.get(key).unwrap_or(&SqlValue::Null);
+            if value.is_null() {
+                continue;
+            }
+
+            while let Some(slot) = self.find(&other, &column.name, value)? {
+                match column.on_delete {
+                    ReferentialAction::Cascade => self.delete(&other, slot)?,
+                    ReferentialAction::SetNull => {
+                        let mut changed = self.table(&other)?.row(slot)?.clone();
+                        changed.push(&column.name, SqlValue::Null);
+                        self.update(&other, slot, changed)?;
+                    }
+                    _ => return Self::referenced(table, &other, &column),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A database holding all rows in memory. It enforces the same constraints as the
+/// sql backends, so it can be used for tests or servers without any native library.
+/// Changes are undone using a log of the replaced rows, keys and unique columns are indexed.
+/// While a thread has a transaction open, other threads wait for it to end.
+#[derive(Resource, Default)]
+pub struct MemoryDatabase {
+    state: Mutex<MemoryState>,
+
+    /// Notified, when the last transaction of a thread ends.
+    released: Condvar,
+}
+
+impl MemoryDatabase {
+    pub fn new() -> Self {
+        MemoryDatabase::default()
+    }
+
+    /// Lock the state, once no other thread has a transaction open.
+    fn state(&self) -> ErmResult<MutexGuard<'_, MemoryState>> {
+        let current = thread::current().id();
+        let state = self
+            .state
+            .lock()
+            .map_err(|e| ErmError::Database(e.to_string()))?;
+
+        self.released
+            .wait_while(state, |x| x.owner.is_some_and(|x| x != current))
+            .map_err(|e| ErmError::Database(e.to_string()))
+    }
+
+    /// Run the closure on the state. Changes are undone, if the closure fails.
+    fn change<R>(&self, f: impl FnOnce(&mut MemoryState) -> ErmResult<R>) -> ErmResult<R> {
+        let mut state = self.state()?;
+        let length = state.log.len();
+
+        let result = f(&mut state);
+        if result.is_err() {
+            state.undo(length);
+        }
+
+        state.settle();
+        result
+    }
+
+    /// End the innermost transaction, undoing its changes or keeping them.
+    fn end(&self, rollback: bool) -> ErmResult<()> {
+        let mut state = self.state()?;
+        let Some(length) = state.savepoints.pop() else {
+            let action = if rollback { "roll back" } else { "commit" };
+            return Err(ErmError::Database(format!("No transaction to {}", action)));
+        };
+
+        if rollback {
+            state.undo(length);
+        }
+
+        if state.savepoints.is_empty() {
+            state.owner = None;
+            self.released.notify_all();
+        }
+
+        state.settle();
+        Ok(())
+    }
+
+    fn table_of(
+        definition: &TableDefinition,
+        registry: &ErmTypesRegistry,
+    ) -> ErmResult<MemoryTable> {
+        let mut columns = Vec::new();
+        for definition in definition.columns() {
+            if let SqlType::One2One(_, _) = definition.sql_type {
+                let (target, key) = registry.referenced_key_of(definition)?;
+                let mut column = MemoryColumn::new(&definition.sql_name, &key.sql_type);
+                column.not_null = definition.is_not_null();
+                column.reference = Some((target.sql_name.clone(), key.sql_name.clone()));
+                column.on_delete = definition.on_delete();
+                column.on_update = definition.on_update();
+                columns.push(column);
+
+                continue;
+            }
+
+            for (name, sql_type) in definition.sql_columns() {
+                let mut column = MemoryColumn::new(&name, &sql_type);
+                column.not_null |= definition.is_key();
+                column.unique = definition.is_unique();
+                if definition.has_max_length() {
+                    column.max_length = Some(definition.get_max_length());
+                }
+
+                columns.push(column);
+            }
+        }
+
+        let keys = definition
+            .key_columns()
+            .iter()
+            .flat_map(|x| x.sql_columns())
+            .map(|x| x.0)
+            .collect();
+
+        Ok(MemoryTable::new(&definition.sql_name, columns, keys))
+    }
+
+    fn child_table_of(
+        owner: &TableDefinition,
+        child: &ChildTable,
+        registry: &ErmTypesRegistry,
+    ) -> ErmResult<MemoryTable> {
+        let owner_key = owner.key_columns()[0];
+        let mut owner_column =
+            MemoryColumn::new(&child.owner_column.sql_name, &child.owner_column.sql_type);
+        owner_column.not_null = true;
+        owner_column.reference = Some((owner.sql_name.clone(), owner_key.sql_name.clone()));
+        owner_column.on_delete = ReferentialAction::Cascade;
+
+        let mut columns = vec![owner_column];
+        for entry in child.columns().into_iter().skip(1) {
+            if entry.is_reference() {
+                let (target, key) = registry.referenced_key(&entry.sql_type, None)?;
+                let mut column = MemoryColumn::new(&entry.sql_name, &key.sql_type);
+                column.not_null = true;
+                column.reference = Some((target.sql_name.clone(), key.sql_name.clone()));
+                columns.push(column);
+
+                continue;
+            }
+
+            columns.push(MemoryColumn::new(&entry.sql_name, &entry.sql_type));
+        }
+
+        let keys = child
+            .primary_key()
+            .iter()
+            .map(|x| x.sql_name.clone())
+            .collect();
+
+        Ok(MemoryTable::new(&child.sql_name, columns, keys))
+    }
+}
+
+impl ErmBackend for MemoryDatabase {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Sqlite
+    }
+
+    fn create_schema(&self, registry: &ErmTypesRegistry) -> ErmResult<()> {
+        let mut tables = Vec::new();
+        for table in registry.tables() {
+            tables.push(Self::table_of(table, registry)?);
+            for child in table.child_tables() {
+                tables.push(Self::child_table_of(table, child, registry)?);
+            }
+        }
+
+        let mut state = self.state()?;
+        for table in tables {
+            state.tables.entry(table.name.clone()).or_insert(table);
+        }
+
+        Ok(())
+    }
+
+    fn insert(&self, table: &TableDefinition, row: &Row) -> ErmResult<()> {
+        self.change(|state| state.insert(&table.sql_name, row))
+    }
+
+    fn insert_many(&self, table: &TableDefinition, rows: &[Row]) -> ErmResult<()> {
+        self.change(|state| {
+            rows.iter()
+                .try_for_each(|row| state.insert(&table.sql_name, row))
+        })
+    }
+
+    fn upsert_many(
+        &self,
+        table: &TableDefinition,
+        rows: &[Row],
+        conflict_columns: &[&str],
+    ) -> ErmResult<()> {
+        check_upsert(table)?;
+
+        self.change(|state| {
+            for row in rows {
+                let target = state.table(&table.sql_name)?;
+                let typed = target.typed(row)?;
+                let values: Vec<SqlValue> = conflict_columns
+                    .iter()
+                    .map(|x| typed.get(x).cloned().unwrap_or_default())
+                    .collect();
+
+                let Some(&slot) = target.slots(conflict_columns, &values).first() else {
+                    state.insert(&table.sql_name, row)?;
+                    continue;
+                };
+
+                let mut changed = target.row(slot)?.clone();
+                for column in upsert_columns(table, row, conflict_columns) {
+                    changed.push(column, typed.get(column).cloned().unwrap_or_default());
+                }
+                state.update(&table.sql_name, slot, changed)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn update_if(&self, table: &TableDefinition, row: &Row, filter: &Filter) -> ErmResult<bool> {
+        let (key_row, values) = split_keys(table, row)?;
+
+        self.change(|state| {
+            let target = state.table(&table.sql_name)?;
+            let key = target.key_of(&target.typed(&key_row)?);
+            let Some(slot) = target.position(&key) else {
+                return Ok(false);
+            };
+
+            let mut changed = target.row(slot)?.clone();
+            if !target.typed_filter(filter)?.matches(&changed) {
+                return Ok(false);
+            }
+
+            for (name, value) in values.columns.iter() {
+                changed.push(name, value.clone());
+            }
+
+            let changed = target.typed(&changed)?;
+            state.update(&table.sql_name, slot, changed)?;
+
+            Ok(true)
+        })
+    }
+
+    fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool> {
+        let (key_row, _) = split_keys(table, key)?;
+
+        self.change(|state| {
+            let target = state.table(&table.sql_name)?;
+            let key = target.key_of(&target.typed(&key_row)?);
+            let Some(slot) = target.position(&key) else {
+                return Ok(false);
+            };
+
+            state.delete(&table.sql_name, slot)?;
+            Ok(true)
+        })
+    }
+
+    fn query(&self, table: &TableDefinition, select: &Select) -> ErmResult<Vec<Row>> {
+        let state = self.state()?;
+        let target = state.table(&table.sql_name)?;
+        for (name, _) in select.order.iter() {
+            target.column(name)?;
+        }
+
+        let select = Select {
+            filter: target.typed_filter(&select.filter)?,
+            ..select.clone()
+        };
+
+        let mut rows = Vec::new();
+        for slot in target.candidates(&select.filter) {
+            let row = target.row(slot)?;
+            if select.filter.matches(row) {
+                rows.push(row.clone());
+            }
+        }
+
+        Ok(select.apply(rows))
+    }
+
+    fn query_sql(&self, _sql: &str, _params: &[SqlValue]) -> ErmResult<Vec<Row>> {
+        Err(ErmError::Unsupported(
+            "The memory database cannot run sql statements".to_owned(),
+        ))
+    }
+
+    fn replace_children(
+        &self,
+        child: &ChildTable,
+        owner: &SqlValue,
+        rows: &[Row],
+    ) -> ErmResult<()> {
+        let owner_name = child.owner_column.sql_name.as_str();
+
+        self.change(|state| {
+            let target = state.table(&child.sql_name)?;
+            let owner = owner
+                .clone()
+                .into_type(&target.column(owner_name)?.sql_type);
+            for slot in target.slots(&[owner_name], std::slice::from_ref(&owner)) {
+                state.put(&child.sql_name, slot, None)?;
+            }
+
+            for row in rows {
+                let mut element = Row::new().with(owner_name, owner.clone());
+                element.columns.extend(row.columns.iter().cloned());
+                state.insert(&child.sql_name, &element)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn select_children(&self, child: &ChildTable, owners: &[SqlValue]) -> ErmResult<Vec<Row>> {
+        let state = self.state()?;
+        let target = state.table(&child.sql_name)?;
+        let owner_name = child.owner_column.sql_name.as_str();
+        let owner_type = &target.column(owner_name)?.sql_type;
+        let owners: Vec<SqlValue> = owners
+            .iter()
+            .map(|x| x.clone().into_type(owner_type))
+            .collect();
+
+        let mut result = Vec::new();
+        for slot in target.candidates(&Filter::In(owner_name.to_owned(), owners)) {
+            result.push(target.row(slot)?.clone());
+        }
+
+        if let Some(ordinal) = &child.ordinal_column {
+            result.sort_by(|a, b| {
+                let a = (a.get(owner_name), a.get(&ordinal.sql_name));
+                let b = (b.get(owner_name), b.get(&ordinal.sql_name));
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn begin(&self) -> ErmResult<()> {
+        let mut state = self.state()?;
+        let length = state.log.len();
+        state.savepoints.push(length);
+        state.owner = Some(thread::current().id());
+
+        Ok(())
+    }
+
+    fn commit(&self) -> ErmResult<()> {
+        self.end(false)
+    }
+
+    fn rollback(&self) -> ErmResult<()> {
+        self.end(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backend::conformance, prelude::*};
+    use bevy::prelude::*;
+
+    #[test]
+    fn crud() {
+        conformance::crud(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn bulk_writes() {
+        conformance::bulk_writes(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn conditional_updates() {
+        conformance::conditional_updates(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn constraints() {
+        conformance::constraints(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn referential_actions() {
+        conformance::referential_actions(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn child_tables() {
+        conformance::child_tables(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn transactions() {
+        conformance::transactions(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn transactions_of_other_threads() {
+        conformance::transactions_of_other_threads(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn many_rows() {
+        conformance::many_rows(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn unknown_columns() {
+        conformance::unknown_columns(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn queries() {
+        conformance::queries(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn raw_queries() {
+        let database = MemoryDatabase::new();
+        assert!(matches!(
+            database.query_sql("SELECT 1", &[]),
+            Err(ErmError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn default_backend() {
+        let mut app = App::new();
+        app.add_plugins(BevyERMPlugin::default().with_dialect(SqlDialect::PostgreSql));
+
+        let database = app.world().resource::<ErmDatabase>();
+        assert!(database.commit().is_err());
+
+        // The memory backend keeps the configured dialect.
+        let registry = app.world().resource::<ErmTypesRegistry>();
+        assert_eq!(registry.dialect, SqlDialect::PostgreSql);
+    }
+
+    #[test]
+    fn explicit_backend() {
+        let mut app = App::new();
+        app.add_plugins(
+            BevyERMPlugin::default()
+                .with_dialect(SqlDialect::MySql)
+                .with_memory_backend(),
+        );
+
+        let database = app.world().resource::<ErmDatabase>();
+        assert!(database.commit().is_err());
+
+        // The explicit memory backend keeps the configured dialect as well.
+        let registry = app.world().resource::<ErmTypesRegistry>();
+        assert_eq!(registry.dialect, SqlDialect::MySql);
+    }
+
+    #[test]
+    #[should_panic(expected = "The backend of the database could not be created")]
+    fn failing_backend() {
+        let mut app = App::new();
+        app.add_plugins(BevyERMPlugin::default().with_backend(|| {
+            Err::<MemoryDatabase, _>(ErmError::Database("unreachable".to_owned()))
+        }));
+    }
+}
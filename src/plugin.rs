@@ -1,11 +1,135 @@
-use crate::prelude::ErmTypesRegistry;
+use std::sync::Arc;
+
+use crate::prelude::{
+    BackendFactory, Clock, ErmBackend, ErmDatabase, ErmResult, ErmTypesRegistry, IdentityMap,
+    MemoryDatabase, Snapshots, SqlDialect, DEFAULT_SNAPSHOT_CAPACITY,
+};
 use bevy::prelude::*;
 
-pub struct BevyERMPlugin;
+/// Adds the ERM-Registry and the database to the app. Use `BevyERMPlugin::default()`
+/// for the defaults.
+#[derive(Default)]
+pub struct BevyERMPlugin {
+    /// Store all vectors and quaternions as one column per component instead of a blob.
+    /// Fields can opt in individually using the decompose attribute.
+    pub decompose_math_types: bool,
+
+    /// The sql dialect of the database.
+    pub dialect: SqlDialect,
+
+    /// Creates the backend of the database resource. Without a backend, the database is held in memory
+    /// and a warning is logged, as nothing is persisted.
+    pub backend: Option<BackendFactory>,
+
+    /// Hold the database in memory on purpose, without logging a warning.
+    /// Ignored, if a backend is set.
+    pub memory_backend: bool,
 
-/// All this plugin does is adding the ERM-Registry as a resource to the app.
+    /// The number of levels eager relations are followed when loading values.
+    /// Without a limit, the default of the ERM-Registry is used.
+    pub eager_depth: Option<usize>,
+
+    /// The maximum number of keys a single select takes when loading in batches.
+    /// Without a size, the default of the ERM-Registry is used.
+    pub batch_size: Option<usize>,
+
+    /// The maximum number of snapshots kept to find the changed columns of values.
+    /// Without a capacity, `DEFAULT_SNAPSHOT_CAPACITY` is used.
+    pub snapshot_capacity: Option<usize>,
+}
+
+impl BevyERMPlugin {
+    /// Store all vectors and quaternions as one column per component.
+    pub fn with_decomposed_math_types(mut self) -> Self {
+        self.decompose_math_types = true;
+        self
+    }
+
+    /// Set the sql dialect of the database.
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Set the number of levels eager relations are followed when loading values.
+    pub fn with_eager_depth(mut self, depth: usize) -> Self {
+        self.eager_depth = Some(depth);
+        self
+    }
+
+    /// Set the maximum number of keys a single select takes when loading in batches.
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = Some(size);
+        self
+    }
+
+    /// Set the maximum number of snapshots kept to find the changed columns of values.
+    pub fn with_snapshot_capacity(mut self, capacity: usize) -> Self {
+        self.snapshot_capacity = Some(capacity);
+        self
+    }
+
+    /// Hold the database in memory on purpose, without the warning logged for a missing
+    /// backend. The configured dialect is kept.
+    pub fn with_memory_backend(mut self) -> Self {
+        self.backend = None;
+        self.memory_backend = true;
+        self
+    }
+
+    /// Set the backend of the database. The dialect is taken from the backend.
+    /// Adding the plugin panics, if the factory fails to create the backend.
+    pub fn with_backend<B, F>(mut self, factory: F) -> Self
+    where
+        B: ErmBackend + 'static,
+        F: Fn() -> ErmResult<B> + Send + Sync + 'static,
+    {
+        self.backend = Some(Arc::new(move || {
+            factory().map(|x| Box::new(x) as Box<dyn ErmBackend>)
+        }));
+        self
+    }
+}
+
+/// The plugin adds the ERM-Registry and the database as resources to the app.
 impl Plugin for BevyERMPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ErmTypesRegistry::default());
+        let mut registry = ErmTypesRegistry::default();
+        registry.decompose_math_types = self.decompose_math_types;
+        registry.dialect = self.dialect;
+        if let Some(depth) = self.eager_depth {
+            registry.eager_depth = depth;
+        }
+        if let Some(size) = self.batch_size {
+            registry.batch_size = size;
+        }
+
+        let backend: Box<dyn ErmBackend> = match &self.backend {
+            Some(factory) => {
+                // Systems using the database cannot run without it, so the app is not built.
+                let backend = factory().unwrap_or_else(|e| {
+                    panic!("The backend of the database could not be created: {}", e)
+                });
+                registry.dialect = backend.dialect();
+                backend
+            }
+            None => {
+                // Keep the configured dialect, the memory backend understands any filter.
+                if !self.memory_backend {
+                    warn!(
+                        "No database backend configured, all rows are held in memory and lost on exit"
+                    );
+                }
+                Box::new(MemoryDatabase::new())
+            }
+        };
+
+        app.insert_resource(ErmDatabase::new(backend));
+        app.insert_resource(registry);
+        app.insert_resource(IdentityMap::new());
+        app.insert_resource(Snapshots::with_capacity(
+            self.snapshot_capacity.unwrap_or(DEFAULT_SNAPSHOT_CAPACITY),
+        ));
+        app.init_resource::<Clock>();
     }
 }
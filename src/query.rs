@@ -0,0 +1,704 @@
+use std::{cmp::Ordering, marker::PhantomData};
+
+use bevy::reflect::{Reflect, TypePath, TypeRegistry};
+
+use crate::{
+    backend::split_keys,
+    prelude::{
+        from_row_as, ErmBackend, ErmError, ErmResult, ErmTypesRegistry, Row, SqlDialect, SqlValue,
+        TableDefinition,
+    },
+    repository::{read_values, table_of},
+    soft_delete::live_filter,
+};
+
+/// A condition the rows returned by a select have to fulfill.
+/// Comparisons with null are never true, as in sql. Use `IsNull` to find null values.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Filter {
+    /// Every row matches.
+    #[default]
+    All,
+
+    Eq(String, SqlValue),
+    NotEq(String, SqlValue),
+    Less(String, SqlValue),
+    LessOrEqual(String, SqlValue),
+    Greater(String, SqlValue),
+    GreaterOrEqual(String, SqlValue),
+
+    /// The column holds one of the values.
+    In(String, Vec<SqlValue>),
+
+    /// The text of the column matches the pattern. `%` matches any text, `_` a single character.
+    Like(String, String),
+
+    IsNull(String),
+
+    /// All filters match.
+    And(Vec<Filter>),
+
+    /// Any of the filters matches.
+    Or(Vec<Filter>),
+
+    Not(Box<Filter>),
+}
+
+/// Start a filter on the column with the given name, e.g. `col("name").eq("Ann")`.
+/// Both, the rust and the sql name of a column can be used.
+pub fn col(name: &str) -> Column {
+    Column {
+        name: name.to_owned(),
+    }
+}
+
+/// A column used to create filters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    name: String,
+}
+
+impl Column {
+    pub fn eq(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::Eq(self.name, value.into())
+    }
+
+    pub fn not_eq(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::NotEq(self.name, value.into())
+    }
+
+    pub fn lt(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::Less(self.name, value.into())
+    }
+
+    pub fn le(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::LessOrEqual(self.name, value.into())
+    }
+
+    pub fn gt(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::Greater(self.name, value.into())
+    }
+
+    pub fn ge(self, value: impl Into<SqlValue>) -> Filter {
+        Filter::GreaterOrEqual(self.name, value.into())
+    }
+
+    /// The value of the column lies within the range, including both bounds.
+    pub fn between(self, low: impl Into<SqlValue>, high: impl Into<SqlValue>) -> Filter {
+        Filter::And(vec![
+            Filter::GreaterOrEqual(self.name.clone(), low.into()),
+            Filter::LessOrEqual(self.name, high.into()),
+        ])
+    }
+
+    pub fn is_in<V: Into<SqlValue>>(self, values: impl IntoIterator<Item = V>) -> Filter {
+        Filter::In(self.name, values.into_iter().map(|x| x.into()).collect())
+    }
+
+    pub fn like(self, pattern: &str) -> Filter {
+        Filter::Like(self.name, pattern.to_owned())
+    }
+
+    pub fn is_null(self) -> Filter {
+        Filter::IsNull(self.name)
+    }
+
+    pub fn is_not_null(self) -> Filter {
+        Filter::Not(Box::new(Filter::IsNull(self.name)))
+    }
+}
+
+impl Filter {
+    /// Create a filter matching the key columns of the table to the values held by the row.
+    pub fn by_key(table: &TableDefinition, row: &Row) -> ErmResult<Filter> {
+        let (keys, _) = split_keys(table, row)?;
+
+        Ok(Filter::And(
+            keys.columns
+                .into_iter()
+                .map(|(name, value)| Filter::Eq(name, value))
+                .collect(),
+        ))
+    }
+
+    /// Match rows matching this and the other filter.
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other);
+                Filter::And(filters)
+            }
+            _ => Filter::And(vec![self, other]),
+        }
+    }
+
+    /// Match rows matching this or the other filter.
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other);
+                Filter::Or(filters)
+            }
+            _ => Filter::Or(vec![self, other]),
+        }
+    }
+
+    /// Return the names of all columns used by this filter.
+    pub fn column_names(&self) -> Vec<&str> {
+        match self {
+            Filter::All => Vec::new(),
+            Filter::Eq(c, _)
+            | Filter::NotEq(c, _)
+            | Filter::Less(c, _)
+            | Filter::LessOrEqual(c, _)
+            | Filter::Greater(c, _)
+            | Filter::GreaterOrEqual(c, _)
+            | Filter::In(c, _)
+            | Filter::Like(c, _)
+            | Filter::IsNull(c) => vec![c.as_str()],
+            Filter::And(filters) | Filter::Or(filters) => {
+                filters.iter().flat_map(|x| x.column_names()).collect()
+            }
+            Filter::Not(filter) => filter.column_names(),
+        }
+    }
+
+    /// Return a copy of this filter, with the names of the columns and the values replaced.
+    pub fn map(
+        &self,
+        column: &impl Fn(&str) -> ErmResult<String>,
+        value: &impl Fn(&str, &SqlValue) -> ErmResult<SqlValue>,
+    ) -> ErmResult<Filter> {
+        let compare = |c: &String, v: &SqlValue| Ok::<_, ErmError>((column(c)?, value(c, v)?));
+        let all = |filters: &Vec<Filter>| {
+            filters
+                .iter()
+                .map(|x| x.map(column, value))
+                .collect::<ErmResult<Vec<Filter>>>()
+        };
+
+        Ok(match self {
+            Filter::All => Filter::All,
+            Filter::Eq(c, v) => compare(c, v).map(|(c, v)| Filter::Eq(c, v))?,
+            Filter::NotEq(c, v) => compare(c, v).map(|(c, v)| Filter::NotEq(c, v))?,
+            Filter::Less(c, v) => compare(c, v).map(|(c, v)| Filter::Less(c, v))?,
+            Filter::LessOrEqual(c, v) => compare(c, v).map(|(c, v)| Filter::LessOrEqual(c, v))?,
+            Filter::Greater(c, v) => compare(c, v).map(|(c, v)| Filter::Greater(c, v))?,
+            Filter::GreaterOrEqual(c, v) => {
+                compare(c, v).map(|(c, v)| Filter::GreaterOrEqual(c, v))?
+            }
+            Filter::In(c, values) => Filter::In(
+                column(c)?,
+                values
+                    .iter()
+                    .map(|v| value(c, v))
+                    .collect::<ErmResult<Vec<SqlValue>>>()?,
+            ),
+            Filter::Like(c, pattern) => Filter::Like(column(c)?, pattern.clone()),
+            Filter::IsNull(c) => Filter::IsNull(column(c)?),
+            Filter::And(filters) => Filter::And(all(filters)?),
+            Filter::Or(filters) => Filter::Or(all(filters)?),
+            Filter::Not(filter) => Filter::Not(Box::new(filter.map(column, value)?)),
+        })
+    }
+
+    /// Return true, if the values of the row match this filter.
+    pub fn matches(&self, row: &Row) -> bool {
+        self.evaluate(row) == Some(true)
+    }
+
+    /// Evaluate the filter as sql does. Comparisons with null are unknown, which is None.
+    fn evaluate(&self, row: &Row) -> Option<bool> {
+        let compare = |c: &String, v: &SqlValue| {
+            let current = row.get(c).unwrap_or(&SqlValue::Null);
+            if current.is_null() || v.is_null() {
+                return None;
+            }
+
+            current.partial_cmp(v)
+        };
+
+        match self {
+            Filter::All => Some(true),
+            Filter::Eq(c, v) => compare(c, v).map(|x| x == Ordering::Equal),
+            Filter::NotEq(c, v) => compare(c, v).map(|x| x != Ordering::Equal),
+            Filter::Less(c, v) => compare(c, v).map(|x| x == Ordering::Less),
+            Filter::LessOrEqual(c, v) => compare(c, v).map(|x| x != Ordering::Greater),
+            Filter::Greater(c, v) => compare(c, v).map(|x| x == Ordering::Greater),
+            Filter::GreaterOrEqual(c, v) => compare(c, v).map(|x| x != Ordering::Less),
+            Filter::In(c, values) => {
+                let results: Vec<Option<bool>> = values
+                    .iter()
+                    .map(|v| compare(c, v).map(|x| x == Ordering::Equal))
+                    .collect();
+                Self::any(results)
+            }
+            Filter::Like(c, pattern) => match row.get(c) {
+                Some(SqlValue::Text(text)) => Some(like(text, pattern)),
+                _ => None,
+            },
+            Filter::IsNull(c) => Some(row.get(c).is_none_or(|x| x.is_null())),
+            Filter::And(filters) => {
+                let results: Vec<Option<bool>> = filters.iter().map(|x| x.evaluate(row)).collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(true)
+                }
+            }
+            Filter::Or(filters) => Self::any(filters.iter().map(|x| x.evaluate(row)).collect()),
+            Filter::Not(filter) => filter.evaluate(row).map(|x| !x),
+        }
+    }
+
+    fn any(results: Vec<Option<bool>>) -> Option<bool> {
+        if results.contains(&Some(true)) {
+            Some(true)
+        } else if results.contains(&None) {
+            None
+        } else {
+            Some(false)
+        }
+    }
+
+    /// Create the condition of a where clause. The values are added to the parameters.
+    pub fn to_sql(&self, dialect: SqlDialect, params: &mut Vec<SqlValue>) -> String {
+        let mut compare = |c: &String, op: &str, v: &SqlValue| {
+            params.push(v.clone());
+            format!(
+                "{} {} {}",
+                dialect.quote(c),
+                op,
+                dialect.placeholder(params.len())
+            )
+        };
+        let join = |filters: &Vec<Filter>, op: &str, params: &mut Vec<SqlValue>| {
+            filters
+                .iter()
+                .map(|x| format!("({})", x.to_sql(dialect, params)))
+                .collect::<Vec<String>>()
+                .join(op)
+        };
+
+        match self {
+            Filter::All => "1 = 1".to_owned(),
+            Filter::Eq(c, v) => compare(c, "=", v),
+            Filter::NotEq(c, v) => compare(c, "<>", v),
+            Filter::Less(c, v) => compare(c, "<", v),
+            Filter::LessOrEqual(c, v) => compare(c, "<=", v),
+            Filter::Greater(c, v) => compare(c, ">", v),
+            Filter::GreaterOrEqual(c, v) => compare(c, ">=", v),
+            Filter::In(_, values) if values.is_empty() => "1 = 0".to_owned(),
+            Filter::In(c, values) => {
+                let placeholders: Vec<String> = values
+                    .iter()
+                    .map(|v| {
+                        params.push(v.clone());
+                        dialect.placeholder(params.len())
+                    })
+                    .collect();
+                format!("{} IN ({})", dialect.quote(c), placeholders.join(", "))
+            }
+            Filter::Like(c, pattern) => compare(c, "LIKE", &SqlValue::Text(pattern.clone())),
+            Filter::IsNull(c) => format!("{} IS NULL", dialect.quote(c)),
+            Filter::And(filters) if filters.is_empty() => "1 = 1".to_owned(),
+            Filter::And(filters) => join(filters, " AND ", params),
+            Filter::Or(filters) if filters.is_empty() => "1 = 0".to_owned(),
+            Filter::Or(filters) => join(filters, " OR ", params),
+            Filter::Not(filter) => format!("NOT ({})", filter.to_sql(dialect, params)),
+        }
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Filter::Not(Box::new(self))
+    }
+}
+
+/// Match the text against a like pattern. Like sqlite, ascii letters are compared
+/// ignoring their case.
+fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().map(|x| x.to_ascii_lowercase()).collect();
+    let pattern: Vec<char> = pattern.chars().map(|x| x.to_ascii_lowercase()).collect();
+
+    fn matches(text: &[char], pattern: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('%', rest)) => (0..=text.len()).any(|i| matches(&text[i..], rest)),
+            Some(('_', rest)) => !text.is_empty() && matches(&text[1..], rest),
+            Some((c, rest)) => text.first() == Some(c) && matches(&text[1..], rest),
+        }
+    }
+
+    matches(&text, &pattern)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// A select on a single table. Columns are named by their sql name.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Select {
+    pub filter: Filter,
+    pub order: Vec<(String, Order)>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl Select {
+    pub fn new(filter: Filter) -> Self {
+        Select {
+            filter,
+            ..Select::default()
+        }
+    }
+
+    /// Create the statement selecting the columns. The values are added to the parameters.
+    pub fn to_sql(
+        &self,
+        dialect: SqlDialect,
+        table: &str,
+        columns: &[&str],
+        params: &mut Vec<SqlValue>,
+    ) -> String {
+        let mut result = format!(
+            "{} WHERE {}",
+            dialect.select(table, columns, &[]),
+            self.filter.to_sql(dialect, params)
+        );
+
+        if !self.order.is_empty() {
+            let order: Vec<String> = self
+                .order
+                .iter()
+                .map(|(c, o)| match o {
+                    Order::Ascending => format!("{} ASC", dialect.quote(c)),
+                    Order::Descending => format!("{} DESC", dialect.quote(c)),
+                })
+                .collect();
+            result.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        }
+
+        // An offset needs a limit in sqlite and mysql.
+        match (self.limit, self.offset, dialect) {
+            (Some(limit), _, _) => result.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(_), SqlDialect::Sqlite) => result.push_str(" LIMIT -1"),
+            (None, Some(_), SqlDialect::MySql) => result.push_str(&format!(" LIMIT {}", u64::MAX)),
+            _ => {}
+        }
+
+        if let Some(offset) = self.offset {
+            result.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        result
+    }
+
+    /// Apply order, offset and limit to rows matching the filter.
+    pub fn apply(&self, mut rows: Vec<Row>) -> Vec<Row> {
+        rows.retain(|x| self.filter.matches(x));
+
+        // Null values come first in ascending order, like in sqlite and mysql.
+        rows.sort_by(|a, b| {
+            for (column, order) in self.order.iter() {
+                let a = a.get(column).unwrap_or(&SqlValue::Null);
+                let b = b.get(column).unwrap_or(&SqlValue::Null);
+                let result = match (a.is_null(), b.is_null()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Less,
+                    (false, true) => Ordering::Greater,
+                    _ => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                };
+
+                let result = match order {
+                    Order::Ascending => result,
+                    Order::Descending => result.reverse(),
+                };
+                if result != Ordering::Equal {
+                    return result;
+                }
+            }
+
+            Ordering::Equal
+        });
+
+        rows.into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// A query on the table of T, e.g.
+/// `ErmQuery::<Player>::new().filter(col("name").eq("Ann")).order_by("id").limit(10)`.
+/// The names of the columns are checked, when the query is built for its table.
+pub struct ErmQuery<T> {
+    select: Select,
+    with_deleted: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for ErmQuery<T> {
+    fn default() -> Self {
+        ErmQuery {
+            select: Select::default(),
+            with_deleted: false,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for ErmQuery<T> {
+    fn clone(&self) -> Self {
+        ErmQuery {
+            select: self.select.clone(),
+            with_deleted: self.with_deleted,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: TypePath + 'static> ErmQuery<T> {
+    pub fn new() -> Self {
+        ErmQuery::default()
+    }
+
+    /// Add a filter. Rows have to match all filters added.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.select.filter = match self.select.filter {
+            Filter::All => filter,
+            current => current.and(filter),
+        };
+        self
+    }
+
+    /// Order the rows by the column, ascending.
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.select
+            .order
+            .push((column.to_owned(), Order::Ascending));
+        self
+    }
+
+    /// Order the rows by the column, descending.
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.select
+            .order
+            .push((column.to_owned(), Order::Descending));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.select.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.select.offset = Some(offset);
+        self
+    }
+
+    /// Include the rows marked as deleted by the soft delete column of the table, see `SoftDelete`.
+    pub fn with_deleted(mut self) -> Self {
+        self.with_deleted = true;
+        self
+    }
+
+    /// Check the columns against the table of T and name them by their sql name.
+    /// Rows marked as deleted are excluded, unless the query includes them.
+    pub fn build(&self, registry: &ErmTypesRegistry) -> ErmResult<Select> {
+        let table = table_of::<T>(registry)?;
+        let column = |name: &str| sql_column(table, name);
+
+        let mut order = Vec::new();
+        for (name, direction) in self.select.order.iter() {
+            order.push((column(name)?, *direction));
+        }
+
+        let mut filter = self.select.filter.map(&column, &|_, v| Ok(v.clone()))?;
+        let live = live_filter(table);
+        if !self.with_deleted && live != Filter::All {
+            filter = match filter {
+                Filter::All => live,
+                current => current.and(live),
+            };
+        }
+
+        Ok(Select {
+            filter,
+            order,
+            ..self.select.clone()
+        })
+    }
+}
+
+/// Return the sql name of a column, given its rust or sql name. Fields stored in several
+/// columns are named by their columns, fields stored in child tables cannot be used.
+fn sql_column(table: &TableDefinition, name: &str) -> ErmResult<String> {
+    let columns = table.sql_columns();
+    if columns.iter().any(|x| x.0 == name) {
+        return Ok(name.to_owned());
+    }
+
+    if let Some(column) = table.get(name) {
+        if let [(sql_name, _)] = &column.sql_columns()[..] {
+            return Ok(sql_name.clone());
+        }
+    }
+
+    Err(ErmError::InvalidMapping(format!(
+        "Table {} has no column {} to query",
+        table.sql_name, name
+    )))
+}
+
+/// Run a statement written in the dialect of the backend and create an instance of T
+/// from each row of its result. Columns are matched to the sql names of the table of T,
+/// unknown columns are ignored and missing nullable columns are read as null.
+/// Collections stored in child tables are read, if the result holds the key of the table.
+pub fn query_as<T: Reflect + TypePath>(
+    db: &dyn ErmBackend,
+    sql: &str,
+    params: &[SqlValue],
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Vec<T>> {
+    let table = table_of::<T>(registry)?;
+    let rows = db
+        .query_sql(sql, params)?
+        .iter()
+        .map(|x| table_row(x, table))
+        .collect::<ErmResult<Vec<Row>>>()?;
+
+    let keyed = table.key_columns().iter().all(|key| {
+        rows.iter()
+            .all(|x| x.get(&key.sql_name).is_some_and(|x| !x.is_null()))
+    });
+    if keyed {
+        return read_values(db, rows, table, registry, type_registry);
+    }
+
+    rows.iter()
+        .map(|x| from_row_as::<T>(x, table, registry, type_registry))
+        .collect()
+}
+
+/// Pick the columns of the table from a row of a result and convert them to their sql types.
+fn table_row(row: &Row, table: &TableDefinition) -> ErmResult<Row> {
+    let mut result = Row::new();
+    for (name, sql_type) in table.sql_columns() {
+        match row.get(&name) {
+            Some(value) => result.push(&name, value.clone().into_type(&sql_type)),
+            None if !sql_type.is_not_null() => result.push(&name, SqlValue::Null),
+            None => return Err(ErmError::MissingColumn(table.sql_name.clone(), name)),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@ColumnName::new("player_name"))]
+        pub name: String,
+        pub tags: Vec<String>,
+    }
+
+    fn registry() -> ErmTypesRegistry {
+        let app_registry = AppTypeRegistry::default();
+        app_registry.write().register::<Player>();
+
+        let mut registry = ErmTypesRegistry::default();
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        registry
+    }
+
+    #[test]
+    fn sql() {
+        let select = Select {
+            filter: col("name")
+                .like("A%")
+                .and(col("id").is_in([1, 2]))
+                .or(!col("score").is_null()),
+            order: vec![("id".to_owned(), Order::Descending)],
+            limit: Some(10),
+            offset: Some(20),
+        };
+
+        let mut params = Vec::new();
+        assert_eq!(
+            select.to_sql(SqlDialect::Sqlite, "Players", &["id"], &mut params),
+            "SELECT \"id\" FROM \"Players\" WHERE ((\"name\" LIKE ?1) AND (\"id\" IN (?2, ?3))) \
+             OR (NOT (\"score\" IS NULL)) ORDER BY \"id\" DESC LIMIT 10 OFFSET 20"
+        );
+        assert_eq!(params, vec!["A%".into(), 1.into(), 2.into()]);
+
+        let select = Select {
+            filter: col("id").between(1, 5),
+            offset: Some(20),
+            ..Select::default()
+        };
+
+        let mut params = Vec::new();
+        assert_eq!(
+            select.to_sql(SqlDialect::PostgreSql, "Players", &["id"], &mut params),
+            "SELECT \"id\" FROM \"Players\" WHERE (\"id\" >= $1) AND (\"id\" <= $2) OFFSET 20"
+        );
+
+        let mut params = Vec::new();
+        assert_eq!(
+            select.to_sql(SqlDialect::MySql, "Players", &["id"], &mut params),
+            format!(
+                "SELECT `id` FROM `Players` WHERE (`id` >= ?) AND (`id` <= ?) LIMIT {} OFFSET 20",
+                u64::MAX
+            )
+        );
+    }
+
+    #[test]
+    fn build() {
+        let registry = registry();
+
+        let select = ErmQuery::<Player>::new()
+            .filter(col("name").eq("Ann"))
+            .filter(col("player_name").not_eq("Bob"))
+            .order_by("name")
+            .limit(3)
+            .build(&registry)
+            .unwrap();
+        assert_eq!(
+            select.filter,
+            Filter::And(vec![
+                Filter::Eq("player_name".to_owned(), "Ann".into()),
+                Filter::NotEq("player_name".to_owned(), "Bob".into()),
+            ])
+        );
+        assert_eq!(
+            select.order,
+            vec![("player_name".to_owned(), Order::Ascending)]
+        );
+        assert_eq!(select.limit, Some(3));
+
+        let query = ErmQuery::<Player>::new().filter(col("tags").is_null());
+        assert!(query.build(&registry).is_err());
+        let query = ErmQuery::<Player>::new().order_by("score");
+        assert!(query.build(&registry).is_err());
+    }
+}
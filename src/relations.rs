@@ -0,0 +1,627 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::reflect::{
+    ApplyError, DynamicEnum, DynamicTuple, DynamicVariant, PartialReflect, ReflectMut, TypeRegistry,
+};
+
+use crate::{
+    erm_types_registry::ErmTypesRegistry,
+    framed_blob::is_option,
+    identity::KeyValue,
+    lazy::{is_lazy, set_lazy_key},
+    prelude::{
+        ColumnDefinition, ErmBackend, ErmError, ErmResult, Filter, Row, SqlType, SqlValue,
+        TableDefinition,
+    },
+    repository::read_reflect,
+    row_mapping::{field_mut_of, reference_key, to_value},
+    soft_delete::is_deleted,
+};
+
+/// The rows a value was reached through, named by their table and key.
+pub(crate) type Path<'a> = Vec<(&'a str, SqlValue)>;
+
+/// Returns true, if the column holds a relation to another table.
+pub(crate) fn is_relation(column: &ColumnDefinition) -> bool {
+    matches!(
+        column.sql_type,
+        SqlType::One2One(_, _) | SqlType::Many2Many(_, _)
+    )
+}
+
+/// Return the number of keys a single select may take.
+pub(crate) fn batch_size(db: &dyn ErmBackend, registry: &ErmTypesRegistry) -> usize {
+    registry
+        .batch_size
+        .min(db.dialect().max_parameters())
+        .max(1)
+}
+
+/// Select the rows of the table holding one of the keys in the column,
+/// using as few selects as the batch size allows.
+pub(crate) fn select_in(
+    db: &dyn ErmBackend,
+    table: &TableDefinition,
+    column: &str,
+    keys: &[SqlValue],
+    registry: &ErmTypesRegistry,
+) -> ErmResult<Vec<Row>> {
+    let mut result = Vec::new();
+    for chunk in keys.chunks(batch_size(db, registry)) {
+        result.extend(db.select(table, &Filter::In(column.to_owned(), chunk.to_vec()))?);
+    }
+
+    Ok(result)
+}
+
+/// Replace relations marked for eager loading, which hold only the key of the referenced row,
+/// by the referenced values. Relations are followed up to the depth configured with the
+/// ERM-Registry, see `load_relation`.
+pub(crate) fn load_eager<'a>(
+    db: &dyn ErmBackend,
+    mut values: Vec<(&mut dyn PartialReflect, Path<'a>)>,
+    table: &'a TableDefinition,
+    registry: &'a ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+    depth: usize,
+) -> ErmResult<()> {
+    if depth >= registry.eager_depth || values.is_empty() {
+        return Ok(());
+    }
+
+    for column in table.columns() {
+        if column.is_eager() && is_relation(column) {
+            load_relation(
+                db,
+                &mut values,
+                table,
+                column,
+                registry,
+                type_registry,
+                depth,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the values referenced by the relation column of all values at once. The keys of the
+/// referenced rows are gathered and selected in batches, then the values are put in place.
+/// Options and lazy relations get the referenced value, other relations are replaced by it.
+/// The eager relations of the referenced values are loaded in turn.
+/// A row which already lies on the path leading to it is a cycle and is not loaded again.
+/// Relations to rows which do not exist or are marked as deleted are dropped from lists,
+/// options and lazy relations are cleared, see `clear`.
+pub(crate) fn load_relation<'a>(
+    db: &dyn ErmBackend,
+    values: &mut [(&mut dyn PartialReflect, Path<'a>)],
+    table: &'a TableDefinition,
+    column: &ColumnDefinition,
+    registry: &'a ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+    depth: usize,
+) -> ErmResult<()> {
+    let (target, key) = registry.referenced_key_of(column)?;
+    let error =
+        |e: String| ErmError::InvalidValue(table.sql_name.clone(), column.sql_name.clone(), e);
+
+    // Lists cannot lend several elements at once, so their elements are taken out
+    // while loading and put back afterwards.
+    let mut elements = Vec::new();
+    for (value, path) in values.iter_mut() {
+        let field = field_mut_of(&mut **value, table, column)?;
+        if let ReflectMut::List(list) = field.reflect_mut() {
+            elements.push((list.drain(), path.clone()));
+        }
+    }
+
+    // List elements are named by the index of their list and their index within it.
+    type Position = Option<(usize, usize)>;
+    let mut stubs: Vec<(&mut dyn PartialReflect, Path<'a>, Position)> = Vec::new();
+    if elements.is_empty() {
+        for (value, path) in values.iter_mut() {
+            let field = field_mut_of(&mut **value, table, column)?;
+            stubs.push((field, path.clone(), None));
+        }
+    } else {
+        for (i, (list, path)) in elements.iter_mut().enumerate() {
+            for (j, element) in list.iter_mut().enumerate() {
+                stubs.push((element.as_mut(), path.clone(), Some((i, j))));
+            }
+        }
+    }
+
+    let mut requests = Vec::new();
+    let mut keys = Vec::new();
+    let mut requested = HashSet::new();
+    for (stub, mut path, position) in stubs {
+        let value = to_value(
+            stub,
+            &column.sql_type,
+            reference_key(column),
+            registry,
+            type_registry,
+        )?
+        .into_type(&key.sql_type);
+
+        let cycle = path
+            .iter()
+            .any(|(t, k)| *t == target.sql_name && *k == value);
+        if value.is_null() || cycle {
+            continue;
+        }
+
+        if requested.insert(KeyValue(value.clone())) {
+            keys.push(value.clone());
+        }
+        path.push((target.sql_name.as_str(), value.clone()));
+        requests.push((stub, value, path, position));
+    }
+
+    let mut rows = select_in(db, target, &key.sql_name, &keys, registry)?;
+    rows.retain(|x| !is_deleted(target, x));
+    let loaded = read_reflect(db, &rows, target, registry, type_registry)?;
+    let mut indexes = HashMap::new();
+    for (index, row) in rows.iter().enumerate() {
+        let value = row.get(&key.sql_name).cloned().unwrap_or_default();
+        indexes.entry(KeyValue(value)).or_insert(index);
+    }
+
+    // Elements referencing rows which do not exist or are deleted are removed from their
+    // lists, options and lazy relations are cleared.
+    let mut missing = HashSet::new();
+    let mut next = Vec::new();
+    for (stub, value, path, position) in requests {
+        let Some(&index) = indexes.get(&KeyValue(value)) else {
+            match position {
+                Some(position) => {
+                    missing.insert(position);
+                }
+                None => clear(stub)?,
+            }
+            continue;
+        };
+
+        if let Some(placed) =
+            place(stub, loaded[index].as_partial_reflect()).map_err(|e| error(e.to_string()))?
+        {
+            next.push((placed, path));
+        }
+    }
+
+    load_eager(db, next, target, registry, type_registry, depth + 1)?;
+
+    // Put the elements of lists back in place.
+    for (i, ((value, _), (list, _))) in values.iter_mut().zip(elements).enumerate() {
+        let field = field_mut_of(&mut **value, table, column)?;
+        let ReflectMut::List(target) = field.reflect_mut() else {
+            continue;
+        };
+
+        for (j, element) in list.into_iter().enumerate() {
+            if !missing.contains(&(i, j)) {
+                target.push(element);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Clear an option or lazy relation holding the key of a row which could not be loaded.
+/// Other values cannot tell a missing row apart and keep the key.
+fn clear(stub: &mut dyn PartialReflect) -> ErmResult<()> {
+    if stub.get_represented_type_info().is_some_and(is_lazy) {
+        return set_lazy_key(stub, &SqlValue::Null);
+    }
+
+    if is_option(stub) {
+        stub.try_apply(&DynamicEnum::new("None", DynamicVariant::Unit))
+            .map_err(|e| ErmError::Unsupported(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Put a loaded value in place of the value holding its key. Returns the value in place,
+/// which is within the option or lazy relation holding it.
+fn place<'a>(
+    stub: &'a mut dyn PartialReflect,
+    loaded: &dyn PartialReflect,
+) -> Result<Option<&'a mut dyn PartialReflect>, ApplyError> {
+    let lazy = stub.get_represented_type_info().is_some_and(is_lazy);
+    let target = match lazy {
+        true => {
+            let ReflectMut::Struct(strct) = stub.reflect_mut() else {
+                return Ok(None);
+            };
+
+            let Some(value) = strct.field_mut("value") else {
+                return Ok(None);
+            };
+
+            let mut tuple = DynamicTuple::default();
+            tuple.insert_boxed(loaded.clone_value());
+            value.try_apply(&DynamicEnum::new("Some", DynamicVariant::Tuple(tuple)))?;
+            value
+        }
+        false => stub,
+    };
+
+    if !is_option(target) {
+        target.try_apply(loaded)?;
+        return Ok(Some(target));
+    }
+
+    let ReflectMut::Enum(e) = target.reflect_mut() else {
+        return Ok(None);
+    };
+
+    let Some(inner) = e.field_at_mut(0) else {
+        return Ok(None);
+    };
+
+    if !lazy {
+        inner.try_apply(loaded)?;
+    }
+
+    Ok(Some(inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("GameModes"))]
+    struct GameMode {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub spawn: SpawnPoint,
+        pub spawn_points: Vec<SpawnPoint>,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("SpawnPoints"))]
+    struct SpawnPoint {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+        pub region: Region,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Regions"), no_field_bounds)]
+    struct Region {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+        pub neighbours: Vec<Region>,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Zombies"))]
+    struct Zombie {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub spawn: Option<SpawnPoint>,
+        pub visited: Vec<Lazy<Region>>,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Region>(&app_registry).is_some());
+        assert!(registry
+            .register_type::<SpawnPoint>(&app_registry)
+            .is_some());
+        assert!(registry.register_type::<GameMode>(&app_registry).is_some());
+        assert!(registry.register_type::<Zombie>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn region(id: i64, neighbours: &[i64]) -> Region {
+        Region {
+            id,
+            name: format!("Region {}", id),
+            neighbours: neighbours
+                .iter()
+                .map(|x| Region {
+                    id: *x,
+                    ..Region::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn spawn_point(id: i64, region: &Region) -> SpawnPoint {
+        SpawnPoint {
+            id,
+            name: format!("Spawn {}", id),
+            region: region.clone(),
+        }
+    }
+
+    /// The value of a relation which was not loaded, holding only the key.
+    fn unloaded(id: i64) -> Region {
+        Region {
+            id,
+            ..Region::default()
+        }
+    }
+
+    /// Insert regions without neighbours first, the neighbours have to exist.
+    fn insert_regions(regions: &Repository<Region>, all: &[Region]) {
+        for value in all {
+            regions.insert(&region(value.id, &[])).unwrap();
+        }
+
+        for value in all {
+            assert!(regions.update(value).unwrap());
+        }
+    }
+
+    fn relations(
+        game_modes: Repository<GameMode>,
+        spawn_points: Repository<SpawnPoint>,
+        regions: Repository<Region>,
+    ) {
+        let north = region(1, &[]);
+        let south = region(2, &[]);
+        insert_regions(&regions, &[north.clone(), south.clone()]);
+
+        let points = [
+            spawn_point(1, &north),
+            spawn_point(2, &north),
+            spawn_point(3, &south),
+        ];
+        for point in points.iter() {
+            spawn_points.insert(point).unwrap();
+        }
+
+        let game_mode = GameMode {
+            id: 1,
+            spawn: points[0].clone(),
+            spawn_points: vec![points[2].clone(), points[1].clone()],
+        };
+        game_modes.insert(&game_mode).unwrap();
+
+        assert_eq!(game_modes.find(1i64).unwrap(), Some(game_mode.clone()));
+        assert_eq!(game_modes.all().unwrap(), vec![game_mode]);
+    }
+
+    fn prepare_app(plugin: BevyERMPlugin) -> App {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(plugin);
+        app.register_type::<GameMode>();
+        app.register_type::<Zombie>();
+        app.add_systems(Startup, startup);
+
+        app
+    }
+
+    #[test]
+    fn eager_relations() {
+        let mut app = prepare_app(BevyERMPlugin::default());
+        app.add_systems(PostStartup, relations);
+
+        app.update();
+    }
+
+    fn cycles(regions: Repository<Region>) {
+        insert_regions(
+            &regions,
+            &[region(1, &[2]), region(2, &[1, 3]), region(3, &[])],
+        );
+
+        // Region 1 is not loaded again as a neighbour of its neighbour.
+        let first = regions.find(1i64).unwrap().unwrap();
+        let second = &first.neighbours[0];
+        assert_eq!(second.name, "Region 2");
+        assert_eq!(second.neighbours[0], unloaded(1));
+        assert_eq!(second.neighbours[1], region(3, &[]));
+    }
+
+    #[test]
+    fn cyclic_relations() {
+        let mut app = prepare_app(BevyERMPlugin::default());
+        app.add_systems(PostStartup, cycles);
+
+        app.update();
+    }
+
+    fn depth(regions: Repository<Region>) {
+        insert_regions(
+            &regions,
+            &[
+                region(1, &[2]),
+                region(2, &[3]),
+                region(3, &[4]),
+                region(4, &[]),
+            ],
+        );
+
+        let first = regions.find(1i64).unwrap().unwrap();
+        let second = &first.neighbours[0];
+        assert_eq!(second.name, "Region 2");
+        assert_eq!(second.neighbours, vec![unloaded(3)]);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let mut app = prepare_app(BevyERMPlugin::default().with_eager_depth(1));
+        app.add_systems(PostStartup, depth);
+
+        app.update();
+    }
+
+    /// A memory database logging the tables selected from.
+    struct Logged {
+        inner: MemoryDatabase,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ErmBackend for Logged {
+        fn dialect(&self) -> SqlDialect {
+            self.inner.dialect()
+        }
+
+        fn create_schema(&self, registry: &ErmTypesRegistry) -> ErmResult<()> {
+            self.inner.create_schema(registry)
+        }
+
+        fn insert(&self, table: &TableDefinition, row: &Row) -> ErmResult<()> {
+            self.inner.insert(table, row)
+        }
+
+        fn update_if(
+            &self,
+            table: &TableDefinition,
+            row: &Row,
+            filter: &Filter,
+        ) -> ErmResult<bool> {
+            self.inner.update_if(table, row, filter)
+        }
+
+        fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool> {
+            self.inner.delete(table, key)
+        }
+
+        fn query(&self, table: &TableDefinition, select: &Select) -> ErmResult<Vec<Row>> {
+            self.log.lock().unwrap().push(table.sql_name.clone());
+            self.inner.query(table, select)
+        }
+
+        fn query_sql(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<Row>> {
+            self.inner.query_sql(sql, params)
+        }
+
+        fn replace_children(
+            &self,
+            child: &ChildTable,
+            owner: &SqlValue,
+            rows: &[Row],
+        ) -> ErmResult<()> {
+            self.inner.replace_children(child, owner, rows)
+        }
+
+        fn select_children(&self, child: &ChildTable, owners: &[SqlValue]) -> ErmResult<Vec<Row>> {
+            self.log.lock().unwrap().push(child.sql_name.clone());
+            self.inner.select_children(child, owners)
+        }
+
+        fn begin(&self) -> ErmResult<()> {
+            self.inner.begin()
+        }
+
+        fn commit(&self) -> ErmResult<()> {
+            self.inner.commit()
+        }
+
+        fn rollback(&self) -> ErmResult<()> {
+            self.inner.rollback()
+        }
+    }
+
+    #[derive(Resource)]
+    struct Log(Arc<Mutex<Vec<String>>>);
+
+    fn batches(
+        zombies: Repository<Zombie>,
+        spawn_points: Repository<SpawnPoint>,
+        regions: Repository<Region>,
+        log: Res<Log>,
+    ) {
+        insert_regions(&regions, &[region(1, &[]), region(2, &[]), region(3, &[])]);
+        let points: Vec<SpawnPoint> = (1..=3)
+            .map(|id| spawn_point(id, &region(id.min(2), &[])))
+            .collect();
+        for point in points.iter() {
+            spawn_points.insert(point).unwrap();
+        }
+
+        for id in 1..=5 {
+            let zombie = Zombie {
+                id,
+                spawn: Some(points[id as usize % 3].clone()),
+                visited: vec![Lazy::new(3i64), Lazy::new(id % 2 + 1)],
+            };
+            zombies.insert(&zombie).unwrap();
+        }
+        zombies.insert(&Zombie::default()).unwrap();
+
+        // Lazy relations hold the key only.
+        let mut all = zombies.all().unwrap();
+        assert_eq!(all.len(), 6);
+        assert_eq!(all[1].spawn.as_ref().unwrap().name, "");
+
+        // Three keys in batches of two, then the regions of the spawn points.
+        log.0.lock().unwrap().clear();
+        zombies.load_relation(&mut all, "spawn").unwrap();
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            vec![
+                "SpawnPoints",
+                "SpawnPoints",
+                "Regions",
+                "Regions_neighbours"
+            ]
+        );
+
+        for zombie in all.iter() {
+            let expected = match zombie.id {
+                0 => None,
+                id => Some(points[id as usize % 3].clone()),
+            };
+            assert_eq!(zombie.spawn, expected);
+        }
+
+        log.0.lock().unwrap().clear();
+        zombies.load_relation(&mut all, "visited").unwrap();
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            vec![
+                "Regions",
+                "Regions",
+                "Regions_neighbours",
+                "Regions_neighbours"
+            ]
+        );
+
+        let visited = &all.iter().find(|x| x.id == 1).unwrap().visited;
+        assert_eq!(visited[0].get(), Some(&region(3, &[])));
+        assert_eq!(visited[1].get(), Some(&region(2, &[])));
+
+        assert!(matches!(
+            zombies.load_relation(&mut all, "id"),
+            Err(ErmError::InvalidMapping(_))
+        ));
+    }
+
+    #[test]
+    fn batch_loading() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let backend_log = log.clone();
+        let plugin = BevyERMPlugin::default()
+            .with_batch_size(2)
+            .with_backend(move || {
+                Ok(Logged {
+                    inner: MemoryDatabase::new(),
+                    log: backend_log.clone(),
+                })
+            });
+
+        let mut app = prepare_app(plugin);
+        app.insert_resource(Log(log));
+        app.add_systems(PostStartup, batches);
+
+        app.update();
+    }
+}
@@ -0,0 +1,1036 @@
+use std::{any::TypeId, collections::HashMap, marker::PhantomData};
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{AppTypeRegistry, Res},
+    reflect::{PartialReflect, Reflect, TypePath, TypeRegistry},
+};
+
+use crate::{
+    backend::split_keys,
+    graph::Changes,
+    identity::{identity_of, KeyValue},
+    prelude::{
+        apply_child_rows, from_row, query_as, to_child_rows, to_row, Clock, ColumnDefinition,
+        ErmBackend, ErmDatabase, ErmError, ErmQuery, ErmResult, ErmTypesRegistry, Filter,
+        GraphWriter, IdentityMap, Lazy, Row, Shared, Snapshots, SqlValue, TableDefinition,
+    },
+    relations::{batch_size, is_relation, load_eager, load_relation, select_in},
+    row_mapping::{apply_value, downcast, field_mut_of, key_of},
+    soft_delete::{is_deleted, live_filter, restore_row},
+    timestamps::{stamp_insert, stamp_update},
+    tracking::dirty_columns,
+};
+
+/// Identifies a row by its key. Single values are used for tables with one key column,
+/// rows holding every key column can be used for any table.
+pub trait IntoKey {
+    fn into_key(self, table: &TableDefinition) -> ErmResult<Row>;
+}
+
+impl<V: Into<SqlValue>> IntoKey for V {
+    fn into_key(self, table: &TableDefinition) -> ErmResult<Row> {
+        let keys = table.key_columns();
+        let [key] = keys[..] else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Table {} has {} key columns, use a row to identify its rows",
+                table.sql_name,
+                keys.len()
+            )));
+        };
+
+        Ok(Row::new().with(&key.sql_name, self))
+    }
+}
+
+impl IntoKey for Row {
+    fn into_key(self, _table: &TableDefinition) -> ErmResult<Row> {
+        Ok(self)
+    }
+}
+
+/// Typed access to the table of T. The table is described by the definition registered
+/// with the ERM-Registry, so T needs to be registered before the repository is used.
+#[derive(SystemParam)]
+pub struct Repository<'w, T: Reflect + TypePath> {
+    database: Res<'w, ErmDatabase>,
+    registry: Res<'w, ErmTypesRegistry>,
+    identities: Res<'w, IdentityMap>,
+    snapshots: Res<'w, Snapshots>,
+    clock: Res<'w, Clock>,
+    type_registry: Res<'w, AppTypeRegistry>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Reflect + TypePath> Repository<'_, T> {
+    /// Return the definition of the table of T.
+    pub fn table(&self) -> ErmResult<&TableDefinition> {
+        table_of::<T>(&self.registry)
+    }
+
+    /// Insert the value, including the elements of its collections.
+    pub fn insert(&self, value: &T) -> ErmResult<()> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        let row = self.database.transaction(|db| {
+            let mut row = to_row(value, table, &self.registry, &type_registry)?;
+            stamp_insert(table, &mut row, self.clock.now());
+            db.insert(table, &row)?;
+            self.identities.remove::<T>(&identity_of(table, &row));
+
+            write_children(db, value, &row, table, &self.registry, &type_registry)?;
+            Ok(row)
+        })?;
+
+        self.snapshots.insert(identity_of(table, &row), value);
+        Ok(())
+    }
+
+    /// Insert the values in a single transaction, see `ErmBackend::insert_many`.
+    pub fn insert_many(&self, values: &[T]) -> ErmResult<()> {
+        self.write_many(values, None)
+    }
+
+    /// Insert the values or update the rows holding their keys, in a single transaction.
+    /// See `ErmBackend::upsert_many`.
+    pub fn upsert_many(&self, values: &[T]) -> ErmResult<()> {
+        let table = self.table()?;
+        let keys = table.key_columns();
+        let conflict: Vec<&str> = keys.iter().map(|x| x.sql_name.as_str()).collect();
+
+        self.write_many(values, Some(&conflict))
+    }
+
+    /// Insert the values or update the rows holding their value in the given key or unique
+    /// column, in a single transaction. Updated rows keep their key, so values stored in
+    /// child tables can only be upserted by key. See `ErmBackend::upsert_many`.
+    pub fn upsert_many_on(&self, values: &[T], column: &str) -> ErmResult<()> {
+        let table = self.table()?;
+        let Some(column) = table.get(column).filter(|x| x.is_key() || x.is_unique()) else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Table {} has no key or unique column {}",
+                table.sql_name, column
+            )));
+        };
+
+        if !column.is_key() && !table.child_tables().is_empty() {
+            return Err(ErmError::Unsupported(format!(
+                "Table {} stores collections in child tables, its values can only be upserted by key",
+                table.sql_name
+            )));
+        }
+
+        self.write_many(values, Some(&[column.sql_name.as_str()]))
+    }
+
+    /// Update the row holding the key of the value. Returns false, if there is no such row.
+    /// Values read or written through a repository before write the columns which changed
+    /// since only, nothing is written if no column changed, see `dirty_columns`.
+    /// Tables with a version column fail with `ErmError::ConcurrencyConflict`, if the row
+    /// no longer holds the version of the value, see `update_row`.
+    pub fn update(&self, value: &T) -> ErmResult<bool> {
+        Ok(self.update_value(value)?.is_some())
+    }
+
+    /// Update the value like `update` and set its version to the version stored with the row,
+    /// so the value can be updated again.
+    pub fn update_versioned(&self, value: &mut T) -> ErmResult<bool> {
+        let Some(written) = self.update_value(value)? else {
+            return Ok(false);
+        };
+
+        let table = self.table()?;
+        let Some(column) = table.version_column().filter(|_| written) else {
+            return Ok(true);
+        };
+
+        let type_registry = self.type_registry.read();
+        let row = to_row(value, table, &self.registry, &type_registry)?;
+        let version = row.get(&column.sql_name).cloned().unwrap_or_default();
+        apply_value(
+            field_mut_of(value.as_partial_reflect_mut(), table, column)?,
+            &next_version(table, column, &version)?,
+            &column.sql_type,
+            None,
+            &self.registry,
+            &type_registry,
+        )?;
+
+        self.snapshots.insert(identity_of(table, &row), value);
+        Ok(true)
+    }
+
+    /// Return the sql names of the columns whose fields changed since the value's row was
+    /// read or written through a repository. Returns None, if there is no snapshot of the row,
+    /// an update writes all columns then.
+    pub fn dirty_columns(&self, value: &T) -> ErmResult<Option<Vec<String>>> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        let row = to_row(value, table, &self.registry, &type_registry)?;
+        let Some(snapshot) = self.snapshots.get::<T>(&identity_of(table, &row)) else {
+            return Ok(None);
+        };
+
+        let columns = dirty_columns(value.as_partial_reflect(), snapshot.as_ref(), table)?;
+        Ok(Some(
+            columns.into_iter().map(|x| x.sql_name.clone()).collect(),
+        ))
+    }
+
+    /// Insert the value or update it, if a row with its key exists.
+    pub fn upsert(&self, value: &T) -> ErmResult<()> {
+        self.database.transaction(|_| {
+            if !self.update(value)? {
+                self.insert(value)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Insert or update the value and the values its relations cascade saves to,
+    /// in a single transaction. See `GraphWriter::save`.
+    pub fn save(&self, value: &T) -> ErmResult<()> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        // Cascades write rows of other tables as well.
+        let mut changes = Changes::default();
+        let result = GraphWriter::new(self.database.backend(), &self.registry, &type_registry)
+            .with_clock(&self.clock)
+            .save_tracked(value.as_partial_reflect(), table, &mut changes);
+        self.forget(&changes);
+
+        result
+    }
+
+    /// Delete the row with the given key and the rows its relations cascade deletes to.
+    /// Tables with a soft delete column mark the rows as deleted, see `SoftDelete`.
+    /// Returns false, if there is no such row.
+    pub fn delete_by_key(&self, key: impl IntoKey) -> ErmResult<bool> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        // Referential actions and cascades change rows of other tables as well.
+        let key = key.into_key(table)?;
+        let mut changes = Changes::default();
+        let result = GraphWriter::new(self.database.backend(), &self.registry, &type_registry)
+            .with_clock(&self.clock)
+            .delete_tracked(table, &key, &mut changes);
+        self.forget(&changes);
+
+        result
+    }
+
+    /// Mark the soft deleted row with the given key as not deleted again.
+    /// Returns false, if there is no such row or it was not deleted.
+    pub fn restore(&self, key: impl IntoKey) -> ErmResult<bool> {
+        let table = self.table()?;
+        if table.soft_delete_column().is_none() {
+            return Err(ErmError::Unsupported(format!(
+                "Table {} does not soft delete its rows",
+                table.sql_name
+            )));
+        }
+
+        let key = key.into_key(table)?;
+        let restored = self
+            .database
+            .transaction(|db| restore_row(db, table, &key))?;
+        let identity = identity_of(table, &key);
+        self.identities.remove::<T>(&identity);
+        self.snapshots.remove::<T>(&identity);
+
+        Ok(restored)
+    }
+
+    /// Return the value with the given key. Rows marked as deleted are not returned.
+    pub fn find(&self, key: impl IntoKey) -> ErmResult<Option<T>> {
+        let table = self.table()?;
+        let Some(row) = self.select_by_key(table, &key.into_key(table)?)? else {
+            return Ok(None);
+        };
+
+        Ok(self.read(vec![row])?.pop())
+    }
+
+    /// Return the value with the given key, shared with everyone who loaded the row before
+    /// and still holds its value, see `IdentityMap`.
+    pub fn find_shared(&self, key: impl IntoKey) -> ErmResult<Option<Shared<T>>> {
+        let table = self.table()?;
+        let key = key.into_key(table)?;
+        if let Some(shared) = self.identities.get::<T>(&identity_of(table, &key)) {
+            return Ok(Some(shared));
+        }
+
+        let Some(row) = self.select_by_key(table, &key)? else {
+            return Ok(None);
+        };
+
+        Ok(self.share(vec![row])?.pop())
+    }
+
+    /// Return the values matching the query, shared like the values of `find_shared`.
+    /// Rows loaded before return the shared value, even if it was changed since.
+    pub fn query_shared(&self, query: ErmQuery<T>) -> ErmResult<Vec<Shared<T>>> {
+        let select = query.build(&self.registry)?;
+        let rows = self.database.query(self.table()?, &select)?;
+
+        self.share(rows)
+    }
+
+    /// Load the value referenced by a lazy relation, unless it was loaded before.
+    /// Returns None, if the relation is null or the referenced row does not exist.
+    pub fn resolve<'a>(&self, lazy: &'a mut Lazy<T>) -> ErmResult<Option<&'a T>> {
+        if !lazy.is_loaded() && !lazy.is_null() {
+            if let Some(value) = self.find(lazy.key().clone())? {
+                lazy.set(value);
+            }
+        }
+
+        Ok(lazy.get())
+    }
+
+    /// Load the values referenced by a relation field of all values, selecting the referenced
+    /// rows in batches. Use this to load lazy relations of many values at once, like
+    /// options and lists of lazy relations. The eager relations of the loaded values are loaded
+    /// as well.
+    pub fn load_relation(&self, values: &mut [T], field: &str) -> ErmResult<()> {
+        let table = self.table()?;
+        let Some(column) = table.get(field).filter(|x| is_relation(x)) else {
+            return Err(ErmError::InvalidMapping(format!(
+                "Table {} has no relation {}",
+                table.sql_name, field
+            )));
+        };
+
+        let type_registry = self.type_registry.read();
+        let mut loading = Vec::new();
+        for value in values.iter_mut() {
+            let key = key_of(
+                value.as_partial_reflect(),
+                table,
+                &self.registry,
+                &type_registry,
+            )?;
+            loading.push((
+                value.as_partial_reflect_mut(),
+                vec![(table.sql_name.as_str(), key)],
+            ));
+        }
+
+        load_relation(
+            self.database.backend(),
+            &mut loading,
+            table,
+            column,
+            &self.registry,
+            &type_registry,
+            0,
+        )
+    }
+
+    /// Return all values stored in the table, except the rows marked as deleted.
+    pub fn all(&self) -> ErmResult<Vec<T>> {
+        let table = self.table()?;
+        let rows = self.database.select(table, &live_filter(table))?;
+
+        self.read(rows)
+    }
+
+    /// Return the values matching the query.
+    pub fn query(&self, query: ErmQuery<T>) -> ErmResult<Vec<T>> {
+        let select = query.build(&self.registry)?;
+        let rows = self.database.query(self.table()?, &select)?;
+
+        self.read(rows)
+    }
+
+    /// Run a statement written in the dialect of the database and return the values created
+    /// from the rows of its result, see `query_as`.
+    pub fn query_as(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<T>> {
+        let type_registry = self.type_registry.read();
+
+        query_as::<T>(
+            self.database.backend(),
+            sql,
+            params,
+            &self.registry,
+            &type_registry,
+        )
+    }
+
+    /// Insert the values, or upsert them on the conflict columns, and write their collections.
+    fn write_many(&self, values: &[T], conflict_columns: Option<&[&str]>) -> ErmResult<()> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        let now = self.clock.now();
+        let mut rows = Vec::new();
+        for value in values {
+            let mut row = to_row(value, table, &self.registry, &type_registry)?;
+            stamp_insert(table, &mut row, now);
+            rows.push(row);
+        }
+
+        let mut changes = Changes::default();
+        let result = self.database.transaction(|db| {
+            match conflict_columns {
+                Some(conflict) => {
+                    self.upserted(db, table, &rows, conflict, &mut changes)?;
+                    db.upsert_many(table, &rows, conflict)?;
+                }
+                None => db.insert_many(table, &rows)?,
+            }
+
+            for (value, row) in values.iter().zip(rows.iter()) {
+                write_children(db, value, row, table, &self.registry, &type_registry)?;
+            }
+            Ok(())
+        });
+
+        // Upserted values are not snapshot, as the rows they update may hold other keys.
+        if conflict_columns.is_some() {
+            self.forget(&changes);
+            return result;
+        }
+
+        result?;
+        for (value, row) in values.iter().zip(rows.iter()) {
+            self.identities.remove::<T>(&identity_of(table, row));
+            self.snapshots.insert(identity_of(table, row), value);
+        }
+        Ok(())
+    }
+
+    /// Add the rows the upsert of the rows may update to the changes: the rows holding
+    /// their keys, and the rows holding their values in a unique conflict column.
+    fn upserted(
+        &self,
+        db: &dyn ErmBackend,
+        table: &TableDefinition,
+        rows: &[Row],
+        conflict_columns: &[&str],
+        changes: &mut Changes,
+    ) -> ErmResult<()> {
+        for row in rows {
+            changes.add(table, row);
+        }
+        changes.add_acting(&self.registry, table, false);
+
+        if let [column] = conflict_columns {
+            if !table.get(column).is_some_and(|x| x.is_key()) {
+                let values: Vec<SqlValue> =
+                    rows.iter().filter_map(|x| x.get(column).cloned()).collect();
+                for row in select_in(db, table, column, &values, &self.registry)? {
+                    changes.add(table, &row);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Select the row with the given key, unless it is marked as deleted.
+    fn select_by_key(&self, table: &TableDefinition, key: &Row) -> ErmResult<Option<Row>> {
+        Ok(self
+            .database
+            .select_by_key(table, key)?
+            .filter(|x| !is_deleted(table, x)))
+    }
+
+    /// Update the row holding the key of the value, writing the changed columns only.
+    /// Returns None, if there is no such row, otherwise whether anything was written.
+    fn update_value(&self, value: &T) -> ErmResult<Option<bool>> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        let row = to_row(value, table, &self.registry, &type_registry)?;
+        let key = identity_of(table, &row);
+        let columns = match self.snapshots.get::<T>(&key) {
+            Some(snapshot) => dirty_columns(value.as_partial_reflect(), snapshot.as_ref(), table)?,
+            None => table.columns(),
+        };
+        if columns.is_empty() {
+            // Nothing is written, the row still has to exist though.
+            let (key_row, _) = split_keys(table, &row)?;
+            return Ok(self.select_by_key(table, &key_row)?.map(|_| false));
+        }
+
+        let updated = self.database.transaction(|db| {
+            let mut changed = Row::new();
+            for column in table
+                .key_columns()
+                .into_iter()
+                .chain(table.version_column())
+                .chain(columns.iter().copied())
+            {
+                for (name, _) in column.sql_columns() {
+                    if let Some(value) = row.get(&name) {
+                        changed.push(&name, value.clone());
+                    }
+                }
+            }
+
+            stamp_update(table, &mut changed, self.clock.now());
+            if !update_row(db, table, &changed)? {
+                return Ok(false);
+            }
+
+            let mut changes = Changes::default();
+            changes.add(table, &row);
+            changes.add_acting(&self.registry, table, false);
+            self.forget(&changes);
+
+            for column in columns.iter() {
+                write_child_table(
+                    db,
+                    value,
+                    &row,
+                    table,
+                    column,
+                    &self.registry,
+                    &type_registry,
+                )?;
+            }
+            Ok(true)
+        })?;
+
+        if !updated {
+            return Ok(None);
+        }
+
+        self.snapshots.insert(key, value);
+        Ok(Some(true))
+    }
+
+    /// Create the values from rows of the table and share them through the identity map.
+    fn share(&self, rows: Vec<Row>) -> ErmResult<Vec<Shared<T>>> {
+        let table = self.table()?;
+        let keys: Vec<_> = rows.iter().map(|x| identity_of(table, x)).collect();
+        let values = self.read(rows)?;
+
+        Ok(self
+            .identities
+            .share(keys.into_iter().zip(values).collect()))
+    }
+
+    /// Drop the changed rows from the identity map and the snapshots.
+    fn forget(&self, changes: &Changes) {
+        self.identities.forget(changes);
+        self.snapshots.forget(changes);
+    }
+
+    /// Create the values from rows of the table and read the elements of their collections.
+    fn read(&self, rows: Vec<Row>) -> ErmResult<Vec<T>> {
+        let table = self.table()?;
+        let type_registry = self.type_registry.read();
+
+        let keys: Vec<_> = rows.iter().map(|x| identity_of(table, x)).collect();
+        let values: Vec<T> = read_values(
+            self.database.backend(),
+            rows,
+            table,
+            &self.registry,
+            &type_registry,
+        )?;
+
+        for (key, value) in keys.into_iter().zip(values.iter()) {
+            self.snapshots.insert(key, value);
+        }
+        Ok(values)
+    }
+}
+
+/// Return the table definition of T.
+pub(crate) fn table_of<T: TypePath + 'static>(
+    registry: &ErmTypesRegistry,
+) -> ErmResult<&TableDefinition> {
+    registry
+        .get_table_definition_by_type_id(TypeId::of::<T>())
+        .ok_or_else(|| {
+            ErmError::Unsupported(format!(
+                "Type {} is not registered with the ERM-Registry",
+                T::short_type_path()
+            ))
+        })
+}
+
+/// Return the value of the owner column of the value's child tables.
+fn owner_of(row: &Row, table: &TableDefinition) -> ErmResult<SqlValue> {
+    let keys = table.key_columns();
+    keys.first()
+        .and_then(|x| row.get(&x.sql_name))
+        .cloned()
+        .ok_or_else(|| ErmError::MissingColumn(table.sql_name.clone(), "key".to_owned()))
+}
+
+/// Update the row. Tables with a version column only update the row, if it still holds
+/// the version of the given row, and store the version incremented. Fails with
+/// `ErmError::ConcurrencyConflict`, if the row holds another version.
+/// Rows marked as deleted are not updated and the soft delete column is never written,
+/// see `delete_row` and `restore_row`.
+pub(crate) fn update_row(
+    db: &dyn ErmBackend,
+    table: &TableDefinition,
+    row: &Row,
+) -> ErmResult<bool> {
+    let mut changed = row.clone();
+    if let Some(column) = table.soft_delete_column() {
+        changed.columns.retain(|x| x.0 != column.sql_name);
+    }
+
+    let Some((column, version)) = table
+        .version_column()
+        .and_then(|x| row.get(&x.sql_name).map(|v| (x, v)))
+    else {
+        return db.update_if(table, &changed, &live_filter(table));
+    };
+
+    changed.push(&column.sql_name, next_version(table, column, version)?);
+    let filter = Filter::Eq(column.sql_name.clone(), version.clone()).and(live_filter(table));
+    if db.update_if(table, &changed, &filter)? {
+        return Ok(true);
+    }
+
+    let (key, _) = split_keys(table, row)?;
+    let existing = db.select_by_key(table, &key)?;
+    if existing.is_none_or(|x| is_deleted(table, &x)) {
+        return Ok(false);
+    }
+
+    let values: Vec<String> = key.values().iter().map(|x| x.to_string()).collect();
+    Err(ErmError::ConcurrencyConflict(
+        table.sql_name.clone(),
+        values.join(", "),
+    ))
+}
+
+/// Return the version following the given one.
+fn next_version(
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+    version: &SqlValue,
+) -> ErmResult<SqlValue> {
+    match version {
+        SqlValue::Integer(v) => Ok(SqlValue::Integer(v.wrapping_add(1))),
+        SqlValue::Unsigned(v) => Ok(SqlValue::Unsigned(v.wrapping_add(1))),
+        _ => Err(ErmError::InvalidValue(
+            table.sql_name.clone(),
+            column.sql_name.clone(),
+            format!("{} is not a version", version),
+        )),
+    }
+}
+
+/// Replace the elements of all collections stored in child tables.
+pub(crate) fn write_children(
+    db: &dyn ErmBackend,
+    value: &dyn PartialReflect,
+    row: &Row,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<()> {
+    for column in table.columns() {
+        write_child_table(db, value, row, table, column, registry, type_registry)?;
+    }
+
+    Ok(())
+}
+
+/// Replace the elements of the collection, if the column is stored in a child table.
+fn write_child_table(
+    db: &dyn ErmBackend,
+    value: &dyn PartialReflect,
+    row: &Row,
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<()> {
+    let Some(child) = column.child_table() else {
+        return Ok(());
+    };
+
+    let rows = to_child_rows(value, table, column, registry, type_registry)?;
+    db.replace_children(child, &owner_of(row, table)?, &rows)
+}
+
+/// Create values from rows and read the elements of their collections,
+/// using a single select per child table. Relations marked for eager loading are loaded.
+pub(crate) fn read_values<T: Reflect>(
+    db: &dyn ErmBackend,
+    rows: Vec<Row>,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Vec<T>> {
+    let mut values = read_reflect(db, &rows, table, registry, type_registry)?;
+
+    let mut loaded = Vec::new();
+    for (value, row) in values.iter_mut().zip(rows.iter()) {
+        let path = vec![(table.sql_name.as_str(), owner_of(row, table)?)];
+        loaded.push((value.as_partial_reflect_mut(), path));
+    }
+    load_eager(db, loaded, table, registry, type_registry, 0)?;
+
+    values.into_iter().map(|x| downcast(x, table)).collect()
+}
+
+/// Create instances of the table's type from rows and read the elements of their collections,
+/// using a single select per child table.
+pub(crate) fn read_reflect(
+    db: &dyn ErmBackend,
+    rows: &[Row],
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Vec<Box<dyn Reflect>>> {
+    let mut values = Vec::new();
+    for row in rows.iter() {
+        values.push(from_row(row, table, registry, type_registry)?);
+    }
+
+    let children: Vec<_> = table
+        .columns()
+        .into_iter()
+        .filter(|x| x.child_table().is_some())
+        .collect();
+    if children.is_empty() {
+        return Ok(values);
+    }
+
+    let owners = rows
+        .iter()
+        .map(|x| owner_of(x, table))
+        .collect::<ErmResult<Vec<SqlValue>>>()?;
+
+    for column in children {
+        let Some(child) = column.child_table() else {
+            continue;
+        };
+
+        let mut elements = Vec::new();
+        for chunk in owners.chunks(batch_size(db, registry)) {
+            elements.extend(db.select_children(child, chunk)?);
+        }
+
+        // Group the elements by their owner, keeping their order.
+        let mut owned: HashMap<KeyValue, Vec<Row>> = HashMap::new();
+        for element in elements {
+            let owner = element.get(&child.owner_column.sql_name).cloned();
+            owned
+                .entry(KeyValue(owner.unwrap_or_default()))
+                .or_default()
+                .push(element);
+        }
+
+        for (value, owner) in values.iter_mut().zip(owners.iter()) {
+            let owned = owned.get(&KeyValue(owner.clone()));
+            apply_child_rows(
+                value.as_partial_reflect_mut(),
+                owned.map(Vec::as_slice).unwrap_or_default(),
+                table,
+                column,
+                registry,
+                type_registry,
+            )?;
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@MaxLength::new(16), @Unique)]
+        pub name: String,
+        pub comment: Option<String>,
+        pub titles: Vec<String>,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn player(id: i64, name: &str) -> Player {
+        Player {
+            id,
+            name: name.to_owned(),
+            comment: None,
+            titles: vec!["First".to_owned(), "Second".to_owned()],
+        }
+    }
+
+    fn crud(players: Repository<Player>) {
+        players.insert(&player(1, "Ann")).unwrap();
+        players.insert(&player(2, "Bob")).unwrap();
+        assert!(players.insert(&player(3, "Ann")).is_err());
+
+        assert_eq!(players.find(1i64).unwrap(), Some(player(1, "Ann")));
+        assert_eq!(players.find(3i64).unwrap(), None);
+
+        let mut changed = player(1, "Ann");
+        changed.comment = Some("Tank".to_owned());
+        changed.titles = vec!["Third".to_owned()];
+        assert!(players.update(&changed).unwrap());
+        assert!(!players.update(&player(5, "Eve")).unwrap());
+        assert_eq!(players.find(1i64).unwrap(), Some(changed.clone()));
+
+        players.upsert(&player(2, "Bobby")).unwrap();
+        players.upsert(&player(4, "Dan")).unwrap();
+
+        let all = players.all().unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.contains(&changed));
+        assert!(all.contains(&player(2, "Bobby")));
+
+        assert!(players.delete_by_key(4i64).unwrap());
+        assert!(!players.delete_by_key(4i64).unwrap());
+        assert!(players.delete_by_key(Row::new().with("id", 2i64)).unwrap());
+        assert_eq!(players.all().unwrap(), vec![changed]);
+    }
+
+    fn bulk_writes(players: Repository<Player>) {
+        let values: Vec<Player> = (1..=3).map(|i| player(i, &format!("P{}", i))).collect();
+        players.insert_many(&values).unwrap();
+        assert_eq!(players.all().unwrap(), values);
+
+        // Nothing is written, if any value fails.
+        assert!(players
+            .insert_many(&[player(4, "P4"), player(5, "P1")])
+            .is_err());
+        assert_eq!(players.find(4i64).unwrap(), None);
+
+        let mut changed = player(2, "Bob");
+        changed.titles = vec!["Third".to_owned()];
+        players
+            .upsert_many(&[changed.clone(), player(4, "Dan")])
+            .unwrap();
+        assert_eq!(players.find(2i64).unwrap(), Some(changed));
+        assert_eq!(players.find(4i64).unwrap(), Some(player(4, "Dan")));
+        assert_eq!(players.all().unwrap().len(), 4);
+
+        // Upserts on unique columns keep the keys of existing rows, collections need the key.
+        assert!(matches!(
+            players.upsert_many_on(&[player(5, "Dan")], "name"),
+            Err(ErmError::Unsupported(_))
+        ));
+        assert!(matches!(
+            players.upsert_many_on(&[player(5, "Dan")], "comment"),
+            Err(ErmError::InvalidMapping(_))
+        ));
+    }
+
+    fn queries(players: Repository<Player>) {
+        for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cid"), (4, "Dan")] {
+            players.insert(&player(id, name)).unwrap();
+        }
+
+        let query = ErmQuery::<Player>::new()
+            .filter(col("name").not_eq("Bob"))
+            .filter(col("id").le(3))
+            .order_by_desc("id")
+            .limit(10);
+        assert_eq!(
+            players.query(query).unwrap(),
+            vec![player(3, "Cid"), player(1, "Ann")]
+        );
+
+        let query = ErmQuery::<Player>::new().order_by("name").offset(3);
+        assert_eq!(players.query(query).unwrap(), vec![player(4, "Dan")]);
+
+        // Unknown columns and collections cannot be queried.
+        let query = ErmQuery::<Player>::new().filter(col("level").eq(1));
+        assert!(matches!(
+            players.query(query),
+            Err(ErmError::InvalidMapping(_))
+        ));
+        let query = ErmQuery::<Player>::new().order_by("titles");
+        assert!(matches!(
+            players.query(query),
+            Err(ErmError::InvalidMapping(_))
+        ));
+    }
+
+    #[test]
+    fn query() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Player>();
+
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, queries);
+
+        app.update();
+    }
+
+    #[test]
+    fn bulk() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Player>();
+
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, bulk_writes);
+
+        app.update();
+    }
+
+    #[test]
+    fn repository() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Player>();
+
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, crud);
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Accounts"))]
+    struct Account {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub balance: i64,
+        #[reflect(@Version)]
+        pub version: u32,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct TextVersion {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@Version)]
+        pub version: String,
+    }
+
+    fn versions(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(matches!(
+            registry.try_register_type::<TextVersion>(&app_registry),
+            Err(ErmError::InvalidMapping(_))
+        ));
+        assert!(registry.register_type::<Account>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn concurrent_updates(accounts: Repository<Account>, snapshots: Res<Snapshots>) {
+        let account = Account {
+            id: 1,
+            balance: 10,
+            version: 1,
+        };
+        accounts.insert(&account).unwrap();
+
+        // Each write stores the next version.
+        let mut first = accounts.find(1i64).unwrap().unwrap();
+        let stale = first.clone();
+        first.balance = 20;
+        assert!(accounts.update_versioned(&mut first).unwrap());
+        assert_eq!(first.version, 2);
+        assert_eq!(accounts.find(1i64).unwrap(), Some(first.clone()));
+
+        // Writing a value loaded before fails, nothing changes.
+        let mut changed = stale.clone();
+        changed.balance = 30;
+        snapshots.clear();
+        assert!(matches!(
+            accounts.update(&changed),
+            Err(ErmError::ConcurrencyConflict(table, key)) if table == "Accounts" && key == "1"
+        ));
+        assert_eq!(accounts.find(1i64).unwrap(), Some(first.clone()));
+
+        // Values without changes are not written and keep their version.
+        assert!(accounts.update_versioned(&mut first).unwrap());
+        assert_eq!(first.version, 2);
+        first.balance = 40;
+        assert!(accounts.update(&first).unwrap());
+        assert_eq!(accounts.find(1i64).unwrap().unwrap().version, 3);
+
+        // Bulk upserts would ignore the version.
+        assert!(matches!(
+            accounts.upsert_many(&[stale]),
+            Err(ErmError::Unsupported(_))
+        ));
+        assert_eq!(accounts.find(1i64).unwrap().unwrap().balance, 40);
+
+        accounts.delete_by_key(1i64).unwrap();
+        assert!(!accounts.update(&changed).unwrap());
+    }
+
+    #[test]
+    fn optimistic_concurrency() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Account>();
+        app.register_type::<TextVersion>();
+
+        app.add_systems(Startup, versions);
+        app.add_systems(PostStartup, concurrent_updates);
+
+        app.update();
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct Unregistered {
+        pub id: i64,
+    }
+
+    #[derive(Reflect, Default)]
+    struct WithoutDefault {
+        #[reflect(@Key)]
+        pub id: i64,
+    }
+
+    fn without_default(app_registry: Res<AppTypeRegistry>, mut registry: ResMut<ErmTypesRegistry>) {
+        assert!(matches!(
+            registry.try_register_type::<WithoutDefault>(&app_registry),
+            Err(ErmError::Unsupported(_))
+        ));
+    }
+
+    fn unregistered(repository: Repository<Unregistered>) {
+        assert!(matches!(
+            repository.find(1i64),
+            Err(ErmError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn unregistered_type() {
+        let mut app = App::new();
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<WithoutDefault>();
+        app.add_systems(Startup, (without_default, unregistered));
+
+        app.update();
+    }
+}
@@ -0,0 +1,1102 @@
+use std::any::TypeId;
+
+use bevy::reflect::{
+    prelude::ReflectDefault, DynamicEnum, DynamicTuple, DynamicVariant, PartialReflect, Reflect,
+    ReflectMut, ReflectRef, TypeInfo, TypeRegistry,
+};
+
+use crate::{
+    erm_types_registry::ErmTypesRegistry,
+    error::{ErmError, ErmResult},
+    framed_blob::{apply_framed_blob, is_option, option_default, to_framed_blob},
+    from_blob::{
+        reflect_from_blob, reflect_from_packed_blob, reflect_into_blob, reflect_into_packed_blob,
+    },
+    json::{apply_json, to_json},
+    lazy::{is_lazy, lazy_key, set_lazy_key},
+    prelude::{
+        ChildColumn, ChildTable, ColumnDefinition, ColumnStorage, FieldConstraint, SqlType,
+        TableDefinition,
+    },
+    sql_value::{Row, SqlValue},
+};
+
+/// Convert a reflected struct into a row of its table. The columns are ordered as the fields
+/// of the struct and named by their sql name. Options are unwrapped, blobs are encoded and
+/// relations are replaced by the key of the referenced value.
+/// Fields stored in child tables are not part of the row, see `to_child_rows`.
+pub fn to_row(
+    value: &dyn PartialReflect,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Row> {
+    let mut row = Row::new();
+    for column in table.columns() {
+        let field = field_of(value, table, column)?;
+        let error = |e: ErmError| {
+            ErmError::InvalidValue(
+                table.sql_name.clone(),
+                column.sql_name.clone(),
+                e.to_string(),
+            )
+        };
+
+        match &column.storage {
+            ColumnStorage::Inline => {
+                let value = to_value(
+                    field,
+                    &column.sql_type,
+                    reference_key(column),
+                    registry,
+                    type_registry,
+                )
+                .map_err(error)?;
+
+                row.push(&column.sql_name, value);
+            }
+
+            ColumnStorage::FramedBlob => {
+                let value = match unwrap_option(field) {
+                    Some(v) => SqlValue::Blob(to_framed_blob(v).map_err(error)?),
+                    None => SqlValue::Null,
+                };
+
+                row.push(&column.sql_name, value);
+            }
+
+            ColumnStorage::Decomposed(components) => {
+                let inner = unwrap_option(field);
+                for (component, (name, sql_type)) in components.iter().zip(column.sql_columns()) {
+                    let value = match inner.map(|x| x.reflect_ref()) {
+                        Some(ReflectRef::Struct(s)) => {
+                            let Some(component) = s.field(component) else {
+                                return Err(error(ErmError::Unsupported(format!(
+                                    "{} has no component {}",
+                                    s.reflect_type_path(),
+                                    component
+                                ))));
+                            };
+
+                            to_value(component, &sql_type, None, registry, type_registry)
+                                .map_err(error)?
+                        }
+                        Some(_) => {
+                            return Err(error(ErmError::Unsupported(format!(
+                                "{} cannot be decomposed",
+                                field.reflect_type_path()
+                            ))))
+                        }
+                        None => SqlValue::Null,
+                    };
+
+                    row.push(&name, value);
+                }
+            }
+
+            ColumnStorage::ChildTable(_) => {}
+
+            ColumnStorage::PackedBlob => {
+                let value = match unwrap_option(field) {
+                    Some(v) => SqlValue::Blob(reflect_into_packed_blob(v).map_err(error)?),
+                    None => SqlValue::Null,
+                };
+
+                row.push(&column.sql_name, value);
+            }
+        }
+    }
+
+    Ok(row)
+}
+
+/// Convert the elements of a field stored in a child table into rows of the child table.
+/// The rows hold all columns of the child table but the owner column.
+pub fn to_child_rows(
+    value: &dyn PartialReflect,
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Vec<Row>> {
+    let Some(child) = column.child_table() else {
+        return Err(ErmError::InvalidMapping(format!(
+            "Column {} of table {} is not stored in a child table",
+            column.sql_name, table.sql_name
+        )));
+    };
+
+    let error = |e: ErmError| {
+        ErmError::InvalidValue(
+            child.sql_name.clone(),
+            column.sql_name.clone(),
+            e.to_string(),
+        )
+    };
+    let convert = |value: &dyn PartialReflect, sql_type: &SqlType| {
+        to_value(value, sql_type, None, registry, type_registry).map_err(error)
+    };
+
+    // An optional relation without value has no elements.
+    let Some(field) = unwrap_option(field_of(value, table, column)?) else {
+        return Ok(Vec::new());
+    };
+
+    let value_column = &child.value_column;
+    let mut rows = Vec::new();
+    match field.reflect_ref() {
+        ReflectRef::List(list) => {
+            for (i, element) in list.iter().enumerate() {
+                rows.push(list_row(
+                    child,
+                    i,
+                    convert(element, &value_column.sql_type)?,
+                ));
+            }
+        }
+
+        ReflectRef::Array(array) => {
+            for (i, element) in array.iter().enumerate() {
+                rows.push(list_row(
+                    child,
+                    i,
+                    convert(element, &value_column.sql_type)?,
+                ));
+            }
+        }
+
+        ReflectRef::Map(map) => {
+            let Some(key_column) = &child.key_column else {
+                return Err(error(ErmError::InvalidMapping(
+                    "child table of a map has no key column".to_owned(),
+                )));
+            };
+
+            for (key, element) in map.iter() {
+                let mut row = Row::new();
+                row.push(&key_column.sql_name, convert(key, &key_column.sql_type)?);
+                row.push(
+                    &value_column.sql_name,
+                    convert(element, &value_column.sql_type)?,
+                );
+                rows.push(row);
+            }
+        }
+
+        ReflectRef::Set(set) => {
+            for element in set.iter() {
+                let mut row = Row::new();
+                row.push(
+                    &value_column.sql_name,
+                    convert(element, &value_column.sql_type)?,
+                );
+                rows.push(row);
+            }
+        }
+
+        _ => {
+            return Err(error(ErmError::Unsupported(format!(
+                "{} is not a collection",
+                field.reflect_type_path()
+            ))))
+        }
+    }
+
+    Ok(rows)
+}
+
+fn list_row(child: &ChildTable, position: usize, value: SqlValue) -> Row {
+    let mut row = Row::new();
+    if let Some(ordinal) = &child.ordinal_column {
+        row.push(&ordinal.sql_name, SqlValue::Unsigned(position as u64));
+    }
+
+    row.push(&child.value_column.sql_name, value);
+    row
+}
+
+/// Return the field of the struct the column is mapped to.
+pub(crate) fn field_of<'a>(
+    value: &'a dyn PartialReflect,
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+) -> ErmResult<&'a dyn PartialReflect> {
+    let ReflectRef::Struct(strct) = value.reflect_ref() else {
+        return Err(ErmError::Unsupported(format!(
+            "{} is not a struct and cannot be stored in table {}",
+            value.reflect_type_path(),
+            table.sql_name
+        )));
+    };
+
+    strct.field(&column.rust_name).ok_or_else(|| {
+        ErmError::InvalidValue(
+            table.sql_name.clone(),
+            column.sql_name.clone(),
+            format!(
+                "{} has no field {}",
+                value.reflect_type_path(),
+                column.rust_name
+            ),
+        )
+    })
+}
+
+/// Return the value of the first key column of a value.
+pub(crate) fn key_of(
+    value: &dyn PartialReflect,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<SqlValue> {
+    let Some(key) = table.key_columns().first().copied() else {
+        return Err(ErmError::MissingColumn(
+            table.sql_name.clone(),
+            "key".to_owned(),
+        ));
+    };
+
+    let field = field_of(value, table, key)?;
+    to_value(field, &key.sql_type, None, registry, type_registry)
+}
+
+/// Return the name of the key field named by the reference attribute of the column.
+pub(crate) fn reference_key(column: &ColumnDefinition) -> Option<&str> {
+    column.constraints.iter().find_map(|x| match x {
+        FieldConstraint::Reference(_, key) => Some(key.as_str()),
+        _ => None,
+    })
+}
+
+/// Return the value held by an option, or None, if the option has no value.
+/// Values which are not options are returned as they are.
+pub(crate) fn unwrap_option(value: &dyn PartialReflect) -> Option<&dyn PartialReflect> {
+    let ReflectRef::Enum(e) = value.reflect_ref() else {
+        return Some(value);
+    };
+
+    if !value
+        .get_represented_type_info()
+        .is_some_and(ErmTypesRegistry::is_option)
+    {
+        return Some(value);
+    }
+
+    match e.variant_name() {
+        "Some" => e.field_at(0),
+        _ => None,
+    }
+}
+
+/// Convert a value to the sql value of the given type.
+/// Relations are replaced by the key of the referenced value.
+pub(crate) fn to_value(
+    value: &dyn PartialReflect,
+    sql_type: &SqlType,
+    key_field: Option<&str>,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<SqlValue> {
+    let Some(value) = unwrap_option(value) else {
+        return Ok(SqlValue::Null);
+    };
+
+    match sql_type {
+        SqlType::Json(_) => Ok(SqlValue::Json(to_json(value, type_registry)?)),
+
+        SqlType::One2One(_, _) | SqlType::Many2Many(_, _) => {
+            if let Some(key) = lazy_key(value) {
+                return Ok(key);
+            }
+
+            let (target, key) = registry.referenced_key(sql_type, key_field)?;
+            let ReflectRef::Struct(strct) = value.reflect_ref() else {
+                return Err(ErmError::Unsupported(format!(
+                    "{} is not a struct and cannot reference table {}",
+                    value.reflect_type_path(),
+                    target.sql_name
+                )));
+            };
+
+            let Some(key_value) = strct.field(&key.rust_name) else {
+                return Err(ErmError::Unsupported(format!(
+                    "{} has no key field {}",
+                    value.reflect_type_path(),
+                    key.rust_name
+                )));
+            };
+
+            to_value(key_value, &key.sql_type, None, registry, type_registry)
+        }
+
+        SqlType::Blob(_) => reflect_into_blob(value).map(SqlValue::Blob).ok_or_else(|| {
+            ErmError::Unsupported(format!(
+                "{} cannot be stored as blob",
+                value.reflect_type_path()
+            ))
+        }),
+
+        SqlType::DateTime(_) => scalar_value(value)
+            .map(|x| x.into_type(sql_type))
+            .ok_or_else(|| {
+                ErmError::Unsupported(format!(
+                    "{} cannot be stored as {}",
+                    value.reflect_type_path(),
+                    sql_type
+                ))
+            }),
+
+        _ => scalar_value(value).ok_or_else(|| {
+            ErmError::Unsupported(format!(
+                "{} cannot be stored as {}",
+                value.reflect_type_path(),
+                sql_type
+            ))
+        }),
+    }
+}
+
+/// Convert numbers, booleans and strings to sql values.
+fn scalar_value(value: &dyn PartialReflect) -> Option<SqlValue> {
+    macro_rules! convert {
+        ($($t:ty),*) => {
+            $(
+                if let Some(v) = value.try_downcast_ref::<$t>() {
+                    return Some(v.clone().into());
+                }
+            )*
+        };
+    }
+    convert!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, bool, String);
+
+    if let Some(v) = value.try_downcast_ref::<usize>() {
+        return i64::try_from(*v).ok().map(SqlValue::Integer);
+    }
+    if let Some(v) = value.try_downcast_ref::<isize>() {
+        return Some(SqlValue::Integer(*v as i64));
+    }
+    if let Some(v) = value.try_downcast_ref::<i128>() {
+        return i64::try_from(*v).ok().map(SqlValue::Integer);
+    }
+    if let Some(v) = value.try_downcast_ref::<u128>() {
+        return u64::try_from(*v).ok().map(SqlValue::Unsigned);
+    }
+
+    None
+}
+
+/// Create an instance of the table's type from a row. The instance is created using
+/// `ReflectDefault`, every column is then written to the field of the same rust name.
+/// Fields stored in child tables keep their default, see `apply_child_rows`.
+pub fn from_row(
+    row: &Row,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<Box<dyn Reflect>> {
+    let mut result = table.reflect_default.default();
+    apply_row(
+        result.as_partial_reflect_mut(),
+        row,
+        table,
+        registry,
+        type_registry,
+    )?;
+
+    Ok(result)
+}
+
+/// Create an instance of T from a row of its table.
+pub fn from_row_as<T: Reflect>(
+    row: &Row,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<T> {
+    downcast(from_row(row, table, registry, type_registry)?, table)
+}
+
+/// Turn an instance created from a row of the table into T.
+pub(crate) fn downcast<T: Reflect>(
+    value: Box<dyn Reflect>,
+    table: &TableDefinition,
+) -> ErmResult<T> {
+    value.downcast::<T>().map(|x| *x).map_err(|x| {
+        ErmError::Unsupported(format!(
+            "Table {} holds {}, not {}",
+            table.sql_name,
+            x.reflect_type_path(),
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+/// Write the columns of a row to the fields of an existing struct.
+pub fn apply_row(
+    target: &mut dyn PartialReflect,
+    row: &Row,
+    table: &TableDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<()> {
+    for column in table.columns() {
+        let error = |e: ErmError| {
+            ErmError::InvalidValue(
+                table.sql_name.clone(),
+                column.sql_name.clone(),
+                e.to_string(),
+            )
+        };
+        let value_of = |name: &str| {
+            row.get(name)
+                .ok_or_else(|| ErmError::MissingColumn(table.sql_name.clone(), name.to_owned()))
+        };
+
+        if column.child_table().is_some() {
+            continue;
+        }
+
+        let field = field_mut_of(target, table, column)?;
+        match &column.storage {
+            ColumnStorage::Inline => {
+                let value = value_of(&column.sql_name)?;
+                apply_value(
+                    field,
+                    value,
+                    &column.sql_type,
+                    reference_key(column),
+                    registry,
+                    type_registry,
+                )
+                .map_err(error)?;
+            }
+
+            ColumnStorage::FramedBlob => {
+                let value = value_of(&column.sql_name)?;
+                apply_optional(field, value, type_registry, |target| match value {
+                    SqlValue::Blob(blob) => apply_framed_blob(target, blob, type_registry),
+                    _ => Err(mismatch(target, value)),
+                })
+                .map_err(error)?;
+            }
+
+            ColumnStorage::Decomposed(components) => {
+                let columns = column.sql_columns();
+                let mut values = Vec::new();
+                for (name, sql_type) in columns.iter() {
+                    values.push((value_of(name)?, sql_type));
+                }
+
+                apply_optional(field, values[0].0, type_registry, |target| {
+                    let ReflectMut::Struct(strct) = target.reflect_mut() else {
+                        return Err(ErmError::Unsupported(
+                            "Value cannot be decomposed".to_owned(),
+                        ));
+                    };
+
+                    for (component, (value, sql_type)) in components.iter().zip(values.iter()) {
+                        let Some(field) = strct.field_mut(component) else {
+                            return Err(ErmError::Unsupported(format!(
+                                "Value has no component {}",
+                                component
+                            )));
+                        };
+
+                        apply_value(field, value, sql_type, None, registry, type_registry)?;
+                    }
+
+                    Ok(())
+                })
+                .map_err(error)?;
+            }
+
+            ColumnStorage::ChildTable(_) => {}
+
+            ColumnStorage::PackedBlob => {
+                let value = value_of(&column.sql_name)?;
+                apply_optional(field, value, type_registry, |target| match value {
+                    SqlValue::Blob(blob) => reflect_from_packed_blob(target, blob),
+                    _ => Err(mismatch(target, value)),
+                })
+                .map_err(error)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the elements of a field stored in a child table with the elements held by the rows.
+/// Elements of lists are expected in the order of their position.
+pub fn apply_child_rows(
+    target: &mut dyn PartialReflect,
+    rows: &[Row],
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<()> {
+    let Some(child) = column.child_table() else {
+        return Err(ErmError::InvalidMapping(format!(
+            "Column {} of table {} is not stored in a child table",
+            column.sql_name, table.sql_name
+        )));
+    };
+
+    let error = |e: ErmError| {
+        ErmError::InvalidValue(
+            child.sql_name.clone(),
+            column.sql_name.clone(),
+            e.to_string(),
+        )
+    };
+    let value_of = |row: &Row, column: &ChildColumn| {
+        row.get(&column.sql_name)
+            .cloned()
+            .ok_or_else(|| ErmError::MissingColumn(child.sql_name.clone(), column.sql_name.clone()))
+    };
+    let element = |type_id: TypeId, value: &SqlValue, sql_type: &SqlType| {
+        let mut result = default_of(type_id, type_registry)?;
+        apply_value(
+            result.as_mut(),
+            value,
+            sql_type,
+            None,
+            registry,
+            type_registry,
+        )?;
+
+        Ok::<Box<dyn PartialReflect>, ErmError>(result)
+    };
+
+    let field = field_mut_of(target, table, column)?;
+
+    // An optional relation without elements has no value.
+    let marker = match rows.is_empty() && is_option(field) {
+        true => SqlValue::Null,
+        false => SqlValue::Bool(true),
+    };
+
+    let value_column = &child.value_column;
+    apply_optional(field, &marker, type_registry, |target| {
+        let type_info = target.get_represented_type_info();
+        match (target.reflect_mut(), type_info) {
+            (ReflectMut::List(list), Some(TypeInfo::List(info))) => {
+                list.drain();
+                for row in rows {
+                    let value = value_of(row, value_column)?;
+                    list.push(element(
+                        info.item_ty().id(),
+                        &value,
+                        &value_column.sql_type,
+                    )?);
+                }
+            }
+
+            (ReflectMut::Array(array), Some(TypeInfo::Array(info))) => {
+                for (i, row) in rows.iter().enumerate() {
+                    let Some(item) = array.get_mut(i) else {
+                        return Err(ErmError::Unsupported(format!(
+                            "Array holds {} elements, got {}",
+                            info.capacity(),
+                            rows.len()
+                        )));
+                    };
+
+                    let value = value_of(row, value_column)?;
+                    item.try_apply(
+                        element(info.item_ty().id(), &value, &value_column.sql_type)?.as_ref(),
+                    )
+                    .map_err(|e| ErmError::Unsupported(e.to_string()))?;
+                }
+            }
+
+            (ReflectMut::Map(map), Some(TypeInfo::Map(info))) => {
+                let Some(key_column) = &child.key_column else {
+                    return Err(ErmError::InvalidMapping(
+                        "child table of a map has no key column".to_owned(),
+                    ));
+                };
+
+                map.drain();
+                for row in rows {
+                    let key = value_of(row, key_column)?;
+                    let value = value_of(row, value_column)?;
+                    map.insert_boxed(
+                        element(info.key_ty().id(), &key, &key_column.sql_type)?,
+                        element(info.value_ty().id(), &value, &value_column.sql_type)?,
+                    );
+                }
+            }
+
+            (ReflectMut::Set(set), Some(TypeInfo::Set(info))) => {
+                set.drain();
+                for row in rows {
+                    let value = value_of(row, value_column)?;
+                    set.insert_boxed(element(
+                        info.value_ty().id(),
+                        &value,
+                        &value_column.sql_type,
+                    )?);
+                }
+            }
+
+            _ => {
+                return Err(ErmError::Unsupported(
+                    "Value is not a collection".to_owned(),
+                ))
+            }
+        }
+
+        Ok(())
+    })
+    .map_err(error)
+}
+
+/// Return the field of the struct the column is mapped to.
+pub(crate) fn field_mut_of<'a>(
+    value: &'a mut dyn PartialReflect,
+    table: &TableDefinition,
+    column: &ColumnDefinition,
+) -> ErmResult<&'a mut dyn PartialReflect> {
+    let type_path = value.reflect_type_path().to_owned();
+    let ReflectMut::Struct(strct) = value.reflect_mut() else {
+        return Err(ErmError::Unsupported(format!(
+            "{} is not a struct and cannot be read from table {}",
+            type_path, table.sql_name
+        )));
+    };
+
+    strct.field_mut(&column.rust_name).ok_or_else(|| {
+        ErmError::InvalidValue(
+            table.sql_name.clone(),
+            column.sql_name.clone(),
+            format!("{} has no field {}", type_path, column.rust_name),
+        )
+    })
+}
+
+/// Create the default value of a type registered with the type registry.
+fn default_of(type_id: TypeId, type_registry: &TypeRegistry) -> ErmResult<Box<dyn PartialReflect>> {
+    let Some(registration) = type_registry.get(type_id) else {
+        return Err(ErmError::Unsupported(
+            "Type is not registered with the type registry".to_owned(),
+        ));
+    };
+
+    let Some(reflect_default) = registration.data::<ReflectDefault>() else {
+        return Err(ErmError::Unsupported(format!(
+            "Type {} has no reflect default",
+            registration.type_info().type_path()
+        )));
+    };
+
+    Ok(reflect_default.default().into_partial_reflect())
+}
+
+/// Apply a value to the target. Null sets options to None, other values are written
+/// to the value of the option using the closure. Null is a mismatch for any other target.
+fn apply_optional(
+    target: &mut dyn PartialReflect,
+    value: &SqlValue,
+    type_registry: &TypeRegistry,
+    f: impl FnOnce(&mut dyn PartialReflect) -> ErmResult<()>,
+) -> ErmResult<()> {
+    if !is_option(target) {
+        if value.is_null() {
+            return Err(mismatch(target, value));
+        }
+
+        return f(target);
+    }
+
+    if value.is_null() {
+        target.apply(&DynamicEnum::new("None", DynamicVariant::Unit));
+        return Ok(());
+    }
+
+    let mut inner = option_default(target, type_registry)?;
+    f(inner.as_mut())?;
+
+    let mut tuple = DynamicTuple::default();
+    tuple.insert_boxed(inner);
+    target.apply(&DynamicEnum::new("Some", DynamicVariant::Tuple(tuple)));
+
+    Ok(())
+}
+
+/// Write a sql value to the target. Relations get a default value holding the key,
+/// lazy relations hold the key only.
+pub(crate) fn apply_value(
+    target: &mut dyn PartialReflect,
+    value: &SqlValue,
+    sql_type: &SqlType,
+    key_field: Option<&str>,
+    registry: &ErmTypesRegistry,
+    type_registry: &TypeRegistry,
+) -> ErmResult<()> {
+    let lazy = |target: &dyn PartialReflect| {
+        matches!(sql_type, SqlType::One2One(_, _) | SqlType::Many2Many(_, _))
+            && target.get_represented_type_info().is_some_and(is_lazy)
+    };
+    if lazy(target) {
+        return set_lazy_key(target, value);
+    }
+
+    apply_optional(target, value, type_registry, |target| {
+        match (sql_type, value) {
+            (SqlType::Json(_), SqlValue::Json(json) | SqlValue::Text(json)) => {
+                apply_json(target, json, type_registry)
+            }
+
+            _ if lazy(target) => set_lazy_key(target, value),
+
+            (SqlType::One2One(_, _) | SqlType::Many2Many(_, _), _) => {
+                let (target_table, key) = registry.referenced_key(sql_type, key_field)?;
+                let type_path = target.reflect_type_path().to_owned();
+                let ReflectMut::Struct(strct) = target.reflect_mut() else {
+                    return Err(ErmError::Unsupported(format!(
+                        "{} is not a struct and cannot reference table {}",
+                        type_path, target_table.sql_name
+                    )));
+                };
+
+                let Some(key_value) = strct.field_mut(&key.rust_name) else {
+                    return Err(ErmError::Unsupported(format!(
+                        "{} has no key field {}",
+                        type_path, key.rust_name
+                    )));
+                };
+
+                apply_value(
+                    key_value,
+                    value,
+                    &key.sql_type,
+                    None,
+                    registry,
+                    type_registry,
+                )
+            }
+
+            (SqlType::Blob(_), SqlValue::Blob(blob)) => match reflect_from_blob(target, blob)? {
+                true => Ok(()),
+                false => Err(mismatch(target, value)),
+            },
+
+            _ => apply_scalar(target, value),
+        }
+    })
+}
+
+/// Write numbers, booleans and strings to the target.
+fn apply_scalar(target: &mut dyn PartialReflect, value: &SqlValue) -> ErmResult<()> {
+    macro_rules! integers {
+        ($($t:ty),*) => {
+            $(
+                if let Some(t) = target.try_downcast_mut::<$t>() {
+                    let converted = match value {
+                        SqlValue::Integer(v) | SqlValue::DateTime(v) => <$t>::try_from(*v).ok(),
+                        SqlValue::Unsigned(v) => <$t>::try_from(*v).ok(),
+                        _ => None,
+                    };
+
+                    if let Some(v) = converted {
+                        *t = v;
+                        return Ok(());
+                    }
+
+                    return Err(mismatch(target, value));
+                }
+            )*
+        };
+    }
+    integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+    macro_rules! floats {
+        ($($t:ty),*) => {
+            $(
+                if let Some(t) = target.try_downcast_mut::<$t>() {
+                    match value {
+                        SqlValue::Float(v) => *t = *v as $t,
+                        SqlValue::Integer(v) => *t = *v as $t,
+                        SqlValue::Unsigned(v) => *t = *v as $t,
+                        _ => return Err(mismatch(target, value)),
+                    }
+
+                    return Ok(());
+                }
+            )*
+        };
+    }
+    floats!(f32, f64);
+
+    if let Some(t) = target.try_downcast_mut::<bool>() {
+        match value {
+            SqlValue::Bool(v) => *t = *v,
+            SqlValue::Integer(v) => *t = *v != 0,
+            _ => return Err(mismatch(target, value)),
+        }
+
+        return Ok(());
+    }
+
+    if let Some(t) = target.try_downcast_mut::<String>() {
+        match value {
+            SqlValue::Text(v) => v.clone_into(t),
+            _ => return Err(mismatch(target, value)),
+        }
+
+        return Ok(());
+    }
+
+    Err(mismatch(target, value))
+}
+
+fn mismatch(target: &dyn PartialReflect, value: &SqlValue) -> ErmError {
+    ErmError::Unsupported(format!(
+        "{} cannot be read from {}",
+        target.reflect_type_path(),
+        value
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Clone, Debug, PartialEq)]
+    #[reflect(Default, @TableName::new("Guilds"))]
+    struct Guild {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+    }
+
+    #[derive(Reflect, Default, Clone, Debug, PartialEq)]
+    #[reflect(Default)]
+    struct Stats {
+        pub level: u32,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key, @ColumnName::new("player_id"))]
+        pub id: i64,
+        pub name: String,
+        pub nickname: Option<String>,
+        pub position: Vec3,
+        #[reflect(@Decompose)]
+        pub velocity: Option<Vec2>,
+        #[reflect(@Framed)]
+        pub stats: Stats,
+        #[reflect(@Json)]
+        pub flags: Vec<bool>,
+        pub guild: Option<Guild>,
+        pub allies: Vec<Guild>,
+        pub scores: Vec<u16>,
+    }
+
+    fn prepare() -> (ErmTypesRegistry, AppTypeRegistry) {
+        let app_registry = AppTypeRegistry::default();
+        app_registry.write().register::<Player>();
+        app_registry.write().register::<Guild>();
+
+        let mut registry = ErmTypesRegistry::default();
+        assert!(registry.register_type::<Guild>(&app_registry).is_some());
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+
+        (registry, app_registry)
+    }
+
+    fn player() -> Player {
+        let guild = Guild {
+            id: 7,
+            name: "Wolves".to_owned(),
+        };
+
+        Player {
+            id: 1,
+            name: "Ann".to_owned(),
+            nickname: None,
+            position: Vec3::new(1.0, 2.0, 3.0),
+            velocity: Some(Vec2::new(0.5, -0.5)),
+            stats: Stats { level: 3 },
+            flags: vec![true, false],
+            guild: Some(guild.clone()),
+            allies: vec![guild, Guild { id: 9, ..default() }],
+            scores: vec![10, 20, 30],
+        }
+    }
+
+    #[test]
+    fn rows() {
+        let (registry, app_registry) = prepare();
+        let table = registry.get_table_definition("Players").unwrap();
+        let row = to_row(&player(), table, &registry, &app_registry.read()).unwrap();
+
+        assert_eq!(
+            row.names(),
+            vec![
+                "player_id",
+                "name",
+                "nickname",
+                "position",
+                "velocity_x",
+                "velocity_y",
+                "stats",
+                "flags",
+                "guild"
+            ]
+        );
+        assert_eq!(row.get("player_id"), Some(&SqlValue::Integer(1)));
+        assert_eq!(row.get("name"), Some(&SqlValue::from("Ann")));
+        assert_eq!(row.get("nickname"), Some(&SqlValue::Null));
+        assert_eq!(
+            row.get("position"),
+            Some(&SqlValue::Blob(Vec3::new(1.0, 2.0, 3.0).into_blob()))
+        );
+        assert_eq!(row.get("velocity_y"), Some(&SqlValue::Float(-0.5)));
+        assert_eq!(
+            row.get("stats"),
+            Some(&SqlValue::Blob(
+                to_framed_blob(&Stats { level: 3 }).unwrap()
+            ))
+        );
+        assert_eq!(
+            row.get("flags"),
+            Some(&SqlValue::Json("[true,false]".to_owned()))
+        );
+
+        // Relations are replaced by the key of the referenced value.
+        assert_eq!(row.get("guild"), Some(&SqlValue::Integer(7)));
+
+        let mut player = player();
+        player.guild = None;
+        player.velocity = None;
+        let row = to_row(&player, table, &registry, &app_registry.read()).unwrap();
+        assert_eq!(row.get("guild"), Some(&SqlValue::Null));
+        assert_eq!(row.get("velocity_x"), Some(&SqlValue::Null));
+    }
+
+    #[test]
+    fn child_rows() {
+        let (registry, app_registry) = prepare();
+        let table = registry.get_table_definition("Players").unwrap();
+        let type_registry = app_registry.read();
+
+        let allies = table.get("allies").unwrap();
+        let rows = to_child_rows(&player(), table, allies, &registry, &type_registry).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get("ordinal"), Some(&SqlValue::Unsigned(1)));
+        assert_eq!(rows[1].get("value"), Some(&SqlValue::Integer(9)));
+
+        let scores = table.get("scores").unwrap();
+        let rows = to_child_rows(&player(), table, scores, &registry, &type_registry).unwrap();
+        let values: Vec<&SqlValue> = rows.iter().map(|x| x.get("value").unwrap()).collect();
+        assert_eq!(
+            values,
+            vec![
+                &SqlValue::Unsigned(10),
+                &SqlValue::Unsigned(20),
+                &SqlValue::Unsigned(30)
+            ]
+        );
+
+        let name = table.get("name").unwrap();
+        assert!(to_child_rows(&player(), table, name, &registry, &type_registry).is_err());
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (registry, app_registry) = prepare();
+        let table = registry.get_table_definition("Players").unwrap();
+
+        // A value of another type cannot be stored in the table.
+        let stats = Stats::default();
+        let result = to_row(&stats, table, &registry, &app_registry.read());
+        assert!(
+            matches!(result, Err(ErmError::InvalidValue(t, c, _)) if t == "Players" && c == "player_id")
+        );
+    }
+
+    #[test]
+    fn instances() {
+        let (registry, app_registry) = prepare();
+        let table = registry.get_table_definition("Players").unwrap();
+        let type_registry = app_registry.read();
+
+        let expected = player();
+        let row = to_row(&expected, table, &registry, &type_registry).unwrap();
+        let mut result: Player = from_row_as(&row, table, &registry, &type_registry).unwrap();
+
+        // Collections are read separately, relations only hold the key.
+        assert!(result.allies.is_empty());
+        assert_eq!(result.guild, Some(Guild { id: 7, ..default() }));
+        assert_eq!(result.velocity, expected.velocity);
+        assert_eq!(result.stats, expected.stats);
+
+        for name in ["allies", "scores"] {
+            let column = table.get(name).unwrap();
+            let rows = to_child_rows(&expected, table, column, &registry, &type_registry).unwrap();
+            apply_child_rows(&mut result, &rows, table, column, &registry, &type_registry).unwrap();
+        }
+
+        result.guild = expected.guild.clone();
+        assert_eq!(result.scores, expected.scores);
+        assert_eq!(
+            result.allies.iter().map(|x| x.id).collect::<Vec<i64>>(),
+            vec![7, 9]
+        );
+
+        result.allies = expected.allies.clone();
+        assert_eq!(result, expected);
+
+        // Applying rows replaces the existing elements.
+        let scores = table.get("scores").unwrap();
+        apply_child_rows(&mut result, &[], table, scores, &registry, &type_registry).unwrap();
+        assert!(result.scores.is_empty());
+    }
+
+    #[test]
+    fn invalid_rows() {
+        let (registry, app_registry) = prepare();
+        let table = registry.get_table_definition("Players").unwrap();
+        let type_registry = app_registry.read();
+        let row = to_row(&player(), table, &registry, &type_registry).unwrap();
+
+        let mut mismatch = row.clone();
+        mismatch.push("name", SqlValue::Integer(3));
+        let result = from_row(&mismatch, table, &registry, &type_registry);
+        assert!(
+            matches!(result, Err(ErmError::InvalidValue(t, c, _)) if t == "Players" && c == "name")
+        );
+
+        let mut null = row.clone();
+        null.push("position", SqlValue::Null);
+        let result = from_row(&null, table, &registry, &type_registry);
+        assert!(
+            matches!(result, Err(ErmError::InvalidValue(t, c, _)) if t == "Players" && c == "position")
+        );
+
+        let mut overflow = row.clone();
+        overflow.push("player_id", SqlValue::Unsigned(u64::MAX));
+        assert!(from_row(&overflow, table, &registry, &type_registry).is_err());
+
+        let mut missing = row.clone();
+        missing.columns.retain(|x| x.0 != "velocity_y");
+        let result = from_row(&missing, table, &registry, &type_registry);
+        assert_eq!(
+            result.err(),
+            Some(ErmError::MissingColumn(
+                "Players".to_owned(),
+                "velocity_y".to_owned()
+            ))
+        );
+
+        let result = from_row_as::<Guild>(&row, table, &registry, &type_registry);
+        assert!(matches!(result, Err(ErmError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    backend::split_keys,
+    prelude::{
+        ColumnDefinition, ErmBackend, ErmResult, Filter, Row, SqlType, SqlValue, TableDefinition,
+    },
+};
+
+/// Return the filter matching the rows of the table which are not soft deleted.
+/// Tables without soft delete column match all rows.
+pub(crate) fn live_filter(table: &TableDefinition) -> Filter {
+    match table.soft_delete_column() {
+        Some(column) if column.sql_type == SqlType::Boolean(true) => {
+            Filter::Eq(column.sql_name.clone(), SqlValue::Bool(false))
+        }
+        Some(column) => Filter::IsNull(column.sql_name.clone()),
+        None => Filter::All,
+    }
+}
+
+/// Returns true, if the row is marked as deleted.
+pub(crate) fn is_deleted(table: &TableDefinition, row: &Row) -> bool {
+    !live_filter(table).matches(row)
+}
+
+/// Delete the row. Tables with a soft delete column mark the row as deleted instead.
+/// Returns false, if there is no such row or it was deleted before.
+pub(crate) fn delete_row(
+    db: &dyn ErmBackend,
+    table: &TableDefinition,
+    row: &Row,
+    now: i64,
+) -> ErmResult<bool> {
+    let Some(column) = table.soft_delete_column() else {
+        return db.delete(table, row);
+    };
+
+    let (mut changed, _) = split_keys(table, row)?;
+    changed.push(&column.sql_name, deleted_value(column, now));
+    db.update_if(table, &changed, &live_filter(table))
+}
+
+/// Remove the deletion mark of the row with the given key.
+/// Returns false, if there is no such row or it was not deleted.
+pub(crate) fn restore_row(
+    db: &dyn ErmBackend,
+    table: &TableDefinition,
+    key: &Row,
+) -> ErmResult<bool> {
+    let Some(column) = table.soft_delete_column() else {
+        return Ok(false);
+    };
+
+    let (mut changed, _) = split_keys(table, key)?;
+    let live = match column.sql_type {
+        SqlType::Boolean(true) => SqlValue::Bool(false),
+        _ => SqlValue::Null,
+    };
+    changed.push(&column.sql_name, live);
+    db.update_if(table, &changed, &Filter::Not(Box::new(live_filter(table))))
+}
+
+/// The value marking a row as deleted: true for flags, the time for timestamps.
+fn deleted_value(column: &ColumnDefinition, now: i64) -> SqlValue {
+    match column.sql_type {
+        SqlType::Boolean(true) => SqlValue::Bool(true),
+        _ => SqlValue::Integer(now).into_type(&column.sql_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Quests"), @SoftDelete::new("deleted"))]
+    struct Quest {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+        pub items: Vec<Item>,
+        pub reward: Option<Item>,
+        pub deleted: bool,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Items"), @SoftDelete::new("deleted_at"))]
+    struct Item {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+        pub deleted_at: Option<i64>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, @SoftDelete::new("deleted"))]
+    struct Unmarked {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub deleted: i64,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(matches!(
+            registry.try_register_type::<Unmarked>(&app_registry),
+            Err(ErmError::InvalidMapping(_))
+        ));
+        assert!(registry.register_type::<Item>(&app_registry).is_some());
+        assert!(registry.register_type::<Quest>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    fn item(id: i64, name: &str) -> Item {
+        Item {
+            id,
+            name: name.to_owned(),
+            deleted_at: None,
+        }
+    }
+
+    fn deleting(quests: Repository<Quest>, items: Repository<Item>) {
+        items.insert(&item(1, "Sword")).unwrap();
+        items.insert(&item(2, "Shield")).unwrap();
+        let quest = Quest {
+            id: 1,
+            name: "Dragon".to_owned(),
+            items: vec![item(1, "Sword"), item(2, "Shield")],
+            // Optional relations are lazy, so they hold the key only.
+            reward: Some(Item { id: 2, ..default() }),
+            deleted: false,
+        };
+        quests.insert(&quest).unwrap();
+
+        // Deleted rows are kept, but no longer read.
+        assert!(quests.delete_by_key(1i64).unwrap());
+        assert!(!quests.delete_by_key(1i64).unwrap());
+        assert_eq!(quests.find(1i64).unwrap(), None);
+        assert!(quests.all().unwrap().is_empty());
+        assert!(quests.query(ErmQuery::new()).unwrap().is_empty());
+
+        let deleted = quests.query(ErmQuery::new().with_deleted()).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].deleted);
+
+        // Updates neither restore nor delete rows.
+        let mut changed = deleted[0].clone();
+        changed.name = "Troll".to_owned();
+        assert!(!quests.update(&changed).unwrap());
+        assert!(quests.upsert(&changed).is_err());
+        quests.upsert_many(&[changed.clone()]).unwrap();
+        assert_eq!(quests.find(1i64).unwrap(), None);
+
+        assert!(quests.restore(1i64).unwrap());
+        assert!(!quests.restore(1i64).unwrap());
+        assert!(quests.update(&changed).unwrap());
+        assert!(!quests.find(1i64).unwrap().unwrap().deleted);
+        assert!(quests.update(&quest).unwrap());
+        assert_eq!(quests.find(1i64).unwrap(), Some(quest));
+
+        // Timestamps mark the time of deletion, relations do not load deleted rows.
+        assert!(items.delete_by_key(2i64).unwrap());
+        let mut loaded = vec![quests.find(1i64).unwrap().unwrap()];
+        assert_eq!(loaded[0].items, vec![item(1, "Sword")]);
+        quests.load_relation(&mut loaded, "reward").unwrap();
+        assert_eq!(loaded[0].reward, None);
+
+        let query = ErmQuery::<Item>::new()
+            .filter(col("deleted_at").is_not_null())
+            .with_deleted();
+        let deleted = items.query(query).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].deleted_at.is_some_and(|x| x > 0));
+
+        assert!(items.restore(2i64).unwrap());
+        assert_eq!(items.all().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn soft_delete() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.register_type::<Quest>();
+        app.register_type::<Unmarked>();
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, deleting);
+
+        app.update();
+    }
+}
@@ -19,15 +19,41 @@ pub enum SqlType {
 
     Date(bool),
     Time(bool),
+
+    /// Seconds since the unix epoch, stored in an integer column.
     DateTime(bool),
 
     Blob(bool),
     Boolean(bool),
 
+    /// Nested structs, collections or enums serialized as json.
+    Json(bool),
+
     One2One(TypeId, bool),      // The bool marks, whether this relation is marked for eager or lazy loading.
     Many2Many(TypeId, bool),    // The bool marks, whether this relation is marked for eager or lazy loading.
 }
 
+impl SqlType {
+    /// Returns true, if a value of this type is required.
+    pub fn is_not_null(&self) -> bool {
+        match self {
+            SqlType::None => false,
+            SqlType::Integer(_, b) => *b,
+            SqlType::UnsingedInteger(_, b) => *b,
+            SqlType::Float(_, b) => *b,
+            SqlType::Text(b) => *b,
+            SqlType::Date(b) => *b,
+            SqlType::Time(b) => *b,
+            SqlType::DateTime(b) => *b,
+            SqlType::Blob(b) => *b,
+            SqlType::Boolean(b) => *b,
+            SqlType::Json(b) => *b,
+            SqlType::One2One(_, b) => *b,
+            SqlType::Many2Many(_, b) => *b,
+        }
+    }
+}
+
 impl Display for SqlType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -80,6 +106,11 @@ impl Display for SqlType {
                 "Boolean ({})",
                 if *not_null { "not null" } else { "nullable" }
             ),
+            SqlType::Json(not_null) => write!(
+                f,
+                "Json ({})",
+                if *not_null { "not null" } else { "nullable" }
+            ),
             SqlType::One2One(_, eager) => write!(
                 f,
                 "One2One (Eager: {})",
@@ -0,0 +1,246 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use bevy::reflect::Reflect;
+
+use crate::prelude::SqlType;
+
+/// A single value read from or written to a database column.
+/// Numbers compare by their value, regardless of their variant, like sql does.
+#[derive(Debug, Default, Clone, Reflect)]
+pub enum SqlValue {
+    #[default]
+    Null,
+
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+    Text(String),
+    Blob(Vec<u8>),
+    Bool(bool),
+
+    /// Seconds since the unix epoch, as written by the clock.
+    DateTime(i64),
+
+    Json(String),
+}
+
+impl SqlValue {
+    /// Returns true, if the value is null.
+    pub fn is_null(&self) -> bool {
+        matches!(self, SqlValue::Null)
+    }
+
+    /// Databases usually know fewer types than the ERM, e.g. sqlite stores booleans
+    /// as integers. Convert a value read from a database to the value the sql type expects.
+    pub fn into_type(self, sql_type: &SqlType) -> SqlValue {
+        match (self, sql_type) {
+            (SqlValue::Integer(v), SqlType::Boolean(_)) => SqlValue::Bool(v != 0),
+            (SqlValue::Integer(v), SqlType::UnsingedInteger(_, _)) if v >= 0 => {
+                SqlValue::Unsigned(v as u64)
+            }
+            (SqlValue::Unsigned(v), SqlType::Integer(_, _)) if v <= i64::MAX as u64 => {
+                SqlValue::Integer(v as i64)
+            }
+            (SqlValue::Integer(v), SqlType::Float(_, _)) => SqlValue::Float(v as f64),
+            (SqlValue::Unsigned(v), SqlType::Float(_, _)) => SqlValue::Float(v as f64),
+            (SqlValue::Integer(v), SqlType::DateTime(_)) => SqlValue::DateTime(v),
+            (SqlValue::Text(v), SqlType::Json(_)) => SqlValue::Json(v),
+            (value, _) => value,
+        }
+    }
+}
+
+/// A number held by a value, widened so integers of all variants compare exactly.
+#[derive(Clone, Copy)]
+pub(crate) enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    /// Return the number as integer, if it has no fraction. Floats too large
+    /// for an integer remain floats.
+    pub(crate) fn integral(self) -> Number {
+        match self {
+            Number::Float(v) if v.fract() == 0.0 && v.abs() < 2f64.powi(127) => {
+                Number::Integer(v as i128)
+            }
+            _ => self,
+        }
+    }
+
+    fn partial_cmp(self, other: Number) -> Option<Ordering> {
+        match (self.integral(), other.integral()) {
+            (Number::Integer(a), Number::Integer(b)) => Some(a.cmp(&b)),
+            (Number::Integer(a), Number::Float(b)) => (a as f64).partial_cmp(&b),
+            (Number::Float(a), Number::Integer(b)) => a.partial_cmp(&(b as f64)),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b),
+        }
+    }
+}
+
+impl SqlValue {
+    /// Return the number held by the value, if it is a number.
+    pub(crate) fn number(&self) -> Option<Number> {
+        match self {
+            SqlValue::Integer(v) | SqlValue::DateTime(v) => Some(Number::Integer(*v as i128)),
+            SqlValue::Unsigned(v) => Some(Number::Integer(*v as i128)),
+            SqlValue::Float(v) => Some(Number::Float(*v)),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for SqlValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+/// Values of different kinds, e.g. texts and numbers, cannot be compared.
+impl PartialOrd for SqlValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if let (Some(a), Some(b)) = (self.number(), other.number()) {
+            return a.partial_cmp(b);
+        }
+
+        match (self, other) {
+            (SqlValue::Null, SqlValue::Null) => Some(Ordering::Equal),
+            (SqlValue::Text(a), SqlValue::Text(b)) | (SqlValue::Json(a), SqlValue::Json(b)) => {
+                a.partial_cmp(b)
+            }
+            (SqlValue::Blob(a), SqlValue::Blob(b)) => a.partial_cmp(b),
+            (SqlValue::Bool(a), SqlValue::Bool(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl Display for SqlValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqlValue::Null => write!(f, "NULL"),
+            SqlValue::Integer(v) => write!(f, "{}", v),
+            SqlValue::Unsigned(v) => write!(f, "{}", v),
+            SqlValue::Float(v) => write!(f, "{}", v),
+            SqlValue::Text(v) => write!(f, "'{}'", v),
+            SqlValue::Blob(v) => write!(f, "blob ({} bytes)", v.len()),
+            SqlValue::Bool(v) => write!(f, "{}", v),
+            SqlValue::DateTime(v) => write!(f, "datetime ({})", v),
+            SqlValue::Json(v) => write!(f, "json ({})", v),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($t:ty => $variant:ident as $target:ty),*) => {
+        $(
+            impl From<$t> for SqlValue {
+                fn from(value: $t) -> Self {
+                    SqlValue::$variant(value as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    i8 => Integer as i64, i16 => Integer as i64, i32 => Integer as i64, i64 => Integer as i64,
+    u8 => Unsigned as u64, u16 => Unsigned as u64, u32 => Unsigned as u64, u64 => Unsigned as u64,
+    f32 => Float as f64, f64 => Float as f64
+);
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.to_owned())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Blob(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(|x| x.into()).unwrap_or(SqlValue::Null)
+    }
+}
+
+/// An ordered list of column names and their values.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Row {
+    pub columns: Vec<(String, SqlValue)>,
+}
+
+impl Row {
+    pub fn new() -> Self {
+        Row::default()
+    }
+
+    /// Add a column to the row. An existing column with the same name is replaced.
+    pub fn push(&mut self, column: &str, value: SqlValue) {
+        if let Some(existing) = self.columns.iter_mut().find(|x| x.0 == column) {
+            existing.1 = value;
+            return;
+        }
+
+        self.columns.push((column.to_owned(), value));
+    }
+
+    /// Add a column to the row and return the row.
+    pub fn with(mut self, column: &str, value: impl Into<SqlValue>) -> Self {
+        self.push(column, value.into());
+        self
+    }
+
+    /// Return the value of the column with the given name.
+    pub fn get(&self, column: &str) -> Option<&SqlValue> {
+        self.columns.iter().find(|x| x.0 == column).map(|x| &x.1)
+    }
+
+    /// Return the names of all columns.
+    pub fn names(&self) -> Vec<&str> {
+        self.columns.iter().map(|x| x.0.as_str()).collect()
+    }
+
+    /// Return all values, in the order of the columns.
+    pub fn values(&self) -> Vec<&SqlValue> {
+        self.columns.iter().map(|x| &x.1).collect()
+    }
+
+    /// Return the number of columns.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+impl Display for Row {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns = self
+            .columns
+            .iter()
+            .map(|x| format!("{}: {}", x.0, x.1))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "({})", columns)
+    }
+}
@@ -0,0 +1,609 @@
+use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    thread::{self, ThreadId},
+};
+
+use bevy::prelude::Resource;
+use rusqlite::{
+    config::DbConfig,
+    params_from_iter,
+    types::{ToSqlOutput, Value, ValueRef},
+    Connection, ToSql,
+};
+
+use crate::{
+    backend::{check_upsert, split_keys, upsert_columns},
+    prelude::{
+        ChildTable, ErmBackend, ErmError, ErmResult, ErmTypesRegistry, Filter, Row, Select,
+        SqlDialect, SqlType, SqlValue, TableDefinition,
+    },
+};
+
+impl From<rusqlite::Error> for ErmError {
+    fn from(value: rusqlite::Error) -> Self {
+        ErmError::Database(value.to_string())
+    }
+}
+
+impl ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let value = match self {
+            SqlValue::Null => Value::Null,
+            SqlValue::Integer(v) => Value::Integer(*v),
+            SqlValue::Unsigned(v) => Value::Integer(
+                i64::try_from(*v)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+            ),
+            SqlValue::Float(v) => Value::Real(*v),
+            SqlValue::Text(v) => return Ok(ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes()))),
+            SqlValue::Blob(v) => return Ok(ToSqlOutput::Borrowed(ValueRef::Blob(v))),
+            SqlValue::Bool(v) => Value::Integer(*v as i64),
+            SqlValue::DateTime(v) => Value::Integer(*v),
+            SqlValue::Json(v) => return Ok(ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes()))),
+        };
+
+        Ok(ToSqlOutput::Owned(value))
+    }
+}
+
+impl From<ValueRef<'_>> for SqlValue {
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => SqlValue::Null,
+            ValueRef::Integer(v) => SqlValue::Integer(v),
+            ValueRef::Real(v) => SqlValue::Float(v),
+            ValueRef::Text(v) => SqlValue::Text(String::from_utf8_lossy(v).into_owned()),
+            ValueRef::Blob(v) => SqlValue::Blob(v.to_vec()),
+        }
+    }
+}
+
+/// A sqlite database holding the tables of the ERM-Registry.
+/// Use `open_in_memory` for tests or data, that need not outlive the app.
+/// While a thread has a transaction open, other threads wait for it to end,
+/// so their statements never become part of it.
+#[derive(Resource)]
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+    transactions: Mutex<Transactions>,
+
+    /// Notified, when the last transaction of a thread ends.
+    released: Condvar,
+}
+
+/// The thread owning the open transactions and their number. Each transaction is a savepoint.
+#[derive(Default)]
+struct Transactions {
+    owner: Option<ThreadId>,
+    depth: usize,
+}
+
+impl SqliteDatabase {
+    /// Open or create the database file at the given path.
+    /// The path `:memory:` opens a database in memory.
+    pub fn open(path: &str) -> ErmResult<Self> {
+        let connection = Connection::open(path)?;
+
+        // Sqlite ignores references, unless told otherwise.
+        connection.pragma_update(None, "foreign_keys", "ON")?;
+
+        // Unknown identifiers in double quotes are errors, not strings.
+        connection.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DML, false)?;
+        connection.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DDL, false)?;
+
+        Ok(SqliteDatabase {
+            connection: Mutex::new(connection),
+            transactions: Mutex::new(Transactions::default()),
+            released: Condvar::new(),
+        })
+    }
+
+    pub fn open_in_memory() -> ErmResult<Self> {
+        SqliteDatabase::open(":memory:")
+    }
+
+    /// Lock the connection, once no other thread has a transaction open.
+    fn connection(&self) -> ErmResult<MutexGuard<'_, Connection>> {
+        let _transactions = self.transactions()?;
+        self.lock_connection()
+    }
+
+    fn lock_connection(&self) -> ErmResult<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|e| ErmError::Database(e.to_string()))
+    }
+
+    /// Lock the transactions, once no other thread has one open.
+    fn transactions(&self) -> ErmResult<MutexGuard<'_, Transactions>> {
+        let current = thread::current().id();
+        let transactions = self
+            .transactions
+            .lock()
+            .map_err(|e| ErmError::Database(e.to_string()))?;
+
+        self.released
+            .wait_while(transactions, |x| x.owner.is_some_and(|x| x != current))
+            .map_err(|e| ErmError::Database(e.to_string()))
+    }
+
+    /// End the innermost transaction, rolling its changes back or keeping them.
+    /// Other threads may use the connection again, once the outermost transaction ended.
+    fn end(&self, rollback: bool) -> ErmResult<()> {
+        let mut transactions = self.transactions()?;
+        if transactions.depth == 0 {
+            let action = if rollback { "roll back" } else { "commit" };
+            return Err(ErmError::Database(format!("No transaction to {}", action)));
+        }
+
+        transactions.depth -= 1;
+        if transactions.depth == 0 {
+            transactions.owner = None;
+            self.released.notify_all();
+        }
+
+        let connection = self.lock_connection()?;
+        let savepoint = format!("erm_{}", transactions.depth);
+        if rollback {
+            connection.execute(&format!("ROLLBACK TO {}", savepoint), [])?;
+        }
+        connection.execute(&format!("RELEASE {}", savepoint), [])?;
+
+        Ok(())
+    }
+
+    /// Execute a statement and return the number of changed rows.
+    pub fn execute(&self, sql: &str, params: &[SqlValue]) -> ErmResult<usize> {
+        let connection = self.connection()?;
+        Ok(connection.execute(sql, params_from_iter(params.iter()))?)
+    }
+
+    /// Run a query and return all rows. The values are returned as sqlite stores them,
+    /// e.g. booleans are integers.
+    pub fn query(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<Row>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(sql)?;
+        let names: Vec<String> = statement
+            .column_names()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        let mut rows = statement.query(params_from_iter(params.iter()))?;
+        let mut result = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut values = Row::new();
+            for (index, name) in names.iter().enumerate() {
+                values.push(name, row.get_ref(index)?.into());
+            }
+
+            result.push(values);
+        }
+
+        Ok(result)
+    }
+
+    /// Run a query and convert the values to the given sql types.
+    fn query_typed(
+        &self,
+        sql: &str,
+        params: &[SqlValue],
+        columns: &[(String, SqlType)],
+    ) -> ErmResult<Vec<Row>> {
+        let rows = self.query(sql, params)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let mut result = Row::new();
+                for ((name, value), (_, sql_type)) in row.columns.into_iter().zip(columns.iter()) {
+                    result.push(&name, value.into_type(sql_type));
+                }
+
+                result
+            })
+            .collect())
+    }
+
+    /// Insert or upsert the rows in a single transaction. Rows holding the same columns
+    /// are inserted with as many rows per statement as the parameter limit allows.
+    fn write_many(
+        &self,
+        table: &TableDefinition,
+        rows: &[Row],
+        conflict_columns: Option<&[&str]>,
+    ) -> ErmResult<()> {
+        let db: &dyn ErmBackend = self;
+        db.transaction(|_| {
+            let dialect = self.dialect();
+            for group in rows.chunk_by(|a, b| a.names() == b.names()) {
+                let columns = group[0].names();
+                let rows_per_statement = (dialect.max_parameters() / columns.len().max(1)).max(1);
+                for chunk in group.chunks(rows_per_statement) {
+                    let sql = match conflict_columns {
+                        Some(conflict) => dialect.upsert_many(
+                            &table.sql_name,
+                            &columns,
+                            chunk.len(),
+                            conflict,
+                            &upsert_columns(table, &group[0], conflict),
+                        ),
+                        None => dialect.insert_many(&table.sql_name, &columns, chunk.len()),
+                    };
+
+                    let params: Vec<SqlValue> =
+                        chunk.iter().flat_map(|x| Self::owned(x.values())).collect();
+                    self.execute(&sql, &params)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn owned(values: Vec<&SqlValue>) -> Vec<SqlValue> {
+        values.into_iter().cloned().collect()
+    }
+}
+
+impl ErmBackend for SqliteDatabase {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Sqlite
+    }
+
+    fn create_schema(&self, registry: &ErmTypesRegistry) -> ErmResult<()> {
+        let mut statements = Vec::new();
+        for table in registry.tables() {
+            statements.extend(self.dialect().create_table(table, registry)?);
+        }
+
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        for statement in statements {
+            transaction.execute(&statement, [])?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn insert(&self, table: &TableDefinition, row: &Row) -> ErmResult<()> {
+        let sql = self.dialect().insert(&table.sql_name, &row.names());
+        self.execute(&sql, &Self::owned(row.values()))?;
+
+        Ok(())
+    }
+
+    fn insert_many(&self, table: &TableDefinition, rows: &[Row]) -> ErmResult<()> {
+        self.write_many(table, rows, None)
+    }
+
+    fn upsert_many(
+        &self,
+        table: &TableDefinition,
+        rows: &[Row],
+        conflict_columns: &[&str],
+    ) -> ErmResult<()> {
+        check_upsert(table)?;
+        self.write_many(table, rows, Some(conflict_columns))
+    }
+
+    fn update_if(&self, table: &TableDefinition, row: &Row, filter: &Filter) -> ErmResult<bool> {
+        let (key_row, values) = split_keys(table, row)?;
+        if values.is_empty() {
+            let filter = Filter::by_key(table, &key_row)?.and(filter.clone());
+            return Ok(!self.select(table, &filter)?.is_empty());
+        }
+
+        let mut sql = self
+            .dialect()
+            .update(&table.sql_name, &values.names(), &key_row.names());
+        let mut params = Self::owned(values.values());
+        params.extend(Self::owned(key_row.values()));
+        if *filter != Filter::All {
+            sql = format!(
+                "{} AND ({})",
+                sql,
+                filter.to_sql(self.dialect(), &mut params)
+            );
+        }
+
+        Ok(self.execute(&sql, &params)? > 0)
+    }
+
+    fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool> {
+        let (key_row, _) = split_keys(table, key)?;
+        let sql = self.dialect().delete(&table.sql_name, &key_row.names());
+
+        Ok(self.execute(&sql, &Self::owned(key_row.values()))? > 0)
+    }
+
+    fn query(&self, table: &TableDefinition, select: &Select) -> ErmResult<Vec<Row>> {
+        let columns = table.sql_columns();
+        let names: Vec<&str> = columns.iter().map(|x| x.0.as_str()).collect();
+
+        let mut params = Vec::new();
+        let sql = select.to_sql(self.dialect(), &table.sql_name, &names, &mut params);
+
+        self.query_typed(&sql, &params, &columns)
+    }
+
+    fn query_sql(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<Row>> {
+        self.query(sql, params)
+    }
+
+    fn replace_children(
+        &self,
+        child: &ChildTable,
+        owner: &SqlValue,
+        rows: &[Row],
+    ) -> ErmResult<()> {
+        let owner_name = child.owner_column.sql_name.as_str();
+        let delete = self.dialect().delete(&child.sql_name, &[owner_name]);
+
+        (self as &dyn ErmBackend).transaction(|_| {
+            self.execute(&delete, std::slice::from_ref(owner))?;
+
+            for row in rows {
+                let mut names = vec![owner_name];
+                names.extend(row.names());
+                let mut params = vec![owner.clone()];
+                params.extend(Self::owned(row.values()));
+
+                let sql = self.dialect().insert(&child.sql_name, &names);
+                self.execute(&sql, &params)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn select_children(&self, child: &ChildTable, owners: &[SqlValue]) -> ErmResult<Vec<Row>> {
+        if owners.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let columns: Vec<(String, SqlType)> = child
+            .columns()
+            .iter()
+            .map(|x| (x.sql_name.clone(), x.sql_type.clone()))
+            .collect();
+        let names: Vec<&str> = columns.iter().map(|x| x.0.as_str()).collect();
+        let placeholders: Vec<String> = (1..=owners.len())
+            .map(|i| self.dialect().placeholder(i))
+            .collect();
+
+        let mut sql = format!(
+            "{} WHERE {} IN ({})",
+            self.dialect().select(&child.sql_name, &names, &[]),
+            self.dialect().quote(&child.owner_column.sql_name),
+            placeholders.join(", ")
+        );
+        if let Some(ordinal) = &child.ordinal_column {
+            sql.push_str(&format!(
+                " ORDER BY {}, {}",
+                self.dialect().quote(&child.owner_column.sql_name),
+                self.dialect().quote(&ordinal.sql_name)
+            ));
+        }
+
+        self.query_typed(&sql, owners, &columns)
+    }
+
+    fn begin(&self) -> ErmResult<()> {
+        let mut transactions = self.transactions()?;
+        let connection = self.lock_connection()?;
+        connection.execute(&format!("SAVEPOINT erm_{}", transactions.depth), [])?;
+        transactions.owner = Some(thread::current().id());
+        transactions.depth += 1;
+
+        Ok(())
+    }
+
+    fn commit(&self) -> ErmResult<()> {
+        self.end(false)
+    }
+
+    fn rollback(&self) -> ErmResult<()> {
+        self.end(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backend::conformance::{self, player, Player},
+        prelude::*,
+    };
+    use bevy::prelude::*;
+
+    #[test]
+    fn crud() {
+        conformance::crud(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn bulk_writes() {
+        conformance::bulk_writes(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn conditional_updates() {
+        conformance::conditional_updates(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn constraints() {
+        conformance::constraints(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn referential_actions() {
+        conformance::referential_actions(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn child_tables() {
+        conformance::child_tables(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn transactions() {
+        conformance::transactions(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn unknown_columns() {
+        conformance::unknown_columns(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn queries() {
+        conformance::queries(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn create_schema() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        conformance::prepare(&database);
+
+        let tables = database
+            .query(
+                "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+                &[],
+            )
+            .unwrap();
+        let names: Vec<&SqlValue> = tables.iter().map(|x| x.get("name").unwrap()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                &SqlValue::from("Minions"),
+                &SqlValue::from("Players"),
+                &SqlValue::from("Players_tags"),
+                &SqlValue::from("Zombies")
+            ]
+        );
+    }
+
+    #[test]
+    fn bulk_writes_beyond_parameter_limit() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let registry = conformance::prepare(&database);
+        let players = registry.get_table_definition("Players").unwrap();
+
+        // Four columns per row take more than one statement.
+        let rows: Vec<Row> = (0..10_000).map(|i| player(i, &i.to_string())).collect();
+        assert!(rows.len() * 4 > SqlDialect::Sqlite.max_parameters());
+        database.insert_many(players, &rows).unwrap();
+        database.upsert_many(players, &rows, &["id"]).unwrap();
+
+        let count = database
+            .query("SELECT COUNT(*) AS count FROM \"Players\"", &[])
+            .unwrap();
+        assert_eq!(count[0].get("count"), Some(&SqlValue::Integer(10_000)));
+    }
+
+    #[test]
+    fn transactions_of_other_threads() {
+        conformance::transactions_of_other_threads(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn many_rows() {
+        conformance::many_rows(&SqliteDatabase::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn raw_queries() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let registry = conformance::prepare(&database);
+        let players = registry.get_table_definition("Players").unwrap();
+        let zombies = registry.get_table_definition("Zombies").unwrap();
+
+        database.insert(players, &player(1, "Ann")).unwrap();
+        database
+            .insert(players, &player(2, "Bob").with("score", 2.5))
+            .unwrap();
+        let tags = [Row::new().with("ordinal", 0u32).with("value", "brave")];
+        database
+            .replace_children(players.child_tables()[0], &1i64.into(), &tags)
+            .unwrap();
+        let zombie = Row::new().with("id", 1i64).with("target", 1i64);
+        database.insert(zombies, &zombie).unwrap();
+
+        let app_registry = AppTypeRegistry::default();
+        app_registry.write().register::<Player>();
+        let type_registry = app_registry.read();
+
+        // Columns of other tables are ignored.
+        let targets = query_as::<Player>(
+            &database,
+            "SELECT p.*, z.id AS zombie FROM Players p JOIN Zombies z ON z.target = p.id \
+             WHERE z.id = ?1",
+            &[1i64.into()],
+            &registry,
+            &type_registry,
+        )
+        .unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "Ann");
+        assert_eq!(targets[0].score, Some(1.5));
+        assert!(targets[0].active);
+        assert_eq!(targets[0].tags, vec!["brave".to_owned()]);
+
+        // Missing nullable columns are read as None.
+        let all = query_as::<Player>(
+            &database,
+            "SELECT id, name, active FROM Players ORDER BY id DESC",
+            &[],
+            &registry,
+            &type_registry,
+        )
+        .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].name, "Bob");
+        assert_eq!(all[0].score, None);
+        assert!(all[0].tags.is_empty());
+        assert_eq!(all[1].tags, vec!["brave".to_owned()]);
+
+        assert!(matches!(
+            query_as::<Player>(
+                &database,
+                "SELECT id, name FROM Players",
+                &[],
+                &registry,
+                &type_registry
+            ),
+            Err(ErmError::MissingColumn(_, _))
+        ));
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        assert!(database.create_schema(&registry).is_ok());
+
+        let table = registry.get_table_definition("Players").unwrap();
+        assert!(database.insert(table, &player(1, "Ann")).is_ok());
+    }
+
+    #[test]
+    fn plugin_backend() {
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default().with_backend(SqliteDatabase::open_in_memory));
+        app.register_type::<Player>();
+        app.add_systems(Startup, startup);
+        app.update();
+
+        let database = app.world().resource::<ErmDatabase>();
+        assert_eq!(database.dialect(), SqlDialect::Sqlite);
+        let registry = app.world().resource::<ErmTypesRegistry>();
+        let table = registry.get_table_definition("Players").unwrap();
+        assert_eq!(database.select(table, &Filter::All).unwrap().len(), 1);
+    }
+}
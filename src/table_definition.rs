@@ -4,7 +4,7 @@ use bevy::{
 };
 use std::{collections::HashMap, fmt::Display};
 
-use crate::prelude::ColumnDefinition;
+use crate::prelude::{ChildTable, ColumnDefinition, SqlType};
 
 #[derive(Reflect, Debug, Default)]
 pub struct TableName {
@@ -19,10 +19,29 @@ impl TableName {
     }
 }
 
+/// Mark rows as deleted in a column instead of removing them, e.g. `@SoftDelete::new("deleted_at")`.
+/// The column is a bool flag or a nullable timestamp, set to the time of the `Clock`.
+/// Rows marked as deleted are skipped by queries and relation loads, unless the query asks for them.
+#[derive(Reflect, Debug, Default)]
+pub struct SoftDelete {
+    pub column: String,
+}
+
+impl SoftDelete {
+    pub fn new(column: &str) -> Self {
+        SoftDelete {
+            column: column.to_owned(),
+        }
+    }
+}
+
 pub struct TableDefinition {
     pub rust_name: String,
     pub sql_name: String,
 
+    /// The sql name of the column marking deleted rows, see `SoftDelete`.
+    pub soft_delete: Option<String>,
+
     pub fields: HashMap<String, ColumnDefinition>,
 
     pub ty: Type,
@@ -41,6 +60,8 @@ impl TableDefinition {
             rust_name: rst_name.to_owned(),
             sql_name: sql_name.to_owned(),
 
+            soft_delete: None,
+
             fields: HashMap::new(),
 
             ty: *ty,
@@ -66,6 +87,56 @@ impl TableDefinition {
         self.fields.len()
     }
 
+    /// Return the key columns of this table.
+    pub fn key_columns(&self) -> Vec<&ColumnDefinition> {
+        let mut result: Vec<&ColumnDefinition> =
+            self.fields.values().filter(|x| x.is_key()).collect();
+        result.sort_by_key(|x| x.order);
+
+        result
+    }
+
+    /// Return the column holding the version of the rows, if the table has one.
+    pub fn version_column(&self) -> Option<&ColumnDefinition> {
+        self.fields.values().find(|x| x.is_version())
+    }
+
+    /// Return the column marking deleted rows, if the table soft deletes its rows.
+    pub fn soft_delete_column(&self) -> Option<&ColumnDefinition> {
+        self.soft_delete.as_ref().and_then(|x| self.fields.get(x))
+    }
+
+    /// Returns true, if the column is the soft delete column of the table.
+    pub fn is_soft_delete(&self, column: &ColumnDefinition) -> bool {
+        self.soft_delete_column()
+            .is_some_and(|x| x.sql_name == column.sql_name)
+    }
+
+    /// Return all columns, ordered as the fields of the struct.
+    pub fn columns(&self) -> Vec<&ColumnDefinition> {
+        let mut result: Vec<&ColumnDefinition> = self.fields.values().collect();
+        result.sort_by_key(|x| x.order);
+
+        result
+    }
+
+    /// Return the names and types of all sql columns of this table, ordered as the fields of the struct.
+    /// Decomposed fields add one column per component, collections stored in child tables add none.
+    pub fn sql_columns(&self) -> Vec<(String, SqlType)> {
+        self.columns()
+            .iter()
+            .flat_map(|x| x.sql_columns())
+            .collect()
+    }
+
+    /// Return all child tables holding the elements of collection fields.
+    pub fn child_tables(&self) -> Vec<&ChildTable> {
+        self.columns()
+            .iter()
+            .filter_map(|x| x.child_table())
+            .collect()
+    }
+
     /// Return the column defintion with the given name.
     pub fn get(&self, column: &str) -> Option<&ColumnDefinition> {
         if let Some(table) = self.fields.get(column) {
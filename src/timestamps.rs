@@ -0,0 +1,183 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::Resource;
+
+use crate::prelude::{Row, SqlValue, TableDefinition};
+
+/// The source of the time written to timestamp columns, in seconds since the unix epoch.
+/// Replace the resource to control the time, e.g. with `Clock::fixed` in tests.
+#[derive(Resource, Clone)]
+pub struct Clock {
+    now: Arc<dyn Fn() -> i64 + Send + Sync>,
+}
+
+impl Clock {
+    /// Create a clock reading the time from the function.
+    pub fn new(now: impl Fn() -> i64 + Send + Sync + 'static) -> Self {
+        Clock { now: Arc::new(now) }
+    }
+
+    /// The clock of the system.
+    pub fn system() -> Self {
+        Clock::new(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs() as i64)
+                .unwrap_or_default()
+        })
+    }
+
+    /// A clock standing still at the given time.
+    pub fn fixed(seconds: i64) -> Self {
+        Clock::new(move || seconds)
+    }
+
+    /// Return the current time.
+    pub fn now(&self) -> i64 {
+        (self.now)()
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::system()
+    }
+}
+
+/// Set the timestamp columns of a row about to be inserted to the time.
+pub(crate) fn stamp_insert(table: &TableDefinition, row: &mut Row, now: i64) {
+    for column in table.columns() {
+        if column.is_created_at() || column.is_updated_at() {
+            row.push(&column.sql_name, SqlValue::DateTime(now));
+        }
+    }
+}
+
+/// Set the time of update of a row about to be updated, keeping its time of creation.
+pub(crate) fn stamp_update(table: &TableDefinition, row: &mut Row, now: i64) {
+    for column in table.columns() {
+        if column.is_created_at() {
+            row.columns.retain(|x| x.0 != column.sql_name);
+        } else if column.is_updated_at() {
+            row.push(&column.sql_name, SqlValue::DateTime(now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    };
+
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Saves"))]
+    struct Save {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub level: i32,
+        #[reflect(@CreatedAt)]
+        pub created_at: i64,
+        #[reflect(@UpdatedAt)]
+        pub updated_at: Option<i64>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct TextTimestamp {
+        #[reflect(@Key)]
+        pub id: i64,
+        #[reflect(@CreatedAt)]
+        pub created_at: String,
+    }
+
+    #[derive(Resource)]
+    struct Now(Arc<AtomicI64>);
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(matches!(
+            registry.try_register_type::<TextTimestamp>(&app_registry),
+            Err(ErmError::InvalidMapping(_))
+        ));
+        assert!(registry.register_type::<Save>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+
+        let table = registry.get_table_definition("Saves").unwrap();
+        assert_eq!(
+            table.get("created_at").unwrap().sql_type,
+            SqlType::DateTime(true)
+        );
+        assert_eq!(
+            table.get("updated_at").unwrap().sql_type,
+            SqlType::DateTime(false)
+        );
+    }
+
+    fn stamping(saves: Repository<Save>, snapshots: Res<Snapshots>, now: Res<Now>) {
+        let save = Save {
+            id: 1,
+            level: 1,
+            ..default()
+        };
+        saves.insert(&save).unwrap();
+
+        let mut loaded = saves.find(1i64).unwrap().unwrap();
+        assert_eq!((loaded.created_at, loaded.updated_at), (100, Some(100)));
+
+        // Updates keep the time of creation, even if the value holds another one.
+        now.0.store(200, Ordering::Relaxed);
+        loaded.level = 2;
+        loaded.created_at = 0;
+        assert!(saves.update(&loaded).unwrap());
+        let loaded = saves.find(1i64).unwrap().unwrap();
+        assert_eq!((loaded.created_at, loaded.updated_at), (100, Some(200)));
+
+        // Values without changes are not written.
+        now.0.store(300, Ordering::Relaxed);
+        assert!(saves.update(&loaded).unwrap());
+        assert_eq!(saves.find(1i64).unwrap(), Some(loaded.clone()));
+
+        snapshots.clear();
+        saves.save(&loaded).unwrap();
+        let loaded = saves.find(1i64).unwrap().unwrap();
+        assert_eq!((loaded.created_at, loaded.updated_at), (100, Some(300)));
+
+        let query = ErmQuery::<Save>::new().filter(col("updated_at").gt(250i64));
+        assert_eq!(saves.query(query).unwrap(), vec![loaded]);
+    }
+
+    #[test]
+    fn timestamps() {
+        let now = Arc::new(AtomicI64::new(100));
+        let clock = now.clone();
+
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default());
+        app.insert_resource(Clock::new(move || clock.load(Ordering::Relaxed)));
+        app.insert_resource(Now(now));
+        app.register_type::<Save>();
+        app.register_type::<TextTimestamp>();
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, stamping);
+
+        app.update();
+    }
+
+    #[test]
+    fn clocks() {
+        assert_eq!(Clock::fixed(42).now(), 42);
+        assert!(Clock::system().now() > 1_600_000_000);
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+};
+
+use bevy::{
+    prelude::Resource,
+    reflect::{PartialReflect, Reflect},
+};
+
+use crate::{
+    graph::Changes,
+    identity::Identity,
+    prelude::{ColumnDefinition, ErmResult, SqlValue, TableDefinition},
+    row_mapping::field_of,
+};
+
+/// The number of snapshots kept by default.
+pub const DEFAULT_SNAPSHOT_CAPACITY: usize = 10_000;
+
+/// Copies of the values read or written through repositories, as their rows were stored then.
+/// Updates compare values with their snapshot and write the changed columns only.
+/// Snapshots are kept, until the row is written again, the snapshots are cleared or the
+/// capacity is exceeded. Then the snapshots taken first are evicted, their values write
+/// all columns on their next update.
+#[derive(Resource)]
+pub struct Snapshots {
+    entries: Mutex<Entries>,
+}
+
+/// The snapshots, each with the generation it was taken in, and the order they were taken in.
+/// The order may name snapshots which were replaced or removed since, these are skipped.
+#[derive(Default)]
+struct Entries {
+    snapshots: HashMap<Identity, (u64, Box<dyn PartialReflect>)>,
+    order: VecDeque<(Identity, u64)>,
+    generation: u64,
+    capacity: usize,
+}
+
+impl Entries {
+    /// Drop the oldest snapshots exceeding the capacity.
+    fn evict(&mut self) {
+        while self.snapshots.len() > self.capacity {
+            let Some((identity, generation)) = self.order.pop_front() else {
+                break;
+            };
+
+            if self
+                .snapshots
+                .get(&identity)
+                .is_some_and(|x| x.0 == generation)
+            {
+                self.snapshots.remove(&identity);
+            }
+        }
+
+        // Forget replaced snapshots, so the order does not outgrow the snapshots.
+        if self.order.len() > 2 * self.snapshots.len() + 16 {
+            let snapshots = &self.snapshots;
+            self.order.retain(|(identity, generation)| {
+                snapshots.get(identity).is_some_and(|x| x.0 == *generation)
+            });
+        }
+    }
+}
+
+impl Default for Snapshots {
+    fn default() -> Self {
+        Snapshots::with_capacity(DEFAULT_SNAPSHOT_CAPACITY)
+    }
+}
+
+impl Snapshots {
+    pub fn new() -> Self {
+        Snapshots::default()
+    }
+
+    /// Keep at most the given number of snapshots.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Snapshots {
+            entries: Mutex::new(Entries {
+                capacity,
+                ..Entries::default()
+            }),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        // The entries stay consistent, even if a thread panicked holding the lock.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Return a copy of the snapshot of the row of T with the given key.
+    pub fn get<T: Reflect>(&self, key: &[SqlValue]) -> Option<Box<dyn PartialReflect>> {
+        self.entries()
+            .snapshots
+            .get(&Identity(TypeId::of::<T>(), key.to_vec()))
+            .map(|x| x.1.clone_value())
+    }
+
+    /// Take a snapshot of the value stored in the row of T with the given key.
+    /// The oldest snapshots are evicted, if the capacity is exceeded.
+    pub fn insert<T: Reflect>(&self, key: Vec<SqlValue>, value: &T) {
+        let mut entries = self.entries();
+        entries.generation += 1;
+        let generation = entries.generation;
+        let identity = Identity(TypeId::of::<T>(), key);
+
+        entries.order.push_back((identity.clone(), generation));
+        entries
+            .snapshots
+            .insert(identity, (generation, value.clone_value()));
+        entries.evict();
+    }
+
+    /// Drop the snapshot of the row of T with the given key.
+    pub fn remove<T: Reflect>(&self, key: &[SqlValue]) {
+        self.entries()
+            .snapshots
+            .remove(&Identity(TypeId::of::<T>(), key.to_vec()));
+    }
+
+    /// Drop the snapshots of all rows of T.
+    pub fn remove_all<T: Reflect>(&self) {
+        self.entries()
+            .snapshots
+            .retain(|x, _| x.0 != TypeId::of::<T>());
+    }
+
+    /// Drop the snapshots of the changed rows and of all rows of the types changed
+    /// along with them.
+    pub(crate) fn forget(&self, changes: &Changes) {
+        let mut entries = self.entries();
+        for identity in changes.rows.iter() {
+            entries.snapshots.remove(identity);
+        }
+
+        if !changes.types.is_empty() {
+            entries
+                .snapshots
+                .retain(|x, _| !changes.types.contains(&x.0));
+        }
+    }
+
+    /// Drop all snapshots, the next update of any value writes all of its columns.
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+        entries.snapshots.clear();
+        entries.order.clear();
+    }
+
+    /// The maximum number of snapshots kept.
+    pub fn capacity(&self) -> usize {
+        self.entries().capacity
+    }
+
+    /// Change the maximum number of snapshots kept, evicting the oldest snapshots
+    /// exceeding it.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut entries = self.entries();
+        entries.capacity = capacity;
+        entries.evict();
+    }
+
+    /// The number of rows with a snapshot.
+    pub fn len(&self) -> usize {
+        self.entries().snapshots.len()
+    }
+
+    /// Returns true, if there are no snapshots.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Return the columns whose fields differ between the value and its snapshot. Key columns
+/// and the versions, timestamps and deletion marks written by the ERM are left out.
+/// Fields which cannot be compared are considered changed.
+pub(crate) fn dirty_columns<'t>(
+    value: &dyn PartialReflect,
+    snapshot: &dyn PartialReflect,
+    table: &'t TableDefinition,
+) -> ErmResult<Vec<&'t ColumnDefinition>> {
+    let mut result = Vec::new();
+    for column in table.columns() {
+        let managed = column.is_version() || column.is_created_at() || column.is_updated_at();
+        if column.is_key() || managed || table.is_soft_delete(column) {
+            continue;
+        }
+
+        let field = field_of(value, table, column)?;
+        let before = field_of(snapshot, table, column)?;
+        // Snapshots are dynamic values, which compare by structure.
+        if before.reflect_partial_eq(field) != Some(true) {
+            result.push(column);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Reflect, Default, Debug, PartialEq, Clone)]
+    #[reflect(Default, @TableName::new("Players"))]
+    struct Player {
+        #[reflect(@Key)]
+        pub id: i64,
+        pub name: String,
+        pub score: Option<f32>,
+        pub position: Vec3,
+        pub titles: Vec<String>,
+    }
+
+    fn startup(
+        app_registry: Res<AppTypeRegistry>,
+        mut registry: ResMut<ErmTypesRegistry>,
+        database: Res<ErmDatabase>,
+    ) {
+        assert!(registry.register_type::<Player>(&app_registry).is_some());
+        database.create_schema(&registry).unwrap();
+    }
+
+    /// A memory database logging the columns of updated rows and the replaced child tables.
+    struct Logged {
+        inner: MemoryDatabase,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ErmBackend for Logged {
+        fn dialect(&self) -> SqlDialect {
+            self.inner.dialect()
+        }
+
+        fn create_schema(&self, registry: &ErmTypesRegistry) -> ErmResult<()> {
+            self.inner.create_schema(registry)
+        }
+
+        fn insert(&self, table: &TableDefinition, row: &Row) -> ErmResult<()> {
+            self.inner.insert(table, row)
+        }
+
+        fn update_if(
+            &self,
+            table: &TableDefinition,
+            row: &Row,
+            filter: &Filter,
+        ) -> ErmResult<bool> {
+            self.log.lock().unwrap().push(row.names().join(", "));
+            self.inner.update_if(table, row, filter)
+        }
+
+        fn delete(&self, table: &TableDefinition, key: &Row) -> ErmResult<bool> {
+            self.inner.delete(table, key)
+        }
+
+        fn query(&self, table: &TableDefinition, select: &Select) -> ErmResult<Vec<Row>> {
+            self.inner.query(table, select)
+        }
+
+        fn query_sql(&self, sql: &str, params: &[SqlValue]) -> ErmResult<Vec<Row>> {
+            self.inner.query_sql(sql, params)
+        }
+
+        fn replace_children(
+            &self,
+            child: &ChildTable,
+            owner: &SqlValue,
+            rows: &[Row],
+        ) -> ErmResult<()> {
+            self.log.lock().unwrap().push(child.sql_name.clone());
+            self.inner.replace_children(child, owner, rows)
+        }
+
+        fn select_children(&self, child: &ChildTable, owners: &[SqlValue]) -> ErmResult<Vec<Row>> {
+            self.inner.select_children(child, owners)
+        }
+
+        fn begin(&self) -> ErmResult<()> {
+            self.inner.begin()
+        }
+
+        fn commit(&self) -> ErmResult<()> {
+            self.inner.commit()
+        }
+
+        fn rollback(&self) -> ErmResult<()> {
+            self.inner.rollback()
+        }
+    }
+
+    #[derive(Resource)]
+    struct Log(Arc<Mutex<Vec<String>>>);
+
+    impl Log {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    fn tracking(
+        players: Repository<Player>,
+        database: Res<ErmDatabase>,
+        snapshots: Res<Snapshots>,
+        log: Res<Log>,
+    ) {
+        let player = Player {
+            id: 1,
+            name: "Ann".to_owned(),
+            titles: vec!["First".to_owned()],
+            ..default()
+        };
+        players.insert(&player).unwrap();
+        log.take();
+
+        // Unchanged values are not written.
+        let mut loaded = players.find(1i64).unwrap().unwrap();
+        assert_eq!(players.dirty_columns(&loaded).unwrap(), Some(vec![]));
+        assert!(players.update(&loaded).unwrap());
+        assert!(log.take().is_empty());
+
+        // Only the changed columns are written, child tables only if their elements changed.
+        loaded.score = Some(2.5);
+        loaded.position.y = 1.0;
+        assert_eq!(
+            players.dirty_columns(&loaded).unwrap(),
+            Some(vec!["score".to_owned(), "position".to_owned()])
+        );
+        assert!(players.update(&loaded).unwrap());
+        assert_eq!(log.take(), vec!["id, score, position"]);
+
+        loaded.titles.push("Second".to_owned());
+        assert!(players.update(&loaded).unwrap());
+        assert_eq!(log.take(), vec!["id", "Players_titles"]);
+        assert_eq!(players.find(1i64).unwrap(), Some(loaded.clone()));
+
+        // Saving or deleting other rows keeps the snapshot.
+        players.save(&Player { id: 2, ..default() }).unwrap();
+        assert!(players.delete_by_key(2i64).unwrap());
+        assert_eq!(players.dirty_columns(&loaded).unwrap(), Some(vec![]));
+        log.take();
+
+        // Values without a snapshot write all columns.
+        snapshots.clear();
+        assert_eq!(players.dirty_columns(&loaded).unwrap(), None);
+        assert!(players.update(&loaded).unwrap());
+        assert_eq!(
+            log.take(),
+            vec!["id, name, score, position", "Players_titles"]
+        );
+
+        // Rows which were deleted in the meantime are not updated, even if nothing changed.
+        players.find(1i64).unwrap();
+        let key = Row::new().with("id", 1i64);
+        assert!(database.delete(players.table().unwrap(), &key).unwrap());
+        assert!(!players.update(&loaded).unwrap());
+        loaded.name = "Anne".to_owned();
+        assert!(!players.update(&loaded).unwrap());
+    }
+
+    #[test]
+    fn eviction() {
+        let snapshots = Snapshots::with_capacity(2);
+        for id in 1..=3i64 {
+            snapshots.insert(vec![id.into()], &Player { id, ..default() });
+        }
+
+        // The snapshot taken first is evicted, replacing a snapshot renews it.
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots.get::<Player>(&[1i64.into()]).is_none());
+        snapshots.insert(vec![2i64.into()], &Player::default());
+        snapshots.insert(vec![4i64.into()], &Player::default());
+        assert!(snapshots.get::<Player>(&[2i64.into()]).is_some());
+        assert!(snapshots.get::<Player>(&[3i64.into()]).is_none());
+
+        snapshots.set_capacity(1);
+        assert_eq!(snapshots.len(), 1);
+        assert!(snapshots.get::<Player>(&[4i64.into()]).is_some());
+
+        snapshots.remove_all::<Player>();
+        assert!(snapshots.is_empty());
+    }
+
+    #[test]
+    fn dirty_tracking() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let backend_log = log.clone();
+
+        let mut app = App::new();
+        app.insert_resource(AppTypeRegistry::default());
+        app.add_plugins(BevyERMPlugin::default().with_backend(move || {
+            Ok(Logged {
+                inner: MemoryDatabase::new(),
+                log: backend_log.clone(),
+            })
+        }));
+        app.insert_resource(Log(log));
+        app.register_type::<Player>();
+        app.add_systems(Startup, startup);
+        app.add_systems(PostStartup, tracking);
+
+        app.update();
+    }
+}